@@ -421,9 +421,27 @@ impl<'a, M: Clone + 'static, T: ValidTheme + 'a> MarkWidget<'a, M, T> {
             .iter()
             .find(|attr| attr.name.local.to_string().as_str() == "href")
         {
-            let url = attr.value.to_string();
+            let mut url = attr.value.to_string();
+            let mut link_col = link_col;
             let children_empty = { node.children.borrow().is_empty() };
-            let msg = self.fn_clicking_link.as_ref();
+            let mut msg = self.fn_clicking_link.as_ref();
+
+            if get_attr(attrs, "data-wikilink").is_some() {
+                // comrak percent-encodes the target of wiki links
+                url = percent_decode(&url);
+
+                if self
+                    .fn_resolving_wikilink
+                    .as_ref()
+                    .is_some_and(|resolve| !resolve(&url))
+                {
+                    link_col = self
+                        .style
+                        .and_then(|n| n.broken_link_color)
+                        .unwrap_or_else(|| cosmic::iced::Color::from_rgb8(0xC0, 0x39, 0x2B));
+                }
+                msg = self.fn_clicking_wikilink.as_ref().or(msg);
+            }
 
             if children_empty {
                 RenderedSpan::Spans(vec![
@@ -647,6 +665,33 @@ fn get_attr<'a>(attrs: &'a [html5ever::Attribute], attr_name: &str) -> Option<&'
         .map(|n| &*n.value)
 }
 
+/// Decodes `%XX` escapes, invalid ones are kept as they are
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let decoded = match bytes[i] {
+            b'%' => bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+
+        if let Some(byte) = decoded {
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
 fn is_node_useless(node: &Node) -> bool {
     if let markup5ever_rcdom::NodeData::Text { contents } = &node.data {
         let contents = contents.borrow();
@@ -97,6 +97,7 @@ impl MarkState {
                     subscript: true,
                     underline: true,
                     table: true,
+                    wikilinks_title_after_pipe: true,
                     ..Default::default()
                 },
                 parse: comrak::options::Parse::default(),
@@ -86,6 +86,7 @@ pub enum UpdateMsgKind {
 type FClickLink<M> = Box<dyn Fn(String) -> M>;
 type FCopyCode<M> = Arc<dyn Fn(String) -> M>;
 type FDrawImage<'a, M, T> = Box<dyn Fn(ImageInfo) -> Element<'static, M, T> + 'a>;
+type FResolveWikilink<'a> = Box<dyn Fn(&str) -> bool + 'a>;
 type FUpdate<M> = Arc<dyn Fn(UpdateMsg) -> M>;
 pub(crate) type FStyleLinkButton<T> =
     Arc<dyn Fn(&T, widget::button::Status) -> widget::button::Style + 'static>;
@@ -119,6 +120,8 @@ pub struct MarkWidget<'a, Message, Theme = cosmic::iced::Theme> {
     pub(crate) heading_scale: f32,
 
     pub(crate) fn_clicking_link: Option<FClickLink<Message>>,
+    pub(crate) fn_clicking_wikilink: Option<FClickLink<Message>>,
+    pub(crate) fn_resolving_wikilink: Option<FResolveWikilink<'a>>,
     pub(crate) fn_copying_code: Option<FCopyCode<Message>>,
     pub(crate) fn_drawing_image: Option<FDrawImage<'a, Message, Theme>>,
     pub(crate) fn_update: Option<FUpdate<Message>>,
@@ -145,6 +148,8 @@ impl<'a, M: 'a, T: 'a> MarkWidget<'a, M, T> {
             font: Font::DEFAULT,
             font_mono: Font::MONOSPACE,
             fn_clicking_link: None,
+            fn_clicking_wikilink: None,
+            fn_resolving_wikilink: None,
             fn_copying_code: None,
             fn_drawing_image: None,
             fn_update: None,
@@ -228,6 +233,26 @@ impl<'a, M: 'a, T: 'a> MarkWidget<'a, M, T> {
         self
     }
 
+    /// When clicking a wiki-style link (`[[Note]]` or `[[Note|label]]`),
+    /// send a message with the link target (`Note`) to handle it.
+    ///
+    /// If this isn't set, wiki links are handled by [`Self::on_clicking_link`].
+    #[must_use]
+    pub fn on_clicking_wikilink(mut self, f: impl Fn(String) -> M + 'static) -> Self {
+        self.fn_clicking_wikilink = Some(Box::new(f));
+        self
+    }
+
+    /// Tells if the target of a wiki-style link exists.
+    ///
+    /// Unresolved links are drawn with [`crate::Style::broken_link_color`]
+    /// so broken references are easy to spot.
+    #[must_use]
+    pub fn on_resolving_wikilink(mut self, f: impl Fn(&str) -> bool + 'a) -> Self {
+        self.fn_resolving_wikilink = Some(Box::new(f));
+        self
+    }
+
     /// When the user clicks the copy button on a codeblock.
     #[must_use]
     pub fn on_copying_code(mut self, f: impl Fn(String) -> M + 'static) -> Self {
@@ -13,6 +13,10 @@ pub struct Style {
     ///
     /// Default: `#5A6B9E`
     pub link_color: Option<cosmic::iced::Color>,
+    /// Color of wiki link **text** when the linked note doesn't exist.
+    ///
+    /// Default: `#C0392B`
+    pub broken_link_color: Option<cosmic::iced::Color>,
     /// Background color for text highlights (`<mark>` element).
     ///
     /// Default: `#F7D84B`
@@ -7,6 +7,10 @@ rename = Rename
 move = Move
 move-to = Move To
 open-file-manager = Reveal in File Manager
+duplicate = Duplicate
+copy-path = Copy Path
+copy-relative-link = Copy Relative Link
+copy-wikilink = Copy Wiki Link
 name = Name
 copied-to-clipboard = Copied to Clipboard
 
@@ -19,9 +23,11 @@ donations = Donate
 editor = Editor
 preview = Preview
 file-name = File Name
+template = Template
+empty-note = Empty Note
 folder-name = Folder Name
 delete-node = Delete File/Folder
-delete-confirmation = Are you sure you want to delete this file/folder and it's contents?
+delete-confirmation = This file/folder and its contents will be moved to the trash of the vault.
 save-changes-closing = Save changes before closing?
 save-warning = You have unsaved changes. If you continue without saving, these changes will be lost.
 discard-changes = Discard Changes
@@ -29,6 +35,33 @@ file-changed-externally = The file has been changed externally
 reload = Reload File
 keep-my-version = Keep Current Version
 modified-text = was modified by another program.
+file-removed-externally = The file has been deleted externally
+removed-text = was deleted or moved by another program. Its content is only kept in the editor.
+save-as = Save As…
+close-file = Close File
+keep-editing = Keep Editing
+deleted-on-disk = Deleted on disk
+deleted-tab-title = {$name} (deleted)
+open-file-moved = "{$name}" was moved to {$path} by another program
+merge-changes = Merge External Changes
+merge-changes-text = "{$file}" was modified by another program and {$conflicts ->
+    [one] one change conflicts
+    *[other] {$conflicts} changes conflict
+} with your unsaved changes. The rest of the changes are merged automatically.
+apply-merge = Apply Merge
+conflict-number = Conflict {$number}
+your-version = Your version
+disk-version = Version on disk
+merge-removed = (removed)
+keep-mine = Keep Mine
+keep-disk = Keep Disk
+keep-both = Keep Both
+edit-manually = Edit Manually
+merge-marker-mine = your version
+merge-marker-disk = version on disk
+external-changes-merged = Changes made by another program were merged with your unsaved changes
+file-reloaded = File reloaded, it was modified by another program
+note-not-found = No note named "{$name}" in the vault
 
 <#-- Appearance -->
 appearance = Appearance
@@ -51,6 +84,19 @@ last-file = Open the last file when the app starts
 yes = Yes
 no = No
 scrollbar-sync = Sync Preview and Editor scrollbars
+autosave = Save files automatically
+autosave-off = Off
+autosave-idle = After a pause in typing
+autosave-focus-lost = When the window loses focus
+autosave-switch-file = When switching files
+autosave-delay = Pause before saving
+persistent-undo = Keep the undo history after closing files
+undo-max-days = Keep the undo history for
+undo-max-size = Max undo history size per file
+days = {$days} days
+saving = Saving...
+saved = Saved
+unsaved-changes = Unsaved changes
 pdf-exporting = PDF Exporting
 gotenberg-url = Set the Gotenberg server URL
 apply = Apply
@@ -62,6 +108,25 @@ flatpak-permissions-note = Note, this also affects image loading. Local images w
 selected-font = Selected Font
 default-font = Default Font
 font-selection-info = Some fonts will not work, and the app will automatically fall back to the default font (without changing the selected font in the settings). That is not a bug; it is the intended behavior.
+vaults = Vaults
+vault-name = Vault Name
+open-vault = Open
+remove = Remove
+add-vault = Add Existing Folder as Vault
+default-vault-name = Cedilla Vault
+vault-not-found = The vault folder {$path} does not exist
+switch-vault-warning = Switching vaults closes all open tabs. If you continue without saving, the unsaved changes of every tab will be lost.
+vault-files = Vault Files
+vault-files-info = Comma separated, exclude patterns use the .gitignore syntax
+note-extensions = Note file extensions
+exclude-patterns = Excluded files and folders
+respect-gitignore = Hide what .gitignore files ignore
+show-attachments = Show attachments
+show-attachments-info = Files that are not notes, they are opened with the default app
+invalid-exclude-pattern = Invalid exclude pattern: {$error}
+vault-config-active = Some settings are set by the .cedilla/config file of this vault and override the ones above
+vault-config-error = Invalid vault settings (.cedilla/config): {$error}
+vault-config-out-of-range = {$key} is {$value} but must be between {$min} and {$max}
 
 <#-- Application MenuBar -->
 file = File
@@ -69,6 +134,7 @@ new-vault-file = New Vault File
 new-folder = New Folder
 open-file = Open File
 save-file = Save File
+close-tab = Close Tab
 new-file = New File
 
 edit = Edit
@@ -76,6 +142,8 @@ undo = Undo
 redo = Redo
 
 view = View
+next-tab = Next Tab
+previous-tab = Previous Tab
 about = About
 settings = Settings
 
@@ -94,3 +162,92 @@ horizontal-rule = Horizontal Rule
 <#-- Search -->
 search = Search
 no-results = No Results
+find-in-vault = Find in Vault
+searching = Searching...
+vault-search-results = {$matches} matches in {$files} files
+replace = Replace
+replace-in-vault = Replace in Vault
+vault-replace-summary = {$replacements} replacements in {$files} files
+replaced-in-files = Replaced in {$files} files
+replace-all = Replace All
+replaced-matches = Replaced {$count} matches
+no-selection = Select some text to search inside of it
+
+<#-- Links -->
+backlinks = Backlinks
+backlinks-no-file = Save the file in the vault to see its backlinks
+no-backlinks = No notes link to this file
+backlinks-results = {$links} links in {$files} files
+update-links = Update Links
+update-links-text = Renaming/moving this broke {$links} links in {$files} files, do you want to update them?
+skip = Skip
+links-updated = Updated {$links} links in {$files} files
+
+<#-- Recovery -->
+recover-documents = Recover Unsaved Documents
+recover-documents-text = Cedilla was not closed correctly, these documents had unsaved changes:
+restore = Restore
+restore-all = Restore All
+discard = Discard
+later = Later
+
+<#-- File History -->
+file-history = File History
+file-history-no-file = Save the file in the vault to see its history
+no-snapshots = No snapshots yet, one is taken on every save
+snapshots-count = {$count} snapshots
+line-count = {$count} lines
+snapshot = Snapshot
+current-version = Current
+snapshot-restored = Restored the snapshot from {$age}
+just-now = just now
+minutes-ago = {$minutes} min ago
+hours-ago = {$hours} h ago
+days-ago = {$days} days ago
+
+<#-- Git -->
+git = Git
+not-a-git-repository = The vault is not a git repository
+no-changes = No changes
+changed-files = {$count} changed files
+git-modified = Modified
+git-new = New
+git-untracked = Untracked
+staged = Staged
+stage = Stage
+unstage = Unstage
+stage-all = Stage All
+commit-message = Commit message
+commit = Commit
+committed = Committed {$summary}
+file-log = History of {$file}
+file-log-no-file = Save the file in the vault to see its history
+no-commits = No commits yet
+
+<#-- Trash -->
+trash = Trash
+trash-empty = The trash is empty
+trash-count = {$count} deleted items
+moved-to-trash = Moved {$name} to the trash
+restored-from-trash = Restored {$name}
+deleted-from = Deleted {$age} from {$path}
+delete-forever = Delete Forever
+empty-trash = Empty Trash
+empty-trash-confirmation = Every item of the trash will be deleted forever, this can't be undone.
+
+<#-- Bulk Actions -->
+bulk-action = {$action} ({$count} Items)
+export-pdf = Export to PDF
+delete-nodes = Delete {$count} Items
+delete-nodes-confirmation = These files/folders and their contents will be moved to the trash of the vault:
+move-nodes = Move {$count} Items To
+bulk-failures = {$count} Items Failed
+moved-items-to-trash = Moved {$count} items to the trash
+restored-items-from-trash = Restored {$count} items
+moved-items = Moved {$count} items to {$folder}
+exported-notes = Exported {$count} notes to PDF
+no-notes-to-export = There are no notes to export
+gotenberg-not-configured = Configure the Gotenberg URL in the settings to export to PDF
+ok = OK
+move-into-itself = A folder can't be moved inside of itself
+already-exists = There is already a file/folder with that name in the target folder
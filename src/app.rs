@@ -3,12 +3,25 @@
 use crate::app::app_menu::MenuAction;
 use crate::app::context_page::ContextPage;
 use crate::app::core::editor::EditorState;
+use crate::app::core::history::{self, StoredHistory};
 use crate::app::core::preview::MarkdownPreview;
-use crate::app::core::project::ProjectNode;
-use crate::app::core::utils::search::SearchAction;
+use crate::app::core::project::{NavDrag, NavDragAction, ProjectNode};
+use crate::app::core::utils::files::VaultFilter;
+use crate::app::core::utils::git::{FileChange, GitAction, GitState};
+use crate::app::core::utils::links::{self, BacklinksAction, BacklinksState, LinkRewrite};
+use crate::app::core::utils::merge::MergeState;
+use crate::app::core::utils::recovery::{self, RecoveredDocument};
+use crate::app::core::utils::search::{
+    DiffChunk, SearchAction, VaultSearchAction, VaultSearchState,
+};
+use crate::app::core::utils::snapshots::{self, FileHistoryAction, FileHistoryState};
+use crate::app::core::utils::trash::{TrashAction, TrashState};
 use crate::app::core::utils::{self, CedillaToast, Image};
 use crate::app::dialogs::{DialogPage, DialogState};
-use crate::config::{AppTheme, BoolState, CONFIG_VERSION, CedillaConfig, ConfigInput, ShowState};
+use crate::config::{
+    AppTheme, AutosaveMode, BoolState, CONFIG_VERSION, CedillaConfig, ConfigInput, Session,
+    ShowState, VaultConfig,
+};
 use crate::key_binds::key_binds;
 use crate::{fl, icons};
 use cosmic::app::context_drawer;
@@ -17,7 +30,7 @@ use cosmic::cosmic_theme::{self, ThemeMode};
 use cosmic::iced::core::keyboard::{Key, Modifiers};
 use cosmic::iced::core::window;
 use cosmic::iced::widget::{center, column, row, scrollable, tooltip};
-use cosmic::iced::{Alignment, Event, Font, Length, Padding, Subscription, highlighter};
+use cosmic::iced::{Alignment, Color, Event, Font, Length, Padding, Subscription, highlighter};
 use cosmic::widget::space::horizontal;
 use cosmic::widget::{self, about::About, menu};
 use cosmic::widget::{
@@ -28,9 +41,11 @@ use cosmic::{cosmic_config, prelude::*, surface, theme};
 use frostmark::{MarkWidget, UpdateMsg};
 use slotmap::Key as SlotMapKey;
 use std::any::TypeId;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
+use widgets::text_editor::LineChange;
 use widgets::{TextEditor, text_editor};
 
 pub mod app_menu;
@@ -40,6 +55,8 @@ mod dialogs;
 mod update;
 
 const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
+/// Left padding of the editor where the git gutter markers are drawn
+const GUTTER_WIDTH: f32 = 8.0;
 
 /// The application model stores app-specific state used to describe its interface and
 /// drive its logic.
@@ -78,8 +95,48 @@ pub struct AppModel {
     highlighter_themes: Vec<String>,
     /// Currently selected path on the navbar (i need these for accurate file creadtion deletion...)
     selected_nav_path: Option<PathBuf>,
+    /// Nodes of the navbar selected with Ctrl/Shift + click, bulk actions of the context menu act on them
+    nav_selection: Vec<PathBuf>,
+    /// Last node clicked on the navbar, where the range of a Shift + click starts
+    nav_selection_anchor: Option<PathBuf>,
+    /// Position of the pointer over the navbar (relative to its first node), if it's there
+    nav_cursor: Option<cosmic::iced::Point>,
+    /// Node of the navbar being dragged onto a folder
+    nav_drag: Option<NavDrag>,
     /// Gotenberg client, needed for Pdf exporting
     gotenberg_client: gotenberg_pdf::Client,
+    /// Holds the state of the vault wide search
+    vault_search: VaultSearchState,
+    /// Every note of the vault, used to resolve links between notes
+    vault_notes: Vec<PathBuf>,
+    /// Holds the state of the backlinks panel
+    backlinks: BacklinksState,
+    /// Holds the state of the file history panel
+    file_history: FileHistoryState,
+    /// Holds the state of the trash panel
+    trash: TrashState,
+    /// Holds the state of the git integration (when the vault is a git working tree)
+    git: GitState,
+    /// Settings stored in the current vault, they override the ones of the app
+    vault_config: VaultConfig,
+    /// Values of the app settings while they are overridden by the [`VaultConfig`]
+    replaced_config: VaultConfig,
+    /// Which files and folders of the vault are shown, and which files are notes
+    vault_filter: VaultFilter,
+    /// Canonical path of the open vault, the one its watcher reports changes with
+    vault_watch_path: PathBuf,
+    /// State of the note extensions field of the settings (until it's saved)
+    note_extensions_input: String,
+    /// State of the exclude patterns field of the settings (until it's saved)
+    exclude_patterns_input: String,
+    /// Content hash of every document this instance has written to the recovery directory (by recovery key)
+    recovery_written: HashMap<String, u64>,
+    /// Id of the last document opened in a tab
+    last_document_id: u64,
+    /// Last time the open document was edited, pending an idle autosave
+    last_edit: Option<Instant>,
+    /// Tabs whose document is being autosaved
+    autosaving: HashSet<segmented_button::Entity>,
     /// Application State
     state: State,
 }
@@ -97,6 +154,8 @@ enum State {
         panes: pane_grid::State<PaneContent>,
         /// Controls if the preview is hidden or not
         preview_state: PreviewState,
+        /// Open documents, the inactive ones are stored as data of their tab
+        tabs: segmented_button::SingleSelectModel,
     },
 }
 
@@ -119,6 +178,19 @@ pub enum PreviewState {
 pub enum DiscardChangesAction {
     CloseApp,
     OpenFile(PathBuf),
+    CloseTab(segmented_button::Entity),
+    SwitchVault(usize),
+}
+
+/// Change of the files/folders of the vault detected by the vault watcher
+#[derive(Debug, Clone)]
+pub enum VaultChange {
+    /// Something was created, removed or modified at the given path
+    Changed(PathBuf),
+    /// A file or folder was renamed (from, to)
+    Renamed(PathBuf, PathBuf),
+    /// The settings file of the vault was created, modified or removed
+    ConfigChanged,
 }
 
 /// Messages emitted by the application and its widgets.
@@ -130,6 +202,8 @@ pub enum Message {
     AddToast(CedillaToast),
     /// Opens the given URL in the browser
     LaunchUrl(String),
+    /// Opens the vault note a wiki link (`[[Note]]`) points to
+    OpenWikilink(String),
     /// Copies some data to the system clipboard
     CopyToClipboard(String),
     /// Opens (or closes if already open) the given [`ContextPage`]
@@ -150,25 +224,69 @@ pub enum Message {
     NavBarContext(segmented_button::Entity),
     /// Fired when a menu item is chosen
     NavMenuAction(NavMenuAction),
+    /// Dragging nodes of the navbar onto folders
+    NavDrag(NavDragAction),
 
     /// Startup Message, checks config and set's the state as needed
     Startup(Option<PathBuf>),
     /// Creates a new empty file (no path)
     NewFile,
-    /// Creates a new markdown file in the vault
-    NewVaultFile(String),
+    /// Creates a new markdown file in the vault (from the given template, if any)
+    NewVaultFile(String, Option<PathBuf>),
+    /// Creates a new markdown file in the vault from the given template content, with the content of the clipboard
+    NewVaultFileFromTemplate(String, String, String),
     /// Creates a new folder in the vault
     NewVaultFolder(String),
     /// Save the current file
     SaveFile,
     /// Callback after opening a new file
     OpenFile(Result<(PathBuf, Arc<String>), anywho::Error>),
-    /// Callback after saving the current file
-    FileSaved(Option<Result<PathBuf, anywho::Error>>),
+    /// Callback after loading again an open file, its document is replaced (discarding its changes)
+    ReloadFile(Result<(PathBuf, Arc<String>), anywho::Error>),
+    /// Callback after loading the files of the last session (and their contents)
+    SessionLoaded(Session, Vec<Arc<String>>),
+    /// Callback after saving the file of the given tab (with the saved content)
+    FileSaved(
+        segmented_button::Entity,
+        String,
+        Option<Result<PathBuf, anywho::Error>>,
+    ),
     /// Callback after asking to close a file discarding changes
     DiscardChanges(DiscardChangesAction),
     /// Fired when the watcher detects an external change to the open file
     ExternalFileChanged(PathBuf),
+    /// Fired when the watcher detects that an open file was deleted (or moved somewhere unknown)
+    ExternalFileRemoved(PathBuf),
+    /// Fired when the vault watcher detects changes made by other programs (debounced)
+    VaultChanged(Vec<VaultChange>),
+    /// Callback after reading the new content of an open file changed externally
+    ExternalFileLoaded(PathBuf, Result<Arc<String>, anywho::Error>),
+    /// Applies the given merge (once every conflict is resolved) to the open file
+    ApplyMerge(MergeState),
+    /// Switches to the given tab
+    ActivateTab(segmented_button::Entity),
+    /// Closes the given tab (asking first if it has unsaved changes)
+    CloseTab(segmented_button::Entity),
+    /// Writes the documents with unsaved changes to the recovery directory
+    AutosaveRecovery,
+    /// Callback after looking for documents left in the recovery directory
+    RecoveryLoaded(Vec<RecoveredDocument>),
+    /// Opens the given recovered documents
+    RestoreDocuments(Vec<RecoveredDocument>),
+    /// Removes the recovery files of the recovered document with the given key
+    DiscardRecoveredDocument(String),
+    /// Checks if the user has stopped editing long enough to autosave
+    AutosaveTick,
+    /// Fired when the app window loses focus
+    WindowUnfocused,
+    /// Callback after loading the persisted undo history of a file
+    HistoryLoaded(StoredHistory),
+    /// Callback after autosaving the file of the given tab (with the saved content)
+    Autosaved(
+        segmented_button::Entity,
+        String,
+        Result<PathBuf, anywho::Error>,
+    ),
 
     /// Deletes the given node entity of the navbar folder or file
     DeleteNode(cosmic::widget::segmented_button::Entity),
@@ -176,10 +294,32 @@ pub enum Message {
     RenameNode(cosmic::widget::segmented_button::Entity, String),
     /// Move one node (to another one)
     MoveNode(cosmic::widget::segmented_button::Entity, PathBuf),
+    /// Callback after duplicating a node of the navbar, with the path of the copy
+    NodeDuplicated(Result<PathBuf, anywho::Error>),
+    /// Deletes the given files/folders selected on the navbar
+    DeleteNodes(Vec<PathBuf>),
+    /// Moves the given files/folders selected on the navbar to the given folder
+    MoveNodes(Vec<PathBuf>, PathBuf),
+    /// Ask where to export the notes of the given files/folders as PDF
+    ExportNodes(Vec<PathBuf>),
+    /// Callback after exporting notes, with how many were exported and the errors of the ones that failed
+    NodesExported(usize, Vec<String>),
     /// Ask to move the main vault to another path
     MoveVault,
     /// Callback after asking to move the vault
     VaultMoved(Result<PathBuf, anywho::Error>),
+    /// Switch to the vault at the given index of the list of vaults
+    SwitchVault(usize),
+    /// Ask to pick an existing folder to add as a vault
+    AddVault,
+    /// Callback after picking the folder of a new vault
+    VaultFolderPicked(PathBuf),
+    /// Callback after finding the links that a rename/move of a node broke
+    LinkRewritesPlanned(Vec<LinkRewrite>),
+    /// Rewrites the given links (after the user confirms it)
+    ApplyLinkRewrites(Vec<LinkRewrite>),
+    /// Callback after rewriting links, (rewrites of the open files, rewritten links, rewritten files)
+    LinkRewritesApplied(Result<(Vec<LinkRewrite>, usize, usize), anywho::Error>),
 
     /// Callback after some action is performed on the text editor
     Edit(text_editor::Action),
@@ -191,6 +331,16 @@ pub enum Message {
     Redo,
     /// Search related action requested
     Search(SearchAction),
+    /// Vault wide search related action requested
+    VaultSearch(VaultSearchAction),
+    /// Backlinks panel related action requested
+    Backlinks(BacklinksAction),
+    /// File history panel related action requested
+    FileHistory(FileHistoryAction),
+    /// Trash panel related action requested
+    Trash(TrashAction),
+    /// Git integration related action requested
+    Git(GitAction),
 
     /// Update the HTML renderer state
     UpdateMarkState(UpdateMsg),
@@ -223,9 +373,13 @@ pub enum NavMenuAction {
     DeleteNode(segmented_button::Entity),
     RenameNode(segmented_button::Entity),
     MoveNode(segmented_button::Entity),
+    DuplicateNode(segmented_button::Entity),
     OpenNodeFileManager(segmented_button::Entity),
+    CopyNodePath(segmented_button::Entity),
+    CopyNodeLink(segmented_button::Entity),
     OpenFolderCreationDialog(segmented_button::Entity),
     OpenVaultFileCreationDialog(segmented_button::Entity),
+    ExportNodes(segmented_button::Entity),
 }
 
 impl cosmic::widget::menu::Action for NavMenuAction {
@@ -288,6 +442,9 @@ impl cosmic::Application for AppModel {
             Font::DEFAULT
         };
 
+        // built again (with the settings of the vault) once the vault is loaded
+        let (vault_filter, _) = VaultFilter::new(&flags.config);
+
         let mut font_options = vec![fl!("default-font")];
         font_options.extend(flags.system_fonts.iter().cloned());
 
@@ -313,7 +470,27 @@ impl cosmic::Application for AppModel {
                 .map(|t| t.to_string())
                 .collect(),
             selected_nav_path: None,
+            nav_selection: Vec::new(),
+            nav_selection_anchor: None,
+            nav_cursor: None,
+            nav_drag: None,
             gotenberg_client: gotenberg_pdf::Client::new(&gotenberg_url),
+            vault_search: VaultSearchState::default(),
+            vault_notes: Vec::new(),
+            backlinks: BacklinksState::default(),
+            file_history: FileHistoryState::default(),
+            trash: TrashState::default(),
+            git: GitState::default(),
+            vault_config: VaultConfig::default(),
+            replaced_config: VaultConfig::default(),
+            vault_filter,
+            vault_watch_path: PathBuf::new(),
+            note_extensions_input: String::new(),
+            exclude_patterns_input: String::new(),
+            recovery_written: HashMap::new(),
+            last_document_id: 0,
+            last_edit: None,
+            autosaving: HashSet::new(),
             state: State::Loading,
         };
 
@@ -325,9 +502,7 @@ impl cosmic::Application for AppModel {
 
         // load vault
         let vault_path = PathBuf::from(&app.config.vault_path);
-        if vault_path.exists() && vault_path.is_dir() {
-            app.open_vault_folder(&vault_path);
-        } else {
+        if !(vault_path.exists() && vault_path.is_dir()) {
             eprintln!("Vault not found, trying to fallback to default path");
             let default_vault_path = PathBuf::from(CedillaConfig::default().vault_path);
 
@@ -342,14 +517,22 @@ impl cosmic::Application for AppModel {
             }
 
             app.config.vault_path = default_vault_path.to_string_lossy().to_string();
-            app.open_vault_folder(&default_vault_path);
         }
+        app.register_current_vault();
+        // the settings of the vault decide what the navbar shows
+        let vault_config_task = app.load_vault_config();
+        app.open_vault_folder(app.config.vault_path());
 
         // Startup tasks.
         let tasks = vec![
             app.update_title(),
             cosmic::command::set_theme(app.config.app_theme.theme()),
             Task::done(cosmic::action::app(Message::Startup(flags.open_with_file))),
+            Task::perform(recovery::load_recovery(), |documents| {
+                cosmic::action::app(Message::RecoveryLoaded(documents))
+            }),
+            Task::done(cosmic::action::app(Message::Git(GitAction::Refresh))),
+            vault_config_task,
         ];
 
         (app, Task::batch(tasks))
@@ -357,7 +540,21 @@ impl cosmic::Application for AppModel {
 
     /// Elements to pack at the start of the header bar.
     fn header_start(&self) -> Vec<Element<'_, Self::Message>> {
-        vec![app_menu::menu_bar(&self.key_binds)]
+        let mut elements = vec![app_menu::menu_bar(&self.key_binds)];
+
+        // the vault switcher is only useful with more than one vault
+        if self.config.vaults.len() > 1 {
+            elements.push(
+                widget::dropdown(
+                    &self.config.vaults,
+                    self.config.current_vault(),
+                    Message::SwitchVault,
+                )
+                .into(),
+            );
+        }
+
+        elements
     }
 
     /// Elements to pack at the end of the header bar.
@@ -407,15 +604,24 @@ impl cosmic::Application for AppModel {
             ..
         } = self.core().system_theme().cosmic().spacing;
 
-        let mut nav = segmented_button::vertical(nav_model)
-            .button_height(space_xxxs + 20 /* line height */ + space_xxxs)
+        let nav = segmented_button::vertical(nav_model)
+            .button_height(self.nav_button_height())
             .button_padding([space_s, space_xxxs, space_s, space_xxxs])
             .button_spacing(space_xxxs)
             .on_activate(|entity| cosmic::action::cosmic(cosmic::app::Action::NavBar(entity)))
             .on_context(|entity| cosmic::Action::App(Message::NavBarContext(entity)))
             .context_menu(self.nav_context_menu())
             .spacing(space_none)
-            .style(theme::SegmentedButton::FileNav)
+            .style(theme::SegmentedButton::FileNav);
+
+        // the pointer is tracked over the nodes to drag them onto folders
+        let mut nav = cosmic::iced::widget::mouse_area(nav)
+            .on_move(|position| {
+                cosmic::Action::App(Message::NavDrag(NavDragAction::CursorMoved(position)))
+            })
+            .on_exit(cosmic::Action::App(Message::NavDrag(
+                NavDragAction::CursorLeft,
+            )))
             .apply(widget::container)
             .padding(space_s)
             .width(Length::Shrink);
@@ -467,11 +673,26 @@ impl cosmic::Application for AppModel {
                                 None,
                                 NavMenuAction::MoveNode(e),
                             ),
+                            cosmic::widget::menu::Item::ButtonDisabled(
+                                fl!("duplicate"),
+                                None,
+                                NavMenuAction::DuplicateNode(e),
+                            ),
                             cosmic::widget::menu::Item::Button(
                                 fl!("open-file-manager"),
                                 None,
                                 NavMenuAction::OpenNodeFileManager(e),
                             ),
+                            cosmic::widget::menu::Item::Button(
+                                fl!("copy-path"),
+                                None,
+                                NavMenuAction::CopyNodePath(e),
+                            ),
+                            cosmic::widget::menu::Item::ButtonDisabled(
+                                fl!("copy-relative-link"),
+                                None,
+                                NavMenuAction::CopyNodeLink(e),
+                            ),
                             cosmic::widget::menu::Item::Button(
                                 fl!("new-folder"),
                                 None,
@@ -482,13 +703,24 @@ impl cosmic::Application for AppModel {
                                 None,
                                 NavMenuAction::OpenVaultFileCreationDialog(e),
                             ),
+                            cosmic::widget::menu::Item::ButtonDisabled(
+                                fl!("export-pdf"),
+                                None,
+                                NavMenuAction::ExportNodes(e),
+                            ),
                         ]
                     } else {
                         // no need to use this for now as we don't have different context menu options for files and folders
                         // let node = self.nav_model.data::<ProjectNode>(e)?;
+                        // delete, move and export act on the whole selection when the node is part of it
+                        let selected = self.nav_selection_for(e).map(|paths| paths.len());
+                        let bulk_label = |single: String, count: Option<usize>| match count {
+                            Some(count) => fl!("bulk-action", action = single, count = count),
+                            None => single,
+                        };
                         vec![
                             cosmic::widget::menu::Item::Button(
-                                fl!("delete"),
+                                bulk_label(fl!("delete"), selected),
                                 None,
                                 NavMenuAction::DeleteNode(e),
                             ),
@@ -498,15 +730,37 @@ impl cosmic::Application for AppModel {
                                 NavMenuAction::RenameNode(e),
                             ),
                             cosmic::widget::menu::Item::Button(
-                                fl!("move-to"),
+                                bulk_label(fl!("move-to"), selected),
                                 None,
                                 NavMenuAction::MoveNode(e),
                             ),
+                            cosmic::widget::menu::Item::Button(
+                                fl!("duplicate"),
+                                None,
+                                NavMenuAction::DuplicateNode(e),
+                            ),
                             cosmic::widget::menu::Item::Button(
                                 fl!("open-file-manager"),
                                 None,
                                 NavMenuAction::OpenNodeFileManager(e),
                             ),
+                            cosmic::widget::menu::Item::Button(
+                                fl!("copy-path"),
+                                None,
+                                NavMenuAction::CopyNodePath(e),
+                            ),
+                            cosmic::widget::menu::Item::Button(
+                                if self
+                                    .nav_node_path(e)
+                                    .is_some_and(|path| self.copies_wikilink(&path))
+                                {
+                                    fl!("copy-wikilink")
+                                } else {
+                                    fl!("copy-relative-link")
+                                },
+                                None,
+                                NavMenuAction::CopyNodeLink(e),
+                            ),
                             // If the entity is a file both creation dialogs will store the new folder/file in the parent folder
                             cosmic::widget::menu::Item::Button(
                                 fl!("new-folder"),
@@ -518,6 +772,11 @@ impl cosmic::Application for AppModel {
                                 None,
                                 NavMenuAction::OpenVaultFileCreationDialog(e),
                             ),
+                            cosmic::widget::menu::Item::Button(
+                                bulk_label(fl!("export-pdf"), selected),
+                                None,
+                                NavMenuAction::ExportNodes(e),
+                            ),
                         ]
                     }
                 })
@@ -526,10 +785,28 @@ impl cosmic::Application for AppModel {
     }
 
     fn on_nav_select(&mut self, id: nav_bar::Id) -> Task<cosmic::Action<Message>> {
-        let State::Ready { editor, .. } = &self.state else {
+        let State::Ready { .. } = &self.state else {
             return Task::none();
         };
 
+        // releasing a dragged node isn't a click
+        if self.nav_drag.as_ref().is_some_and(|drag| drag.dragging) {
+            return Task::none();
+        }
+
+        // Ctrl/Shift + click selects nodes instead of opening them (the root node can't be selected)
+        if self.modifiers.control() || self.modifiers.shift() {
+            if self.nav_model.indent(id).unwrap_or(0) > 0 {
+                if self.modifiers.shift() {
+                    self.extend_nav_selection(id);
+                } else {
+                    self.toggle_nav_selection(id);
+                }
+            }
+            return Task::none();
+        }
+        self.clear_nav_selection(id);
+
         let node_opt = match self.nav_model.data_mut::<ProjectNode>(id) {
             Some(node) => {
                 if let ProjectNode::Folder { open, .. } = node {
@@ -542,7 +819,8 @@ impl cosmic::Application for AppModel {
 
         match node_opt {
             Some(node) => {
-                self.nav_model.icon_set(id, node.icon(18));
+                let icon = self.node_icon(&node);
+                self.nav_model.icon_set(id, icon);
 
                 match node {
                     ProjectNode::Folder { path, open, .. } => {
@@ -571,23 +849,13 @@ impl cosmic::Application for AppModel {
                         //store parent directory of selected file
                         //self.selected_nav_path = path.parent().map(|p| p.to_path_buf());
 
-                        if editor.is_dirty {
-                            if editor.needs_confirmation() {
-                                Task::done(cosmic::action::app(Message::DialogAction(
-                                    dialogs::DialogAction::OpenConfirmCloseFileDialog(
-                                        DiscardChangesAction::OpenFile(path),
-                                    ),
-                                )))
-                            } else {
-                                Task::perform(utils::files::load_file(path), |res| {
-                                    cosmic::action::app(Message::OpenFile(res))
-                                })
-                            }
-                        } else {
-                            Task::perform(utils::files::load_file(path), |res| {
-                                cosmic::action::app(Message::OpenFile(res))
-                            })
+                        // attachments can't be edited, they are opened with the default app
+                        if !self.vault_filter.is_note(&path) {
+                            return self.handle_open_in_file_explorer(&path);
                         }
+
+                        // the file is opened in a new tab (or we switch to it's tab if already open)
+                        self.open_path(path)
                     }
                 }
             }
@@ -622,14 +890,21 @@ impl cosmic::Application for AppModel {
                 preview,
                 panes,
                 preview_state,
-                ..
+                tabs,
             } => cedilla_main_view(
                 &self.config,
                 editor,
                 preview,
                 panes,
                 preview_state,
+                tabs,
+                &self.vault_notes,
                 self.cedilla_font,
+                self.autosaving.contains(&tabs.active()),
+                self.git
+                    .repo
+                    .is_some()
+                    .then_some(self.git.line_changes.as_slice()),
             ),
         };
 
@@ -646,10 +921,7 @@ impl cosmic::Application for AppModel {
         struct ConfigSubscription;
         struct ThemeSubscription;
 
-        let watched_path = match &self.state {
-            State::Ready { editor, .. } => editor.path.clone(),
-            _ => None,
-        };
+        let watched_paths = self.open_paths();
 
         // Add subscriptions which are always active.
         let subscriptions = vec![
@@ -667,6 +939,7 @@ impl cosmic::Application for AppModel {
                     Some(Message::Modifiers(modifiers))
                 }
                 Event::Window(window::Event::CloseRequested) => Some(Message::AppCloseRequested),
+                Event::Window(window::Event::Unfocused) => Some(Message::WindowUnfocused),
                 _ => None,
             }),
             cosmic_config::config_subscription(
@@ -697,11 +970,64 @@ impl cosmic::Application for AppModel {
                 }
                 Message::ConfigInput(ConfigInput::SystemThemeModeChange)
             }),
-            // Watch for external file changes
-            file_watch_subscription(watched_path),
+            // Periodically write the unsaved documents somewhere safe
+            cosmic::iced::time::every(std::time::Duration::from_secs(
+                recovery::RECOVERY_INTERVAL_SECS,
+            ))
+            .map(|_| Message::AutosaveRecovery),
         ];
 
-        Subscription::batch(subscriptions)
+        // Check every second if it's time to autosave while there are edits waiting for it
+        let autosave_tick =
+            (self.config.autosave == AutosaveMode::Idle && self.last_edit.is_some()).then(|| {
+                cosmic::iced::time::every(std::time::Duration::from_secs(1))
+                    .map(|_| Message::AutosaveTick)
+            });
+
+        // Start dragging a node when the button is pressed over the navbar (the navbar captures the press itself)
+        let nav_drag_press = self.nav_cursor.is_some().then(|| {
+            cosmic::iced::event::listen_with(|event, _, _| match event {
+                Event::Mouse(cosmic::iced::mouse::Event::ButtonPressed(
+                    cosmic::iced::mouse::Button::Left,
+                )) => Some(Message::NavDrag(NavDragAction::Pressed)),
+                _ => None,
+            })
+        });
+
+        // Drop the dragged node wherever the button is released
+        let nav_drag_release = self.nav_drag.is_some().then(|| {
+            cosmic::iced::event::listen_with(|event, _, _| match event {
+                Event::Mouse(cosmic::iced::mouse::Event::ButtonReleased(
+                    cosmic::iced::mouse::Button::Left,
+                )) => Some(Message::NavDrag(NavDragAction::Released)),
+                _ => None,
+            })
+        });
+
+        // Expand the collapsed folders a node is dragged over after a while
+        let nav_drag_tick = self
+            .nav_drag
+            .as_ref()
+            .is_some_and(|drag| drag.dragging)
+            .then(|| {
+                cosmic::iced::time::every(std::time::Duration::from_millis(100))
+                    .map(|_| Message::NavDrag(NavDragAction::Tick))
+            });
+
+        // Watch for files and folders added, removed or renamed by other programs to keep the navbar live
+        let vault_watch = vault_watch_subscription(self.vault_watch_path.clone());
+
+        // Watch for external changes of every open file
+        Subscription::batch(
+            subscriptions
+                .into_iter()
+                .chain(watched_paths.into_iter().map(file_watch_subscription))
+                .chain(std::iter::once(vault_watch))
+                .chain(autosave_tick)
+                .chain(nav_drag_press)
+                .chain(nav_drag_release)
+                .chain(nav_drag_tick),
+        )
     }
 
     fn on_app_exit(&mut self) -> Option<Self::Message> {
@@ -713,11 +1039,36 @@ impl cosmic::Application for AppModel {
     /// Tasks may be returned for asynchronous execution of code in the background
     /// on the application's async runtime.
     fn update(&mut self, message: Self::Message) -> Task<cosmic::Action<Self::Message>> {
-        match message {
+        let is_edit = matches!(&message, Message::Edit(action) if action.is_edit())
+            || matches!(
+                message,
+                Message::ApplyFormatting(_) | Message::Undo | Message::Redo
+            );
+        // other messages that may change the content of the open document
+        let changes_content = is_edit
+            || matches!(
+                message,
+                Message::OpenFile(_)
+                    | Message::ReloadFile(_)
+                    | Message::FileHistory(_)
+                    | Message::LinkRewritesApplied(_)
+                    | Message::ExternalFileLoaded(..)
+                    | Message::ApplyMerge(_)
+            );
+
+        // the idle autosave waits until the user stops editing
+        if is_edit && self.config.autosave == AutosaveMode::Idle {
+            self.last_edit = Some(Instant::now());
+        }
+
+        let previous_path = self.active_path();
+
+        let task = match message {
             // UI
             Message::CloseToast(id) => self.handle_close_toast(id),
             Message::AddToast(toast) => self.handle_add_toast(toast),
             Message::LaunchUrl(url) => self.handle_launch_url(url),
+            Message::OpenWikilink(target) => self.handle_open_wikilink(target),
             Message::CopyToClipboard(content) => self.handle_copy_to_clipboard(content),
             Message::ToggleContextPage(page) => self.handle_toggle_context_page(page),
             Message::Surface(a) => self.handle_surface(a),
@@ -730,25 +1081,64 @@ impl cosmic::Application for AppModel {
             // Dialog & NavBar
             Message::DialogAction(action) => self.handle_dialog_action(action),
             Message::NavBarContext(entity) => self.handle_nav_bar_context(entity),
+            Message::NavDrag(action) => self.handle_nav_drag(action),
             Message::NavMenuAction(action) => self.handle_nav_menu_action(action),
 
             // File
             Message::Startup(open_with_file) => self.handle_startup(open_with_file),
             Message::NewFile => self.handle_new_file(),
-            Message::NewVaultFile(name) => self.handle_new_vault_file(name),
+            Message::NewVaultFile(name, template) => self.handle_new_vault_file(name, template),
+            Message::NewVaultFileFromTemplate(name, template, clipboard) => {
+                self.handle_new_vault_file_from_template(name, template, clipboard)
+            }
             Message::NewVaultFolder(name) => self.handle_new_vault_folder(name),
             Message::SaveFile => self.handle_save_file(),
             Message::OpenFile(result) => self.handle_open_file(result),
-            Message::FileSaved(result) => self.handle_file_saved(result),
+            Message::ReloadFile(result) => self.handle_reload_file(result),
+            Message::SessionLoaded(session, contents) => {
+                self.handle_session_loaded(session, contents)
+            }
+            Message::FileSaved(tab, content, result) => {
+                self.handle_file_saved(tab, content, result)
+            }
             Message::DiscardChanges(action) => self.handle_discard_changes(action),
             Message::ExternalFileChanged(path) => self.handle_external_file_changed(path),
+            Message::ExternalFileLoaded(path, result) => {
+                self.handle_external_file_loaded(path, result)
+            }
+            Message::ApplyMerge(merge) => self.handle_apply_merge(merge),
+            Message::ExternalFileRemoved(path) => self.handle_external_file_removed(path),
+            Message::VaultChanged(changes) => self.handle_vault_changed(changes),
+            Message::ActivateTab(entity) => self.handle_activate_tab(entity),
+            Message::CloseTab(entity) => self.handle_close_tab(entity),
+            Message::AutosaveRecovery => self.handle_autosave_recovery(),
+            Message::RecoveryLoaded(documents) => self.handle_recovery_loaded(documents),
+            Message::RestoreDocuments(documents) => self.handle_restore_documents(documents),
+            Message::DiscardRecoveredDocument(key) => self.handle_discard_recovered_document(key),
+            Message::AutosaveTick => self.handle_autosave_tick(),
+            Message::WindowUnfocused => self.handle_window_unfocused(),
+            Message::Autosaved(tab, content, result) => self.handle_autosaved(tab, content, result),
+            Message::HistoryLoaded(stored) => self.handle_history_loaded(stored),
 
             // Vault / Node
             Message::DeleteNode(entity) => self.handle_delete_node(entity),
             Message::RenameNode(entity, name) => self.handle_rename_node(entity, name),
             Message::MoveNode(entity, path) => self.handle_move_node(entity, path),
+            Message::DeleteNodes(paths) => self.handle_delete_nodes(paths),
+            Message::MoveNodes(paths, target) => self.handle_move_nodes(paths, target),
+            Message::NodeDuplicated(result) => self.handle_node_duplicated(result),
+            Message::ExportNodes(paths) => self.handle_export_nodes(paths),
+            Message::NodesExported(exported, failures) => {
+                self.handle_nodes_exported(exported, failures)
+            }
             Message::MoveVault => self.handle_move_vault(),
             Message::VaultMoved(result) => self.handle_vault_moved(result),
+            Message::SwitchVault(index) => self.handle_switch_vault(index),
+            Message::AddVault => self.handle_add_vault(),
+            Message::VaultFolderPicked(path) => self.handle_vault_folder_picked(path),
+            Message::LinkRewritesPlanned(rewrites) => self.handle_link_rewrites_planned(rewrites),
+            Message::ApplyLinkRewrites(rewrites) => self.handle_apply_link_rewrites(rewrites),
+            Message::LinkRewritesApplied(result) => self.handle_link_rewrites_applied(result),
 
             // Editor
             Message::Edit(action) => self.handle_edit(action),
@@ -756,6 +1146,11 @@ impl cosmic::Application for AppModel {
             Message::Undo => self.handle_undo(),
             Message::Redo => self.handle_redo(),
             Message::Search(action) => self.handle_search(action),
+            Message::VaultSearch(action) => self.handle_vault_search(action),
+            Message::Backlinks(action) => self.handle_backlinks(action),
+            Message::FileHistory(action) => self.handle_file_history(action),
+            Message::Trash(action) => self.handle_trash(action),
+            Message::Git(action) => self.handle_git(action),
 
             // Preview / Pane
             Message::UpdateMarkState(msg) => self.handle_update_mark_state(msg),
@@ -772,7 +1167,34 @@ impl cosmic::Application for AppModel {
             Message::OpenInFileExplorer(path) => self.handle_open_in_file_explorer(&path),
             Message::ExportPDF => self.handle_export_pdf(),
             Message::AppCloseRequested => self.handle_app_close_requested(),
+        };
+
+        // any message may have changed the name or the dirty state of the open document
+        self.sync_active_tab_title();
+
+        if changes_content {
+            self.update_git_line_changes();
+
+            // the search goes back to the whole document
+            if let State::Ready { editor, .. } = &mut self.state
+                && editor.search.clear_selection_range()
+            {
+                editor.search.compute_matches(&editor.content.text());
+            }
+        }
+
+        if self.active_path() != previous_path {
+            let task = task
+                .chain(self.refresh_file_history())
+                .chain(self.refresh_git_file());
+
+            if self.config.autosave == AutosaveMode::SwitchFile {
+                return task.chain(self.autosave_documents());
+            }
+            return task;
         }
+
+        task
     }
 }
 
@@ -825,6 +1247,185 @@ impl AppModel {
             .map(|i| i + 1) // offset by 1 because "Default" is at index 0
             .unwrap_or(0);
 
+        let autosave_settings = {
+            let mut items: Vec<Element<Message>> = vec![
+                widget::settings::item::builder(fl!("autosave"))
+                    .control(widget::dropdown(
+                        AutosaveMode::all_labels(),
+                        Some(self.config.autosave.to_index()),
+                        |index| {
+                            Message::ConfigInput(ConfigInput::Autosave(AutosaveMode::from_index(
+                                index,
+                            )))
+                        },
+                    ))
+                    .into(),
+            ];
+
+            if self.config.autosave == AutosaveMode::Idle {
+                items.push(
+                    widget::settings::item::builder(fl!("autosave-delay"))
+                        .description(format!("{}s", self.config.autosave_delay))
+                        .control(
+                            widget::slider(1..=60, self.config.autosave_delay as u16, |v| {
+                                Message::ConfigInput(ConfigInput::UpdateAutosaveDelay(v))
+                            })
+                            .step(1u16),
+                        )
+                        .into(),
+                );
+            }
+
+            cosmic::widget::column::with_children(items).spacing(cosmic::theme::spacing().space_xxs)
+        };
+
+        let undo_settings = {
+            let mut items: Vec<Element<Message>> = vec![
+                widget::settings::item::builder(fl!("persistent-undo"))
+                    .control(widget::dropdown(
+                        BoolState::all_labels(),
+                        Some(self.config.persistent_undo.to_index()),
+                        |index| {
+                            Message::ConfigInput(ConfigInput::PersistentUndo(
+                                BoolState::from_index(index),
+                            ))
+                        },
+                    ))
+                    .into(),
+            ];
+
+            if self.config.persistent_undo == BoolState::Yes {
+                items.push(
+                    widget::settings::item::builder(fl!("undo-max-days"))
+                        .description(fl!("days", days = self.config.undo_max_days))
+                        .control(
+                            widget::slider(1..=365, self.config.undo_max_days as u16, |v| {
+                                Message::ConfigInput(ConfigInput::UpdateUndoMaxDays(v))
+                            })
+                            .step(1u16),
+                        )
+                        .into(),
+                );
+                items.push(
+                    widget::settings::item::builder(fl!("undo-max-size"))
+                        .description(format!("{} KB", self.config.undo_max_kb))
+                        .control(
+                            widget::slider(64..=8192, self.config.undo_max_kb as u16, |v| {
+                                Message::ConfigInput(ConfigInput::UpdateUndoMaxKb(v))
+                            })
+                            .step(64u16),
+                        )
+                        .into(),
+                );
+            }
+
+            cosmic::widget::column::with_children(items).spacing(cosmic::theme::spacing().space_xxs)
+        };
+
+        let vault_settings = {
+            let current_vault = self.config.current_vault();
+            let mut items: Vec<Element<Message>> = self
+                .config
+                .vaults
+                .iter()
+                .enumerate()
+                .map(|(index, vault)| {
+                    let is_current = current_vault == Some(index);
+
+                    let open_button = widget::button::text(fl!("open-vault"))
+                        .on_press_maybe((!is_current).then_some(Message::SwitchVault(index)));
+                    let remove_button = widget::button::destructive(fl!("remove")).on_press_maybe(
+                        (!is_current)
+                            .then_some(Message::ConfigInput(ConfigInput::RemoveVault(index))),
+                    );
+
+                    column![
+                        text_input(fl!("vault-name"), &vault.name).on_input(move |v| {
+                            Message::ConfigInput(ConfigInput::RenameVault(index, v))
+                        }),
+                        row![
+                            text::caption(vault.path.to_string_lossy().to_string())
+                                .wrapping(cosmic::iced::core::text::Wrapping::Glyph)
+                                .width(Length::Fill),
+                            open_button,
+                            remove_button,
+                        ]
+                        .align_y(Alignment::Center)
+                        .spacing(cosmic::theme::spacing().space_xxs),
+                    ]
+                    .spacing(cosmic::theme::spacing().space_xxxs)
+                    .into()
+                })
+                .collect();
+
+            items.push(
+                row![
+                    horizontal(),
+                    button::standard(fl!("add-vault")).on_press(Message::AddVault)
+                ]
+                .into(),
+            );
+
+            items.push(
+                cosmic::widget::column::with_children(vec![
+                    column![
+                        text::body(fl!("vault-files")),
+                        text::caption(fl!("vault-files-info"))
+                    ]
+                    .into(),
+                    text::caption(fl!("note-extensions")).into(),
+                    text_input("md, txt", &self.note_extensions_input)
+                        .on_input(|v| Message::ConfigInput(ConfigInput::NoteExtensionsInput(v)))
+                        .into(),
+                    text::caption(fl!("exclude-patterns")).into(),
+                    text_input(".git, drafts/", &self.exclude_patterns_input)
+                        .on_input(|v| Message::ConfigInput(ConfigInput::ExcludePatternsInput(v)))
+                        .into(),
+                    row![
+                        horizontal(),
+                        button::suggested(fl!("apply"))
+                            .on_press(Message::ConfigInput(ConfigInput::VaultFilterSave))
+                    ]
+                    .into(),
+                ])
+                .spacing(cosmic::theme::spacing().space_xxs)
+                .into(),
+            );
+            items.push(
+                widget::settings::item::builder(fl!("respect-gitignore"))
+                    .control(widget::dropdown(
+                        BoolState::all_labels(),
+                        Some(self.config.respect_gitignore.to_index()),
+                        |index| {
+                            Message::ConfigInput(ConfigInput::RespectGitignore(
+                                BoolState::from_index(index),
+                            ))
+                        },
+                    ))
+                    .into(),
+            );
+            items.push(
+                widget::settings::item::builder(fl!("show-attachments"))
+                    .description(fl!("show-attachments-info"))
+                    .control(widget::dropdown(
+                        BoolState::all_labels(),
+                        Some(self.config.show_attachments.to_index()),
+                        |index| {
+                            Message::ConfigInput(ConfigInput::ShowAttachments(
+                                BoolState::from_index(index),
+                            ))
+                        },
+                    ))
+                    .into(),
+            );
+
+            if self.vault_config != VaultConfig::default() {
+                items.push(text::caption(fl!("vault-config-active")).into());
+            }
+
+            cosmic::widget::column::with_children(items).spacing(cosmic::theme::spacing().space_s)
+        };
+
         widget::settings::view_column(vec![
             widget::settings::section()
                 .title(fl!("appearance"))
@@ -952,6 +1553,8 @@ impl AppModel {
                         ),
                     ),
                 )
+                .add(autosave_settings)
+                .add(undo_settings)
                 .add(
                     cosmic::widget::column::with_children(vec![
                         column![
@@ -975,6 +1578,10 @@ impl AppModel {
                     .spacing(cosmic::theme::spacing().space_xxs),
                 )
                 .into(),
+            widget::settings::section()
+                .title(fl!("vaults"))
+                .add(vault_settings)
+                .into(),
             widget::settings::section()
                 .title(fl!("view"))
                 .add(
@@ -1003,6 +1610,558 @@ impl AppModel {
         ])
         .into()
     }
+
+    /// Backlinks context page
+    pub fn backlinks(&self) -> Element<'_, Message> {
+        let spacing = theme::active().cosmic().spacing;
+        let vault_path = self.config.vault_path();
+        let backlinks = &self.backlinks;
+
+        let relative_path = |path: &Path| {
+            path.strip_prefix(&vault_path)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string()
+        };
+
+        let status: String = if backlinks.target.is_none() {
+            fl!("backlinks-no-file")
+        } else if backlinks.is_searching && backlinks.results.is_empty() {
+            fl!("searching")
+        } else if backlinks.results.is_empty() {
+            fl!("no-backlinks")
+        } else {
+            fl!(
+                "backlinks-results",
+                links = backlinks.link_count(),
+                files = backlinks.results.len()
+            )
+        };
+
+        let header = row![
+            text::caption(status).width(Length::Fill),
+            button::icon(icons::get_handle("view-refresh-symbolic", 18))
+                .on_press_maybe(
+                    backlinks
+                        .target
+                        .is_some()
+                        .then_some(Message::Backlinks(BacklinksAction::Refresh))
+                )
+                .class(theme::Button::Icon),
+        ]
+        .spacing(spacing.space_xxs)
+        .align_y(Alignment::Center);
+
+        let results = backlinks.results.iter().map(|result| {
+            let links = result.matches.iter().map(|m| {
+                button::custom(
+                    row![
+                        text::caption(format!("{}", m.search_match.line + 1))
+                            .font(Font::MONOSPACE)
+                            .width(Length::Fixed(40.)),
+                        text::body(m.snippet.clone())
+                            .wrapping(cosmic::iced::core::text::Wrapping::WordOrGlyph)
+                            .width(Length::Fill),
+                    ]
+                    .spacing(spacing.space_xxs),
+                )
+                .on_press(Message::Backlinks(BacklinksAction::Open(
+                    result.path.clone(),
+                    m.search_match.clone(),
+                )))
+                .class(theme::Button::MenuItem)
+                .width(Length::Fill)
+                .into()
+            });
+
+            cosmic::widget::column::with_capacity(result.matches.len() + 1)
+                .push(text::heading(relative_path(&result.path)))
+                .extend(links)
+                .spacing(spacing.space_xxxs)
+                .into()
+        });
+
+        cosmic::widget::column::with_capacity(backlinks.results.len() + 1)
+            .push(header)
+            .extend(results)
+            .spacing(spacing.space_s)
+            .into()
+    }
+
+    /// File history context page
+    pub fn file_history(&self) -> Element<'_, Message> {
+        let cosmic_theme = theme::active();
+        let spacing = cosmic_theme.cosmic().spacing;
+        let insert_color: Color = cosmic_theme.cosmic().success_color().into();
+        let delete_color: Color = cosmic_theme.cosmic().destructive_color().into();
+        let file_history = &self.file_history;
+
+        let status: String = if file_history.target.is_none() {
+            fl!("file-history-no-file")
+        } else if file_history.is_loading && file_history.snapshots.is_empty() {
+            fl!("loading")
+        } else if file_history.snapshots.is_empty() {
+            fl!("no-snapshots")
+        } else {
+            fl!("snapshots-count", count = file_history.snapshots.len())
+        };
+
+        let header = row![
+            text::caption(status).width(Length::Fill),
+            button::icon(icons::get_handle("view-refresh-symbolic", 18))
+                .on_press_maybe(
+                    file_history
+                        .target
+                        .is_some()
+                        .then_some(Message::FileHistory(FileHistoryAction::Refresh))
+                )
+                .class(theme::Button::Icon),
+        ]
+        .spacing(spacing.space_xxs)
+        .align_y(Alignment::Center);
+
+        // newest snapshots first
+        let now = history::unix_now();
+        let snapshots = file_history
+            .snapshots
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, snapshot)| {
+                button::custom(
+                    row![
+                        text::body(snapshot.age(now)).width(Length::Fill),
+                        text::caption(fl!("line-count", count = snapshot.content.lines().count())),
+                    ]
+                    .spacing(spacing.space_xxs)
+                    .align_y(Alignment::Center),
+                )
+                .on_press(Message::FileHistory(FileHistoryAction::Select(index)))
+                .selected(file_history.selected == Some(index))
+                .class(theme::Button::MenuItem)
+                .width(Length::Fill)
+                .into()
+            });
+
+        let mut content = cosmic::widget::column::with_capacity(file_history.snapshots.len() + 2)
+            .push(header)
+            .extend(snapshots)
+            .spacing(spacing.space_xxs);
+
+        if let Some(index) = file_history.selected {
+            // the snapshot shows what has been removed since, the current version what has been added
+            let snapshot_spans: Vec<cosmic::iced::core::text::Span<'_, (), Font>> = file_history
+                .diff
+                .iter()
+                .filter_map(|chunk| match chunk {
+                    DiffChunk::Equal(s) => Some(cosmic::iced::widget::span(s.as_str())),
+                    DiffChunk::Delete(s) => {
+                        Some(cosmic::iced::widget::span(s.as_str()).color(delete_color))
+                    }
+                    DiffChunk::Insert(_) => None,
+                })
+                .collect();
+            let current_spans: Vec<cosmic::iced::core::text::Span<'_, (), Font>> = file_history
+                .diff
+                .iter()
+                .filter_map(|chunk| match chunk {
+                    DiffChunk::Equal(s) => Some(cosmic::iced::widget::span(s.as_str())),
+                    DiffChunk::Insert(s) => {
+                        Some(cosmic::iced::widget::span(s.as_str()).color(insert_color))
+                    }
+                    DiffChunk::Delete(_) => None,
+                })
+                .collect();
+
+            let side = |title: String, spans| {
+                column![
+                    text::heading(title),
+                    cosmic::iced::widget::rich_text(spans)
+                        .font(Font::MONOSPACE)
+                        .size(12),
+                ]
+                .spacing(spacing.space_xxs)
+                .width(Length::FillPortion(1))
+            };
+
+            content = content.push(
+                column![
+                    row![
+                        horizontal(),
+                        button::standard(fl!("restore"))
+                            .on_press(Message::FileHistory(FileHistoryAction::Restore(index))),
+                    ],
+                    row![
+                        side(fl!("snapshot"), snapshot_spans),
+                        side(fl!("current-version"), current_spans),
+                    ]
+                    .spacing(spacing.space_s),
+                ]
+                .padding(Padding::from([spacing.space_s, 0]))
+                .spacing(spacing.space_xxs),
+            );
+        }
+
+        content.into()
+    }
+
+    /// Trash context page
+    pub fn trash(&self) -> Element<'_, Message> {
+        let spacing = theme::active().cosmic().spacing;
+        let trash = &self.trash;
+
+        let status: String = if trash.is_loading && trash.items.is_empty() {
+            fl!("loading")
+        } else if trash.items.is_empty() {
+            fl!("trash-empty")
+        } else {
+            fl!("trash-count", count = trash.items.len())
+        };
+
+        let header = row![
+            text::caption(status).width(Length::Fill),
+            button::icon(icons::get_handle("view-refresh-symbolic", 18))
+                .on_press(Message::Trash(TrashAction::Refresh))
+                .class(theme::Button::Icon),
+            button::destructive(fl!("empty-trash")).on_press_maybe(
+                (!trash.items.is_empty()).then_some(Message::DialogAction(
+                    dialogs::DialogAction::OpenEmptyTrashDialog
+                ))
+            ),
+        ]
+        .spacing(spacing.space_xxs)
+        .align_y(Alignment::Center);
+
+        let now = history::unix_now();
+        let items = trash.items.iter().map(|item| {
+            let icon = if item.is_dir {
+                "folder-symbolic"
+            } else {
+                "text-x-generic-symbolic"
+            };
+            let location = item
+                .path
+                .parent()
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            column![
+                row![
+                    icons::get_icon(icon, 16),
+                    text::body(item.name()).width(Length::Fill),
+                ]
+                .spacing(spacing.space_xxs)
+                .align_y(Alignment::Center),
+                text::caption(fl!(
+                    "deleted-from",
+                    age = snapshots::time_ago(item.deleted_at, now),
+                    path = format!("/{location}")
+                )),
+                row![
+                    horizontal(),
+                    button::text(fl!("delete-forever"))
+                        .on_press(Message::Trash(TrashAction::Delete(item.id.clone()))),
+                    button::standard(fl!("restore"))
+                        .on_press(Message::Trash(TrashAction::Restore(vec![item.id.clone()]))),
+                ]
+                .spacing(spacing.space_xxs),
+            ]
+            .padding(Padding::from([spacing.space_xxs, 0]))
+            .spacing(spacing.space_xxxs)
+            .into()
+        });
+
+        cosmic::widget::column::with_capacity(trash.items.len() + 1)
+            .push(header)
+            .extend(items)
+            .spacing(spacing.space_xxs)
+            .into()
+    }
+
+    /// Git context page
+    pub fn git(&self) -> Element<'_, Message> {
+        let spacing = theme::active().cosmic().spacing;
+        let git = &self.git;
+
+        let Some(repo) = &git.repo else {
+            return text::caption(fl!("not-a-git-repository")).into();
+        };
+
+        let relative_path = |path: &Path| {
+            path.strip_prefix(&repo.root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string()
+        };
+
+        let mut files: Vec<_> = repo.files.iter().collect();
+        files.sort_by(|a, b| a.0.cmp(b.0));
+
+        let header = row![
+            text::caption(if files.is_empty() {
+                fl!("no-changes")
+            } else {
+                fl!("changed-files", count = files.len())
+            })
+            .width(Length::Fill),
+            button::text(fl!("stage-all")).on_press_maybe(
+                (!files.is_empty() && !git.is_busy).then_some(Message::Git(GitAction::StageAll))
+            ),
+            button::icon(icons::get_handle("view-refresh-symbolic", 18))
+                .on_press(Message::Git(GitAction::Refresh))
+                .class(theme::Button::Icon),
+        ]
+        .spacing(spacing.space_xxs)
+        .align_y(Alignment::Center);
+
+        let changes = files.iter().map(|(path, status)| {
+            let mut label = match status.change {
+                FileChange::Modified => fl!("git-modified"),
+                FileChange::New => fl!("git-new"),
+                FileChange::Untracked => fl!("git-untracked"),
+            };
+            if status.is_staged {
+                label = format!("{label} · {}", fl!("staged"));
+            }
+
+            // files with unstaged changes get staged, fully staged files get unstaged
+            let (button_label, action) = if status.has_unstaged {
+                (fl!("stage"), GitAction::Stage(path.to_path_buf()))
+            } else {
+                (fl!("unstage"), GitAction::Unstage(path.to_path_buf()))
+            };
+
+            row![
+                icons::get_icon(status.change.icon_name(), 16),
+                column![text::body(relative_path(path)), text::caption(label)].width(Length::Fill),
+                button::text(button_label)
+                    .on_press_maybe((!git.is_busy).then_some(Message::Git(action))),
+            ]
+            .spacing(spacing.space_xxs)
+            .align_y(Alignment::Center)
+            .into()
+        });
+
+        let has_staged = repo.files.values().any(|status| status.is_staged);
+        let commit = column![
+            text_input(fl!("commit-message"), &git.commit_message)
+                .on_input(|v| Message::Git(GitAction::UpdateCommitMessage(v)))
+                .on_submit(|_v| Message::Git(GitAction::Commit))
+                .width(Length::Fill),
+            row![
+                horizontal(),
+                button::suggested(fl!("commit")).on_press_maybe(
+                    (has_staged && !git.is_busy && !git.commit_message.trim().is_empty())
+                        .then_some(Message::Git(GitAction::Commit))
+                ),
+            ],
+        ]
+        .spacing(spacing.space_xxs);
+
+        // log of the open file
+        let log_title = match &git.log_target {
+            Some(path) => fl!("file-log", file = relative_path(path)),
+            None => fl!("file-log-no-file"),
+        };
+
+        let now = history::unix_now();
+        let log = git.log.iter().map(|entry| {
+            column![
+                text::body(entry.summary.clone()),
+                text::caption(format!(
+                    "{} · {} · {}",
+                    entry.hash,
+                    entry.author,
+                    snapshots::time_ago(entry.timestamp, now)
+                )),
+            ]
+            .spacing(spacing.space_xxxs)
+            .into()
+        });
+
+        let mut content = cosmic::widget::column::with_capacity(files.len() + git.log.len() + 4)
+            .push(header)
+            .extend(changes)
+            .push(commit)
+            .push(text::heading(log_title))
+            .spacing(spacing.space_s);
+
+        if git.log_target.is_some() && git.log.is_empty() {
+            content = content.push(text::caption(fl!("no-commits")));
+        }
+
+        content.extend(log).into()
+    }
+
+    /// Find in vault context page
+    pub fn vault_search(&self) -> Element<'_, Message> {
+        let spacing = theme::active().cosmic().spacing;
+        let vault_path = self.config.vault_path();
+        let search = &self.vault_search;
+
+        let relative_path = |path: &Path| {
+            path.strip_prefix(&vault_path)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string()
+        };
+
+        let status: String = if let Some(err) = &search.regex_error {
+            err.clone()
+        } else if search.is_searching {
+            fl!("searching")
+        } else if search.replace_mode && !search.replace_previews.is_empty() {
+            fl!(
+                "vault-replace-summary",
+                replacements = search.checked_replace_count(),
+                files = search.replace_previews.iter().filter(|p| p.checked).count()
+            )
+        } else if !search.replace_mode && !search.results.is_empty() {
+            fl!(
+                "vault-search-results",
+                matches = search.match_count(),
+                files = search.results.len()
+            )
+        } else if search.generation > 0 && !search.search_value.is_empty() {
+            fl!("no-results")
+        } else {
+            String::new()
+        };
+
+        let search_row = row![
+            text_input(fl!("search"), &search.search_value)
+                .id(vault_search_input_id())
+                .on_input(|v| Message::VaultSearch(VaultSearchAction::UpdateSearchValue(v)))
+                .on_submit(|_v| Message::VaultSearch(VaultSearchAction::StartSearch))
+                .width(Length::Fill),
+            button::icon(icons::get_handle("regex-symbolic", 18))
+                .on_press(Message::VaultSearch(VaultSearchAction::ToggleRegex))
+                .class(if search.use_regex {
+                    theme::Button::Suggested
+                } else {
+                    theme::Button::Standard
+                }),
+            button::icon(icons::get_handle("edit-find-replace-symbolic", 18))
+                .on_press(Message::VaultSearch(VaultSearchAction::ToggleReplaceMode))
+                .class(if search.replace_mode {
+                    theme::Button::Suggested
+                } else {
+                    theme::Button::Standard
+                }),
+        ]
+        .spacing(spacing.space_xxs)
+        .align_y(Alignment::Center);
+
+        let mut content = cosmic::widget::column::with_capacity(4).push(search_row);
+
+        if !search.replace_mode {
+            let results = search.results.iter().map(|result| {
+                let matches = result.matches.iter().map(|m| {
+                    button::custom(
+                        row![
+                            text::caption(format!("{}", m.search_match.line + 1))
+                                .font(Font::MONOSPACE)
+                                .width(Length::Fixed(40.)),
+                            text::body(m.snippet.clone())
+                                .wrapping(cosmic::iced::core::text::Wrapping::WordOrGlyph)
+                                .width(Length::Fill),
+                        ]
+                        .spacing(spacing.space_xxs),
+                    )
+                    .on_press(Message::VaultSearch(VaultSearchAction::OpenResult(
+                        result.path.clone(),
+                        m.search_match.clone(),
+                    )))
+                    .class(theme::Button::MenuItem)
+                    .width(Length::Fill)
+                    .into()
+                });
+
+                cosmic::widget::column::with_capacity(result.matches.len() + 1)
+                    .push(text::heading(relative_path(&result.path)))
+                    .extend(matches)
+                    .spacing(spacing.space_xxxs)
+                    .into()
+            });
+
+            return content
+                .push(text::caption(status))
+                .extend(results)
+                .spacing(spacing.space_s)
+                .into();
+        }
+
+        let replace_row = row![
+            text_input(fl!("replace"), &search.replace_value)
+                .on_input(|v| Message::VaultSearch(VaultSearchAction::UpdateReplaceValue(v)))
+                .on_submit(|_v| Message::VaultSearch(VaultSearchAction::PreviewReplace))
+                .width(Length::Fill),
+            button::standard(fl!("preview"))
+                .on_press(Message::VaultSearch(VaultSearchAction::PreviewReplace)),
+        ]
+        .spacing(spacing.space_xxs)
+        .align_y(Alignment::Center);
+
+        let apply_row = row![
+            text::caption(status).width(Length::Fill),
+            button::suggested(fl!("replace-in-vault")).on_press_maybe(
+                (search.checked_replace_count() > 0)
+                    .then_some(Message::VaultSearch(VaultSearchAction::ApplyReplace))
+            ),
+        ]
+        .spacing(spacing.space_xxs)
+        .align_y(Alignment::Center);
+
+        let cosmic_theme = theme::active();
+        let insert_color: Color = cosmic_theme.cosmic().success_color().into();
+        let delete_color: Color = cosmic_theme.cosmic().destructive_color().into();
+
+        let previews = search.replace_previews.iter().enumerate().map(|(idx, p)| {
+            let header = row![
+                cosmic::iced::widget::checkbox(p.checked).on_toggle(move |_| {
+                    Message::VaultSearch(VaultSearchAction::ToggleReplaceFile(idx))
+                }),
+                text::heading(relative_path(&p.path)).width(Length::Fill),
+                text::caption(p.count().to_string()),
+            ]
+            .spacing(spacing.space_xxs)
+            .align_y(Alignment::Center);
+
+            let lines = p.lines.iter().map(|l| {
+                let spans: Vec<cosmic::iced::core::text::Span<'_, (), Font>> = l
+                    .chunks
+                    .iter()
+                    .map(|chunk| match chunk {
+                        DiffChunk::Equal(s) => cosmic::iced::widget::span(s.as_str()),
+                        DiffChunk::Insert(s) => {
+                            cosmic::iced::widget::span(s.as_str()).color(insert_color)
+                        }
+                        DiffChunk::Delete(s) => cosmic::iced::widget::span(s.as_str())
+                            .color(delete_color)
+                            .strikethrough(true),
+                    })
+                    .collect();
+
+                row![
+                    text::caption(format!("{}", l.line + 1))
+                        .font(Font::MONOSPACE)
+                        .width(Length::Fixed(40.)),
+                    cosmic::iced::widget::rich_text(spans).width(Length::Fill),
+                ]
+                .spacing(spacing.space_xxs)
+                .into()
+            });
+
+            cosmic::widget::column::with_capacity(p.lines.len() + 1)
+                .push(header)
+                .extend(lines)
+                .spacing(spacing.space_xxxs)
+                .into()
+        });
+
+        content = content.push(replace_row).push(apply_row).extend(previews);
+        content.spacing(spacing.space_s).into()
+    }
 }
 
 //
@@ -1010,15 +2169,21 @@ impl AppModel {
 //
 
 /// View of the header of this screen
+#[allow(clippy::too_many_arguments)]
 fn cedilla_main_view<'a>(
     app_config: &'a CedillaConfig,
     editor: &'a EditorState,
     preview: &'a MarkdownPreview,
     panes: &'a pane_grid::State<PaneContent>,
     preview_state: &'a PreviewState,
+    tabs: &'a segmented_button::SingleSelectModel,
+    vault_notes: &'a [PathBuf],
     font: Font,
+    is_saving: bool,
+    line_changes: Option<&'a [(usize, LineChange)]>,
 ) -> Element<'a, Message> {
     let spacing = theme::active().cosmic().spacing;
+    let vault_path = app_config.vault_path();
 
     let create_editor = move || {
         container(responsive(move |size| {
@@ -1050,9 +2215,15 @@ fn cedilla_main_view<'a>(
                         |highlight, _theme| highlight.to_format(),
                     )
                     .key_binding(text_editor_key_bindings)
+                    .line_changes(line_changes.unwrap_or_default())
                     .size(app_config.text_size)
                     .font(font)
-                    .padding(0)
+                    // leave room for the gutter markers of the changed lines
+                    .padding(if line_changes.is_some() {
+                        Padding::ZERO.left(GUTTER_WIDTH)
+                    } else {
+                        Padding::ZERO
+                    })
                     .retain_focus_on_external_click(!editor.search.show_search_box)
                     .on_action(Message::Edit),
             )
@@ -1116,6 +2287,14 @@ fn cedilla_main_view<'a>(
                         MarkWidget::new(&preview.markstate)
                             .on_updating_state(Message::UpdateMarkState)
                             .on_clicking_link(Message::LaunchUrl)
+                            .on_clicking_wikilink(Message::OpenWikilink)
+                            .on_resolving_wikilink({
+                                let vault_path = vault_path.clone();
+                                move |target| {
+                                    links::resolve_wikilink(&vault_path, vault_notes, target)
+                                        .is_some()
+                                }
+                            })
                             .on_copying_code(Message::CopyToClipboard)
                             .font(font)
                             .text_size(app_config.text_size)
@@ -1171,7 +2350,7 @@ fn cedilla_main_view<'a>(
                     let relative = path
                         .trim_start_matches(&*app_config.vault_path)
                         .trim_start_matches('/');
-                    text(format!("Cedilla Vault/{relative}")).size(12)
+                    text(format!("{}/{relative}", app_config.vault_name())).size(12)
                 } else {
                     text(path).size(12)
                 }
@@ -1185,6 +2364,18 @@ fn cedilla_main_view<'a>(
             text("").size(12)
         };
 
+        let save_state = if is_saving {
+            text(fl!("saving")).size(12)
+        } else if editor.is_orphaned {
+            text(fl!("deleted-on-disk")).size(12)
+        } else if editor.path.is_none() {
+            text("").size(12)
+        } else if editor.is_dirty {
+            text(fl!("unsaved-changes")).size(12)
+        } else {
+            text(fl!("saved")).size(12)
+        };
+
         let position = {
             let cursor = editor.content.cursor();
             text(format!(
@@ -1196,9 +2387,15 @@ fn cedilla_main_view<'a>(
         };
 
         container(
-            row![file_path, dirty_indicator, horizontal(), position]
-                .padding(spacing.space_xxs)
-                .spacing(spacing.space_xxs),
+            row![
+                file_path,
+                dirty_indicator,
+                horizontal(),
+                save_state,
+                position
+            ]
+            .padding(spacing.space_xxs)
+            .spacing(spacing.space_xxs),
         )
         .width(Length::Fill)
         .class(theme::Container::Card)
@@ -1249,9 +2446,17 @@ fn cedilla_main_view<'a>(
         .class(theme::Container::Card)
     };
 
+    let tab_bar = widget::tab_bar::horizontal(tabs)
+        .button_height(32)
+        .button_spacing(spacing.space_xxs)
+        .on_activate(Message::ActivateTab)
+        .on_close(Message::CloseTab)
+        .width(Length::Shrink)
+        .apply(widget::scrollable::horizontal);
+
     let content_column = match app_config.show_helper_header_bar {
-        ShowState::Show => column![helper_header_bar, main_content],
-        ShowState::Hide => column![main_content],
+        ShowState::Show => column![helper_header_bar, tab_bar, main_content],
+        ShowState::Hide => column![tab_bar, main_content],
     }
     .spacing(spacing.space_xxxs);
 
@@ -1282,35 +2487,86 @@ fn cedilla_main_view<'a>(
             None => fl!("no-results"),
         };
 
-        let search_box = container(
-            row![
-                text_input(fl!("search"), &editor.search.search_value)
-                    .id(search_input_id())
-                    .on_focus(Message::Search(SearchAction::FocusSearchField))
-                    .on_input(|v| Message::Search(SearchAction::UpdateSearchValue(v)))
-                    .on_submit(|_v| Message::Search(SearchAction::NextResult))
+        let search_row = row![
+            text_input(fl!("search"), &editor.search.search_value)
+                .id(search_input_id())
+                .on_focus(Message::Search(SearchAction::FocusSearchField))
+                .on_input(|v| Message::Search(SearchAction::UpdateSearchValue(v)))
+                .on_submit(|_v| Message::Search(SearchAction::NextResult))
+                .width(Length::Fixed(200.)),
+            button::icon(icons::get_handle("go-up-symbolic", 16))
+                .on_press(Message::Search(SearchAction::PrevResult))
+                .class(theme::Button::Icon),
+            button::icon(icons::get_handle("go-down-symbolic", 16))
+                .on_press(Message::Search(SearchAction::NextResult))
+                .class(theme::Button::Icon),
+            button::icon(icons::get_handle("regex-symbolic", 18))
+                .on_press(Message::Search(SearchAction::ToggleRegex))
+                .class(if editor.search.use_regex {
+                    theme::Button::Suggested
+                } else {
+                    theme::Button::Standard
+                }),
+            button::icon(icons::get_handle("case-sensitive-symbolic", 18))
+                .on_press(Message::Search(SearchAction::ToggleCaseInsensitive))
+                // the icon says "case sensitive", which is the default
+                .class(if !editor.search.case_insensitive {
+                    theme::Button::Suggested
+                } else {
+                    theme::Button::Standard
+                }),
+            button::icon(icons::get_handle("whole-word-symbolic", 18))
+                .on_press(Message::Search(SearchAction::ToggleWholeWord))
+                .class(if editor.search.whole_word {
+                    theme::Button::Suggested
+                } else {
+                    theme::Button::Standard
+                }),
+            button::icon(icons::get_handle("text-select-symbolic", 18))
+                .on_press(Message::Search(SearchAction::ToggleInSelection))
+                .class(if editor.search.in_selection {
+                    theme::Button::Suggested
+                } else {
+                    theme::Button::Standard
+                }),
+            button::icon(icons::get_handle("edit-find-replace-symbolic", 18))
+                .on_press(Message::Search(SearchAction::ToggleReplace))
+                .class(if editor.search.show_replace {
+                    theme::Button::Suggested
+                } else {
+                    theme::Button::Standard
+                }),
+            text(match_status).size(12),
+            button::icon(icons::get_handle("window-close-symbolic", 16))
+                .on_press(Message::Search(SearchAction::ToggleSearch))
+                .class(theme::Button::Icon),
+        ]
+        .spacing(spacing.space_xxs)
+        .align_y(Alignment::Center);
+
+        let search_column = if editor.search.show_replace {
+            let replace_row = row![
+                text_input(fl!("replace"), &editor.search.replace_value)
+                    .id(replace_input_id())
+                    .on_input(|v| Message::Search(SearchAction::UpdateReplaceValue(v)))
+                    .on_submit(|_v| Message::Search(SearchAction::Replace))
                     .width(Length::Fixed(200.)),
-                button::icon(icons::get_handle("go-up-symbolic", 16))
-                    .on_press(Message::Search(SearchAction::PrevResult))
-                    .class(theme::Button::Icon),
-                button::icon(icons::get_handle("go-down-symbolic", 16))
-                    .on_press(Message::Search(SearchAction::NextResult))
-                    .class(theme::Button::Icon),
-                button::icon(icons::get_handle("regex-symbolic", 18))
-                    .on_press(Message::Search(SearchAction::ToggleRegex))
-                    .class(if editor.search.use_regex {
-                        theme::Button::Suggested
-                    } else {
-                        theme::Button::Standard
-                    }),
-                text(match_status).size(12),
-                button::icon(icons::get_handle("window-close-symbolic", 16))
-                    .on_press(Message::Search(SearchAction::ToggleSearch))
-                    .class(theme::Button::Icon),
+                button::text(fl!("replace")).on_press(Message::Search(SearchAction::Replace)),
+                button::text(fl!("replace-all"))
+                    .on_press(Message::Search(SearchAction::ReplaceAll)),
             ]
             .spacing(spacing.space_xxs)
-            .align_y(Alignment::Center)
-            .padding(spacing.space_xs),
+            .align_y(Alignment::Center);
+
+            column![search_row, replace_row]
+        } else {
+            column![search_row]
+        };
+
+        let search_box = container(
+            search_column
+                .spacing(spacing.space_xxs)
+                .padding(spacing.space_xs),
         )
         .class(theme::Container::Background)
         .width(Length::Shrink);
@@ -1328,16 +2584,12 @@ fn cedilla_main_view<'a>(
     }
 }
 
-// Watches for external changes on the currently open file
-fn file_watch_subscription(path: Option<PathBuf>) -> Subscription<Message> {
+// Watches for external changes on the given open file
+fn file_watch_subscription(path: PathBuf) -> Subscription<Message> {
     use cosmic::iced::futures::SinkExt;
     use cosmic::iced::futures::channel::mpsc as iced_mpsc;
     use notify::{EventKind, RecursiveMode, Watcher, recommended_watcher};
 
-    let Some(path) = path else {
-        return Subscription::none();
-    };
-
     Subscription::run_with(path, |path| {
         let path_owned = path.clone();
 
@@ -1349,8 +2601,10 @@ fn file_watch_subscription(path: Option<PathBuf>) -> Subscription<Message> {
                 let mut watcher =
                     match recommended_watcher(move |res: notify::Result<notify::Event>| {
                         if let Ok(event) = res {
-                            let is_relevant =
-                                matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_));
+                            let is_relevant = matches!(
+                                event.kind,
+                                EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
+                            );
                             if is_relevant {
                                 for p in event.paths {
                                     let _ = tx.send(p);
@@ -1380,9 +2634,18 @@ fn file_watch_subscription(path: Option<PathBuf>) -> Subscription<Message> {
                     };
 
                     if changed_path == path_owned {
-                        let _ = output
-                            .send(Message::ExternalFileChanged(changed_path.clone()))
-                            .await;
+                        if !path_owned.exists() {
+                            // give the vault watcher time to report where it was renamed to
+                            // (and programs replacing the file time to write it again)
+                            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                        }
+
+                        let message = if path_owned.exists() {
+                            Message::ExternalFileChanged(changed_path.clone())
+                        } else {
+                            Message::ExternalFileRemoved(changed_path.clone())
+                        };
+                        let _ = output.send(message).await;
 
                         tokio::time::sleep(std::time::Duration::from_millis(300)).await;
                         while rx.try_recv().is_ok() {}
@@ -1393,6 +2656,106 @@ fn file_watch_subscription(path: Option<PathBuf>) -> Subscription<Message> {
     })
 }
 
+// Watches (recursively) for files and folders of the vault changed by other programs
+fn vault_watch_subscription(vault_path: PathBuf) -> Subscription<Message> {
+    use cosmic::iced::futures::SinkExt;
+    use cosmic::iced::futures::channel::mpsc as iced_mpsc;
+    use notify::event::{ModifyKind, RenameMode};
+    use notify::{EventKind, RecursiveMode, Watcher, recommended_watcher};
+
+    // changes are sent once the vault has been quiet this long, so bursts (syncs, checkouts...) come together
+    const DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(500);
+
+    Subscription::run_with(vault_path, |vault_path| {
+        let vault_path = vault_path.clone();
+
+        cosmic::iced::stream::channel(
+            16,
+            move |mut output: iced_mpsc::Sender<Message>| async move {
+                let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<VaultChange>();
+                let config_vault_path = vault_path.clone();
+
+                let mut watcher =
+                    match recommended_watcher(move |res: notify::Result<notify::Event>| {
+                        let Ok(event) = res else {
+                            return;
+                        };
+
+                        // reading the settings file must not trigger loading it again
+                        let is_write = matches!(
+                            event.kind,
+                            EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(_)
+                        );
+                        if is_write
+                            && event
+                                .paths
+                                .iter()
+                                .any(|p| utils::files::is_vault_config_file(&config_vault_path, p))
+                        {
+                            let _ = tx.send(VaultChange::ConfigChanged);
+                            return;
+                        }
+
+                        // our own data and the internals of git change all the time and are never shown
+                        let is_internal = event.paths.iter().any(|p| {
+                            p.components().any(|c| {
+                                c.as_os_str() == utils::files::VAULT_DATA_DIR
+                                    || c.as_os_str() == ".git"
+                            })
+                        });
+                        if is_internal {
+                            return;
+                        }
+
+                        match event.kind {
+                            EventKind::Modify(ModifyKind::Name(RenameMode::Both))
+                                if event.paths.len() == 2 =>
+                            {
+                                let _ = tx.send(VaultChange::Renamed(
+                                    event.paths[0].clone(),
+                                    event.paths[1].clone(),
+                                ));
+                            }
+                            EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(_) => {
+                                for p in event.paths {
+                                    let _ = tx.send(VaultChange::Changed(p));
+                                }
+                            }
+                            _ => {}
+                        }
+                    }) {
+                        Ok(w) => w,
+                        Err(e) => {
+                            eprintln!("vault_watcher: failed to create watcher: {e}");
+                            return;
+                        }
+                    };
+
+                if let Err(e) = watcher.watch(&vault_path, RecursiveMode::Recursive) {
+                    eprintln!("vault_watcher: failed to watch {vault_path:?}: {e}");
+                    return;
+                }
+
+                // keep the watcher alive
+                let _watcher = watcher;
+
+                loop {
+                    let mut changes = match rx.recv().await {
+                        Some(change) => vec![change],
+                        None => return, // channel closed, exit the stream
+                    };
+
+                    while let Ok(Some(change)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+                        changes.push(change);
+                    }
+
+                    let _ = output.send(Message::VaultChanged(changes)).await;
+                }
+            },
+        )
+    })
+}
+
 /// Custom Text Editor Key Bindings
 fn text_editor_key_bindings(key_press: text_editor::KeyPress) -> Option<text_editor::Binding<Message>> {
     use cosmic::iced::keyboard::{key::Named, Key};
@@ -1449,6 +2812,16 @@ pub fn search_input_id() -> widget::Id {
     widget::Id::new("search_input")
 }
 
+/// Returns the replace input id
+pub fn replace_input_id() -> widget::Id {
+    widget::Id::new("replace_input")
+}
+
+/// Returns the vault search input id
+pub fn vault_search_input_id() -> widget::Id {
+    widget::Id::new("vault_search_input")
+}
+
 /// Creates the default panes for the app
 fn create_default_panes() -> pane_grid::State<PaneContent> {
     let (mut panes, first_pane) = pane_grid::State::new(PaneContent::Editor);
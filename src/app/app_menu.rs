@@ -22,6 +22,14 @@ pub enum MenuAction {
     NewVaultFolder,
     /// Save the current file
     SaveFile,
+    /// Pick an existing folder to add as a vault
+    AddVault,
+    /// Close the current tab
+    CloseTab,
+    /// Switch to the next tab
+    NextTab,
+    /// Switch to the previous tab
+    PreviousTab,
     /// Toggle the preview for the current file
     TogglePreview,
     /// Undo
@@ -30,6 +38,16 @@ pub enum MenuAction {
     Redo,
     /// Search
     Search,
+    /// Search in all the vault files
+    FindInVault,
+    /// Open the Backlinks [`ContextPage`] of the application
+    Backlinks,
+    /// Open the File History [`ContextPage`] of the application
+    FileHistory,
+    /// Open the Trash [`ContextPage`] of the application
+    Trash,
+    /// Open the Git [`ContextPage`] of the application
+    Git,
     /// Close the current open popup dialog
     CloseCurrentDialog,
 }
@@ -46,10 +64,19 @@ impl menu::action::MenuAction for MenuAction {
             MenuAction::NewVaultFile => Message::MenuAction(MenuAction::NewVaultFile),
             MenuAction::NewVaultFolder => Message::MenuAction(MenuAction::NewVaultFolder),
             MenuAction::SaveFile => Message::MenuAction(MenuAction::SaveFile),
+            MenuAction::AddVault => Message::MenuAction(MenuAction::AddVault),
+            MenuAction::CloseTab => Message::MenuAction(MenuAction::CloseTab),
+            MenuAction::NextTab => Message::MenuAction(MenuAction::NextTab),
+            MenuAction::PreviousTab => Message::MenuAction(MenuAction::PreviousTab),
             MenuAction::TogglePreview => Message::MenuAction(MenuAction::TogglePreview),
             MenuAction::Undo => Message::MenuAction(MenuAction::Undo),
             MenuAction::Redo => Message::MenuAction(MenuAction::Redo),
             MenuAction::Search => Message::MenuAction(MenuAction::Search),
+            MenuAction::FindInVault => Message::MenuAction(MenuAction::FindInVault),
+            MenuAction::Backlinks => Message::MenuAction(MenuAction::Backlinks),
+            MenuAction::FileHistory => Message::MenuAction(MenuAction::FileHistory),
+            MenuAction::Trash => Message::MenuAction(MenuAction::Trash),
+            MenuAction::Git => Message::MenuAction(MenuAction::Git),
             MenuAction::CloseCurrentDialog => Message::MenuAction(MenuAction::CloseCurrentDialog),
         }
     }
@@ -66,8 +93,11 @@ pub fn menu_bar<'a>(key_binds: &HashMap<KeyBind, MenuAction>) -> Element<'a, Mes
                     menu::Item::Button(fl!("new-folder"), None, MenuAction::NewVaultFolder),
                     menu::Item::Button(fl!("open-file"), None, MenuAction::OpenFile),
                     menu::Item::Button(fl!("save-file"), None, MenuAction::SaveFile),
+                    menu::Item::Button(fl!("close-tab"), None, MenuAction::CloseTab),
                     menu::Item::Divider,
                     menu::Item::Button(fl!("new-file"), None, MenuAction::NewFile),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("add-vault"), None, MenuAction::AddVault),
                 ],
             ),
         ),
@@ -79,6 +109,7 @@ pub fn menu_bar<'a>(key_binds: &HashMap<KeyBind, MenuAction>) -> Element<'a, Mes
                     menu::Item::Button(fl!("undo"), None, MenuAction::Undo),
                     menu::Item::Button(fl!("redo"), None, MenuAction::Redo),
                     menu::Item::Button(fl!("search"), None, MenuAction::Search),
+                    menu::Item::Button(fl!("find-in-vault"), None, MenuAction::FindInVault),
                 ],
             ),
         ),
@@ -87,6 +118,14 @@ pub fn menu_bar<'a>(key_binds: &HashMap<KeyBind, MenuAction>) -> Element<'a, Mes
             menu::items(
                 key_binds,
                 vec![
+                    menu::Item::Button(fl!("next-tab"), None, MenuAction::NextTab),
+                    menu::Item::Button(fl!("previous-tab"), None, MenuAction::PreviousTab),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("backlinks"), None, MenuAction::Backlinks),
+                    menu::Item::Button(fl!("file-history"), None, MenuAction::FileHistory),
+                    menu::Item::Button(fl!("trash"), None, MenuAction::Trash),
+                    menu::Item::Button(fl!("git"), None, MenuAction::Git),
+                    menu::Item::Divider,
                     menu::Item::Button(fl!("about"), None, MenuAction::About),
                     menu::Item::Button(fl!("settings"), None, MenuAction::Settings),
                 ],
@@ -15,6 +15,16 @@ pub enum ContextPage {
     About,
     /// Settings [`ContextPage`] of the application
     Settings,
+    /// Find in Vault [`ContextPage`] of the application
+    VaultSearch,
+    /// Backlinks [`ContextPage`] of the application, lists the notes linking to the open file
+    Backlinks,
+    /// File History [`ContextPage`] of the application, lists the snapshots of the open file
+    FileHistory,
+    /// Trash [`ContextPage`] of the application, lists the files and folders deleted from the vault
+    Trash,
+    /// Git [`ContextPage`] of the application, stages and commits the changes of the vault
+    Git,
 }
 
 impl ContextPage {
@@ -35,6 +45,31 @@ impl ContextPage {
                 Message::ToggleContextPage(ContextPage::Settings),
             )
             .title(fl!("settings")),
+            ContextPage::VaultSearch => context_drawer::context_drawer(
+                app_model.vault_search(),
+                Message::ToggleContextPage(ContextPage::VaultSearch),
+            )
+            .title(fl!("find-in-vault")),
+            ContextPage::Backlinks => context_drawer::context_drawer(
+                app_model.backlinks(),
+                Message::ToggleContextPage(ContextPage::Backlinks),
+            )
+            .title(fl!("backlinks")),
+            ContextPage::FileHistory => context_drawer::context_drawer(
+                app_model.file_history(),
+                Message::ToggleContextPage(ContextPage::FileHistory),
+            )
+            .title(fl!("file-history")),
+            ContextPage::Trash => context_drawer::context_drawer(
+                app_model.trash(),
+                Message::ToggleContextPage(ContextPage::Trash),
+            )
+            .title(fl!("trash")),
+            ContextPage::Git => context_drawer::context_drawer(
+                app_model.git(),
+                Message::ToggleContextPage(ContextPage::Git),
+            )
+            .title(fl!("git")),
         })
     }
 
@@ -4,4 +4,5 @@ pub mod editor;
 pub mod history;
 pub mod preview;
 pub mod project;
+pub mod tabs;
 pub mod utils;
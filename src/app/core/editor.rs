@@ -4,16 +4,22 @@ use cosmic::widget::text_editor::{Cursor, Position};
 use widgets::text_editor;
 
 use crate::app::core::{
-    history::HistoryState, preview::MarkdownPreview, utils::search::SearchMatch,
+    history::HistoryState,
+    preview::MarkdownPreview,
+    utils::search::{self, SearchMatch, SearchRange},
 };
 
 pub struct EditorState {
+    /// Identifies the document while it's open (it never changes), set when it's opened in a tab
+    pub id: u64,
     /// Current if/any file path
     pub path: Option<PathBuf>,
     /// Text Editor Content
     pub content: text_editor::Content,
     /// Track if any changes have been made to the current file
     pub is_dirty: bool,
+    /// Content of the file when it was last loaded/saved, the base to merge changes made outside of Cedilla
+    pub saved_content: String,
     /// Allows us to undo and redo
     pub history: HistoryState,
     /// Holds state about the scrollbars/scrolling of the editor
@@ -22,6 +28,8 @@ pub struct EditorState {
     pub search: EditorSearchState,
     /// Helper field to not open the external changes dialog when we save a file, move, delete...
     pub ignore_next_external_change: bool,
+    /// The file was deleted (or moved somewhere we can't follow) by another program, saving asks where to
+    pub is_orphaned: bool,
 }
 
 /// Allows us to correctly follow the cursor with the scrollbar
@@ -33,6 +41,10 @@ pub struct EditorScrollState {
     pub pending_preview_scrolls: u32,
     // we need this becasue the viewport get's calculated before images load-in then there's a weird jump in the preview
     pub last_preview_content_height: f32,
+    /// Scroll to the cursor as soon as we know the editor viewport (used when opening a file at a given line)
+    pub reveal_cursor: bool,
+    /// Editor and preview offsets to scroll to the first time the document is shown (used when restoring a session)
+    pub restore_offsets: Option<(f32, f32)>,
 }
 
 #[derive(Default)]
@@ -43,6 +55,18 @@ pub struct EditorSearchState {
     pub search_value: String,
     /// Wether to use regex or not for searching
     pub use_regex: bool,
+    /// Wether to ignore case when searching
+    pub case_insensitive: bool,
+    /// Wether to only match whole words
+    pub whole_word: bool,
+    /// Wether to only search inside `selection_range`
+    pub in_selection: bool,
+    /// Selection the search is restricted to, captured when enabling `in_selection`
+    pub selection_range: Option<SearchRange>,
+    /// Controls wether the replace field is shown or hidden
+    pub show_replace: bool,
+    /// State of the replace field
+    pub replace_value: String,
     /// Matches found (if any)
     pub matches: Vec<SearchMatch>,
     /// Contains the current match index
@@ -147,9 +171,162 @@ impl EditorState {
         self.content.move_to(m.into());
     }
 
-    /// Returns true if it's a vault path with any modification or if it's a new file with any content
+    /// Returns the range of the current selection (if anything is selected)
+    pub fn selection_range(&self) -> Option<SearchRange> {
+        let cursor = self.content.cursor();
+        let selection = cursor.selection?;
+
+        let position = (cursor.position.line, cursor.position.column);
+        let selection = (selection.line, selection.column);
+        if position == selection {
+            return None;
+        }
+
+        Some(SearchRange {
+            start: position.min(selection),
+            end: position.max(selection),
+        })
+    }
+
+    /// Moves the end of the search selection range after editing its last line,
+    /// `old_len` is the length of that line before the edit
+    fn shift_selection_range(&mut self, old_len: usize) {
+        let Some(range) = self.search.selection_range.as_mut() else {
+            return;
+        };
+
+        let new_len = self
+            .content
+            .line(range.end.0)
+            .map(|l| l.text.len())
+            .unwrap_or(old_len);
+        range.end.1 = (range.end.1 + new_len).saturating_sub(old_len);
+    }
+
+    /// Length of the last line of the search selection range (if any)
+    fn selection_range_end_len(&self) -> Option<usize> {
+        let range = self.search.selection_range?;
+        self.content.line(range.end.0).map(|l| l.text.len())
+    }
+
+    /// Replaces the current search match with the replace value, returns true if something was replaced
+    pub fn replace_current_match(&mut self, preview: &mut MarkdownPreview) -> bool {
+        if self.search.search_value.is_empty() {
+            return false;
+        }
+
+        let Some(m) = self
+            .search
+            .current_match_index
+            .and_then(|i| self.search.matches.get(i))
+            .cloned()
+        else {
+            return false;
+        };
+
+        let Ok(matcher) = self.search.matcher() else {
+            return false;
+        };
+
+        let Some(line) = self.content.line(m.line).map(|l| l.text.to_string()) else {
+            return false;
+        };
+
+        let Some(replacement) = search::replacement_for_match(
+            &matcher,
+            &line,
+            &m,
+            &self.search.replace_value,
+            self.search.use_regex,
+        ) else {
+            return false;
+        };
+
+        let cursor_before = self.content.cursor().position;
+        let range_end_len = self
+            .search
+            .selection_range
+            .filter(|r| r.end.0 == m.line)
+            .and(self.selection_range_end_len());
+
+        self.navigate_to_match(&m);
+        if replacement.is_empty() {
+            self.content
+                .perform(text_editor::Action::Edit(text_editor::Edit::Delete));
+        } else {
+            self.content
+                .perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                    std::sync::Arc::new(replacement.clone()),
+                )));
+        }
+
+        let text = self.content.text();
+        preview.update_content(&text);
+        self.is_dirty = true;
+        self.push_history((cursor_before.line, cursor_before.column));
+        if let Some(old_len) = range_end_len {
+            self.shift_selection_range(old_len);
+        }
+
+        // continue with the first match after the replaced text so we never replace our own replacement
+        self.search.compute_matches(&text);
+        let replaced_end = m.col_start + replacement.len();
+        self.search.current_match_index = (!self.search.matches.is_empty()).then(|| {
+            self.search
+                .matches
+                .iter()
+                .position(|n| (n.line, n.col_start) >= (m.line, replaced_end))
+                .unwrap_or(0)
+        });
+
+        true
+    }
+
+    /// Replaces every search match with the replace value as a single undo step, returns the number of replacements
+    pub fn replace_all_matches(&mut self, preview: &mut MarkdownPreview) -> usize {
+        if self.search.search_value.is_empty() {
+            return 0;
+        }
+
+        let Ok(matcher) = self.search.matcher() else {
+            return 0;
+        };
+
+        let (new_text, lines) = search::replace_in_text(
+            &matcher,
+            &self.content.text(),
+            &self.search.replace_value,
+            self.search.use_regex,
+            self.search
+                .selection_range
+                .filter(|_| self.search.in_selection),
+        );
+
+        let count = lines.iter().map(|l| l.count).sum();
+        if count == 0 {
+            return 0;
+        }
+
+        let cursor_before = self.content.cursor().position;
+        let range_end_len = self.selection_range_end_len();
+
+        self.content.replace_text(&new_text);
+        self.restore_cursor(cursor_before.line, cursor_before.column);
+        preview.update_content(&new_text);
+        self.is_dirty = true;
+        self.push_history((cursor_before.line, cursor_before.column));
+        if let Some(old_len) = range_end_len {
+            self.shift_selection_range(old_len);
+        }
+
+        self.search.compute_matches(&new_text);
+
+        count
+    }
+
+    /// Returns true if it's a vault path with any modification (or deleted on disk) or if it's a new file with any content
     pub fn needs_confirmation(&self) -> bool {
-        (self.path.is_some() && self.history.history_index != 0)
+        (self.path.is_some() && (self.history.history_index != 0 || self.is_orphaned))
             || (self.path.is_none() && !self.content.text().trim().is_empty())
     }
 
@@ -202,7 +379,7 @@ impl EditorState {
         }
     }
 
-    fn restore_cursor(&mut self, line: usize, column: usize) {
+    pub fn restore_cursor(&mut self, line: usize, column: usize) {
         let line_count = self.content.line_count();
         let safe_line = line.min(line_count.saturating_sub(1));
 
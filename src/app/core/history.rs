@@ -1,4 +1,15 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anywho::anywho;
+use cosmic::Application;
 use dissimilar::Chunk;
+use serde::{Deserialize, Serialize};
+
+use crate::app::AppModel;
+use crate::app::core::utils::files;
 
 /// Holds the state of the File History
 #[derive(Default)]
@@ -51,3 +62,242 @@ pub fn apply_single(text: &str, patch: &[Chunk<'static>]) -> String {
     }
     result
 }
+
+/// Piece of a stored patch, unchanged text is stored as its length (in bytes) to keep the store small
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum StoredChunk {
+    Equal(usize),
+    Insert(String),
+    Delete(String),
+}
+
+/// Undo history of a file as it's persisted on disk
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoredHistory {
+    pub path: PathBuf,
+    /// Hash of the file content the history leads to, if the file changes outside of Cedilla the history is not used
+    pub content_hash: u64,
+    /// Unix timestamp (in seconds) of when the history was stored
+    pub saved_at: u64,
+    pub base: String,
+    pub patches: Vec<Vec<StoredChunk>>,
+    pub index: usize,
+    pub cursor_before: Vec<(usize, usize)>,
+    pub cursor_after: Vec<(usize, usize)>,
+}
+
+/// Limits of the persisted undo history
+#[derive(Clone, Copy, Debug)]
+pub struct HistoryLimits {
+    /// Stored histories older than this are discarded
+    pub max_age_days: u64,
+    /// The oldest changes are dropped until the stored history fits this size
+    pub max_size_kb: u64,
+}
+
+impl StoredHistory {
+    /// Snapshot of the given history of the file at `path` which currently has the content with the given hash
+    pub fn new(path: PathBuf, history: &HistoryState, content_hash: u64) -> Self {
+        let patches = history
+            .history_patches
+            .iter()
+            .map(|patch| {
+                patch
+                    .iter()
+                    .map(|chunk| match chunk {
+                        Chunk::Equal(s) => StoredChunk::Equal(s.len()),
+                        Chunk::Insert(s) => StoredChunk::Insert(s.to_string()),
+                        Chunk::Delete(s) => StoredChunk::Delete(s.to_string()),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            path,
+            content_hash,
+            saved_at: unix_now(),
+            base: history.history_base.clone(),
+            patches,
+            index: history.history_index,
+            cursor_before: history.cursor_before.clone(),
+            cursor_after: history.cursor_after.clone(),
+        }
+    }
+
+    /// Rebuilds the [`HistoryState`], `None` if the stored patches don't match their base
+    pub fn into_history(self) -> Option<HistoryState> {
+        let mut text = self.base.clone();
+        let mut history_patches = Vec::with_capacity(self.patches.len());
+
+        for stored in self.patches {
+            let mut patch = Vec::with_capacity(stored.len());
+            let mut pos = 0;
+
+            for chunk in stored {
+                patch.push(match chunk {
+                    StoredChunk::Equal(len) => {
+                        let s = text.get(pos..pos + len)?;
+                        pos += len;
+                        Chunk::Equal(Box::leak(s.to_string().into_boxed_str()))
+                    }
+                    StoredChunk::Insert(s) => Chunk::Insert(Box::leak(s.into_boxed_str())),
+                    StoredChunk::Delete(s) => {
+                        pos += s.len();
+                        Chunk::Delete(Box::leak(s.into_boxed_str()))
+                    }
+                });
+            }
+
+            text = apply_single(&text, &patch);
+            history_patches.push(patch);
+        }
+
+        if self.index > history_patches.len()
+            || self.cursor_before.len() != history_patches.len()
+            || self.cursor_after.len() != history_patches.len()
+        {
+            return None;
+        }
+
+        Some(HistoryState {
+            history_base: self.base,
+            history_patches,
+            history_index: self.index,
+            cursor_before: self.cursor_before,
+            cursor_after: self.cursor_after,
+        })
+    }
+
+    /// Drops the oldest changes (and the redo ones if needed) until it fits in `max_bytes`
+    fn shrink_to(&mut self, max_bytes: usize) -> Result<Vec<u8>, anywho::Error> {
+        loop {
+            let bytes =
+                serde_json::to_vec(self).map_err(|e| anywho!("Failed to encode history: {}", e))?;
+
+            if bytes.len() <= max_bytes || self.patches.is_empty() {
+                return Ok(bytes);
+            }
+
+            if self.index > 0 {
+                // advance the base by applying the oldest patch
+                let oldest = self.patches.remove(0);
+                self.base = apply_stored(&self.base, &oldest)
+                    .ok_or_else(|| anywho!("Stored history doesn't match its base"))?;
+                self.cursor_before.remove(0);
+                self.cursor_after.remove(0);
+                self.index -= 1;
+            } else {
+                self.patches.pop();
+                self.cursor_before.pop();
+                self.cursor_after.pop();
+            }
+        }
+    }
+}
+
+/// Compute a stored patch (unchanged text as lengths) from `old` → `new`
+pub fn stored_patch(old: &str, new: &str) -> Vec<StoredChunk> {
+    dissimilar::diff(old, new)
+        .into_iter()
+        .map(|chunk| match chunk {
+            Chunk::Equal(s) => StoredChunk::Equal(s.len()),
+            Chunk::Insert(s) => StoredChunk::Insert(s.to_string()),
+            Chunk::Delete(s) => StoredChunk::Delete(s.to_string()),
+        })
+        .collect()
+}
+
+/// Apply a stored patch to `text`, `None` if the patch doesn't match the text
+pub fn apply_stored(text: &str, patch: &[StoredChunk]) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut pos = 0;
+
+    for chunk in patch {
+        match chunk {
+            StoredChunk::Equal(len) => {
+                result.push_str(text.get(pos..pos + len)?);
+                pos += len;
+            }
+            StoredChunk::Insert(s) => result.push_str(s),
+            StoredChunk::Delete(s) => pos += s.len(),
+        }
+    }
+
+    Some(result)
+}
+
+/// Directory where the undo histories are stored
+fn history_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap()
+        .join(AppModel::APP_ID)
+        .join("undo")
+}
+
+/// File of the stored history of the given path
+fn history_file(path: &Path) -> PathBuf {
+    let hash = files::stable_hash(path.as_os_str().as_encoded_bytes());
+    history_dir().join(format!("{hash:016x}.json"))
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Writes the given history to the store (respecting the given limits) and removes the expired ones
+pub async fn store_history(
+    mut history: StoredHistory,
+    limits: HistoryLimits,
+) -> Result<(), anywho::Error> {
+    let file = history_file(&history.path);
+    let bytes = history.shrink_to(limits.max_size_kb as usize * 1024)?;
+
+    tokio::fs::create_dir_all(history_dir())
+        .await
+        .map_err(|e| anywho!("Failed to create history directory: {}", e))?;
+    tokio::fs::write(&file, bytes)
+        .await
+        .map_err(|e| anywho!("Failed to write history: {}", e))?;
+
+    prune_histories(limits).await;
+    Ok(())
+}
+
+/// Loads the stored history of the given path if the file hasn't changed since it was stored
+pub async fn load_history(
+    path: PathBuf,
+    content_hash: u64,
+    limits: HistoryLimits,
+) -> Option<StoredHistory> {
+    let bytes = tokio::fs::read(history_file(&path)).await.ok()?;
+    let history: StoredHistory = serde_json::from_slice(&bytes).ok()?;
+
+    let expired = unix_now().saturating_sub(history.saved_at) > limits.max_age_days * 24 * 60 * 60;
+    (history.path == path && history.content_hash == content_hash && !expired).then_some(history)
+}
+
+/// Removes the stored histories older than the age limit
+async fn prune_histories(limits: HistoryLimits) {
+    let Ok(mut entries) = tokio::fs::read_dir(history_dir()).await else {
+        return;
+    };
+
+    let max_age = Duration::from_secs(limits.max_age_days * 24 * 60 * 60);
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let expired = entry
+            .metadata()
+            .await
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+
+        if expired {
+            let _ = tokio::fs::remove_file(entry.path()).await;
+        }
+    }
+}
@@ -4,22 +4,65 @@
 
 // Code based on System76's, see: https://github.com/pop-os/cosmic-edit/blob/master/src/project.rs
 
-use cosmic::widget::icon;
+use cosmic::{
+    Task,
+    iced::Point,
+    widget::{icon, segmented_button},
+};
 
 use std::{
     cmp::Ordering,
     fs, io,
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
 use crate::{
-    app::AppModel,
+    app::{
+        AppModel, Message,
+        core::utils::{self, files::VaultFilter},
+    },
     icons::{self},
 };
 
+/// How far the pointer has to move (with the button pressed) before a node starts being dragged
+const DRAG_THRESHOLD: f32 = 8.0;
+/// How long a node has to be dragged over a collapsed folder before it's expanded
+const DRAG_EXPAND_DELAY: Duration = Duration::from_millis(600);
+
+/// Actions related to dragging nodes of the navbar onto folders
+#[derive(Debug, Clone)]
+pub enum NavDragAction {
+    /// The pointer moved over the navbar (relative to its first node)
+    CursorMoved(Point),
+    /// The pointer left the navbar
+    CursorLeft,
+    /// The left mouse button has been pressed over the navbar
+    Pressed,
+    /// The left mouse button has been released anywhere in the app while a node is pressed
+    Released,
+    /// Sent periodically while dragging, to expand the hovered folder
+    Tick,
+}
+
+/// Node of the navbar being dragged
+pub struct NavDrag {
+    source: segmented_button::Entity,
+    /// Where the button was pressed
+    origin: Point,
+    /// False until the pointer moves far enough, it's just a click until then
+    pub dragging: bool,
+    /// Folder the node would be dropped on, and since when it's hovered
+    target: Option<(segmented_button::Entity, Instant)>,
+    /// The hovered folder is highlighted by activating it, this one is activated back afterwards
+    previous_active: segmented_button::Entity,
+}
+
 impl AppModel {
     pub fn open_vault_folder<P: AsRef<Path>>(&mut self, path: P) {
         let path = path.as_ref();
+        // resolved once here, the watcher subscription is rebuilt after every message
+        self.vault_watch_path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
 
         if let Ok(mut node) = ProjectNode::new(path) {
             if let ProjectNode::Folder { open, root, .. } = &mut node {
@@ -30,56 +73,95 @@ impl AppModel {
                 .nav_model
                 .insert()
                 .icon(node.icon(18))
-                .text("Cedilla Vault")
+                .text(self.config.vault_name())
                 .data(node)
                 .id();
 
             let position = self.nav_model.position(id).unwrap_or(0);
             self.open_folder(path, position + 1, 1);
         }
-    }
 
-    pub fn open_folder<P: AsRef<Path>>(&mut self, path: P, mut position: u16, indent: u16) {
-        let mut nodes = Vec::new();
-        for entry_res in ignore::WalkBuilder::new(&path)
-            .hidden(false)
-            .max_depth(Some(1))
-            .build()
-        {
-            let entry = match entry_res {
-                Ok(ok) => ok,
-                Err(_) => continue,
-            };
-            if entry.depth() == 0 {
-                continue;
-            }
-            if entry.file_type().is_some_and(|ft| ft.is_file()) {
-                match entry.path().extension().and_then(std::ffi::OsStr::to_str) {
-                    Some("md") | Some("txt") => {}
-                    _ => continue,
-                }
-            }
-            let node = match ProjectNode::new(entry.path()) {
-                Ok(ok) => ok,
-                Err(_) => continue,
-            };
-            nodes.push(node);
-        }
+        self.refresh_vault_notes();
+    }
 
-        nodes.sort();
+    /// Rebuilds the list of every note of the vault (used to resolve links between notes)
+    ///
+    /// It walks the whole vault, so the helpers changing the navbar leave it to the operation calling them
+    pub fn refresh_vault_notes(&mut self) {
+        self.vault_notes = utils::files::vault_notes(&self.vault_filter, &self.config.vault_path());
+    }
 
-        for node in nodes {
+    pub fn open_folder<P: AsRef<Path>>(&mut self, path: P, mut position: u16, indent: u16) {
+        for node in folder_nodes(&self.vault_filter, path) {
+            let icon = self.node_icon(&node);
             self.nav_model
                 .insert()
                 .position(position)
                 .indent(indent)
-                .icon(node.icon(18))
+                .icon(icon)
                 .text(node.name().to_string())
                 .data(node);
             position += 1;
         }
     }
 
+    /// Brings the children of the given folder in the navbar in sync with the disk (only if the folder is open)
+    pub fn sync_nav_folder(&mut self, folder_path: &PathBuf) {
+        let folder = self.nav_model.iter().find(|&id| {
+            matches!(
+                self.nav_model.data::<ProjectNode>(id),
+                Some(ProjectNode::Folder { path, open: true, .. }) if path == folder_path
+            )
+        });
+
+        let Some(folder) = folder else {
+            return;
+        };
+
+        let position = self.nav_model.position(folder).unwrap_or(0);
+        let indent = self.nav_model.indent(folder).unwrap_or(0);
+
+        // direct children currently in the navbar
+        let mut children = Vec::new();
+        let mut pos = position + 1;
+        while let Some(id) = self.nav_model.entity_at(pos) {
+            let child_indent = self.nav_model.indent(id).unwrap_or(0);
+            if child_indent <= indent {
+                break;
+            }
+            if child_indent == indent + 1
+                && let Some(ProjectNode::File { path, .. } | ProjectNode::Folder { path, .. }) =
+                    self.nav_model.data::<ProjectNode>(id)
+            {
+                children.push(path.clone());
+            }
+            pos += 1;
+        }
+
+        let on_disk = folder_nodes(&self.vault_filter, folder_path);
+
+        for child in &children {
+            let exists = on_disk.iter().any(|node| match node {
+                ProjectNode::File { path, .. } | ProjectNode::Folder { path, .. } => path == child,
+            });
+            if !exists {
+                self.remove_nav_node(child);
+            }
+        }
+
+        for node in on_disk {
+            match node {
+                ProjectNode::File { path, .. } if !children.contains(&path) => {
+                    self.insert_file_node(&path, folder_path);
+                }
+                ProjectNode::Folder { path, .. } if !children.contains(&path) => {
+                    self.insert_folder_node(&path, folder_path);
+                }
+                _ => {}
+            }
+        }
+    }
+
     pub fn insert_file_node(&mut self, file_path: &PathBuf, parent_dir: &PathBuf) {
         let Ok(node) = ProjectNode::new(file_path) else {
             return;
@@ -122,11 +204,12 @@ impl AppModel {
             (pos, indent)
         };
 
+        let icon = self.node_icon(&node);
         self.nav_model
             .insert()
             .position(insert_position)
             .indent(insert_indent)
-            .icon(node.icon(18))
+            .icon(icon)
             .text(node.name().to_string())
             .data(node);
     }
@@ -222,6 +305,7 @@ impl AppModel {
                 self.selected_nav_path = None;
             }
         }
+        self.nav_selection.retain(|p| !p.starts_with(target_path));
     }
 
     pub fn rename_nav_node(&mut self, old_path: &Path, new_path: &Path, new_name: &str) {
@@ -269,6 +353,15 @@ impl AppModel {
                 });
             }
         }
+        for selected in &mut self.nav_selection {
+            if let Ok(suffix) = selected.strip_prefix(old_path) {
+                *selected = if suffix.as_os_str().is_empty() {
+                    new_path.to_path_buf()
+                } else {
+                    new_path.join(suffix)
+                };
+            }
+        }
     }
 
     pub fn move_nav_node(
@@ -345,11 +438,12 @@ impl AppModel {
             return;
         };
 
+        let icon = self.node_icon(&node);
         self.nav_model
             .insert()
             .position(insert_position)
             .indent(target_indent + 1)
-            .icon(node.icon(18))
+            .icon(icon)
             .text(node.name().to_string())
             .data(node);
 
@@ -359,24 +453,275 @@ impl AppModel {
         }
     }
 
+    /// Icon of the given node in the navbar, files with git changes show their status instead
+    pub fn node_icon(&self, node: &ProjectNode) -> icon::Icon {
+        if self.nav_selection.iter().any(|p| p == node.path()) {
+            return icons::get_icon("object-select-symbolic", 18);
+        }
+
+        match node {
+            ProjectNode::File { path, .. } => match self.git.status(path) {
+                Some(status) => icons::get_icon(status.change.icon_name(), 18),
+                // attachments are opened with the default app
+                None if !self.vault_filter.is_note(path) => {
+                    icons::get_icon("text-x-generic-symbolic", 18)
+                }
+                None => node.icon(18),
+            },
+            ProjectNode::Folder { .. } => node.icon(18),
+        }
+    }
+
+    /// Updates the icons of the files of the navbar after the git status changed
+    pub fn update_git_decorations(&mut self) {
+        let ids: Vec<_> = self.nav_model.iter().collect();
+
+        for id in ids {
+            if let Some(node @ ProjectNode::File { .. }) = self.nav_model.data::<ProjectNode>(id) {
+                let icon = self.node_icon(node);
+                self.nav_model.icon_set(id, icon);
+            }
+        }
+    }
+
+    /// Adds the given node to the multi-selection of the navbar, or removes it if it was already selected
+    pub fn toggle_nav_selection(&mut self, id: segmented_button::Entity) {
+        let Some(path) = self.nav_node_path(id) else {
+            return;
+        };
+
+        match self.nav_selection.iter().position(|p| *p == path) {
+            Some(index) => {
+                self.nav_selection.remove(index);
+            }
+            None => self.nav_selection.push(path.clone()),
+        }
+        self.nav_selection_anchor = Some(path);
+        self.update_nav_icons();
+    }
+
+    /// Selects every node between the last clicked one and the given one (both included)
+    pub fn extend_nav_selection(&mut self, id: segmented_button::Entity) {
+        let anchor = self.nav_selection_anchor.as_ref().and_then(|anchor| {
+            self.nav_model
+                .iter()
+                .find(|&e| self.nav_node_path(e).as_ref() == Some(anchor))
+        });
+
+        let Some(anchor) = anchor else {
+            self.toggle_nav_selection(id);
+            return;
+        };
+
+        let (Some(from), Some(to)) = (self.nav_model.position(anchor), self.nav_model.position(id))
+        else {
+            return;
+        };
+
+        // the root node can't be selected
+        self.nav_selection = (from.min(to)..=from.max(to))
+            .filter_map(|position| self.nav_model.entity_at(position))
+            .filter(|&e| self.nav_model.indent(e).unwrap_or(0) > 0)
+            .filter_map(|e| self.nav_node_path(e))
+            .collect();
+        self.update_nav_icons();
+    }
+
+    /// Clears the multi-selection of the navbar, the given node becomes the start of the next Shift + click
+    pub fn clear_nav_selection(&mut self, id: segmented_button::Entity) {
+        self.nav_selection_anchor = self.nav_node_path(id);
+        if !self.nav_selection.is_empty() {
+            self.nav_selection.clear();
+            self.update_nav_icons();
+        }
+    }
+
+    /// Selected nodes the context menu of the given node acts on, `None` if it's not part of a multi-selection
+    ///
+    /// Nodes inside of another selected folder are left out, they go wherever their folder goes
+    pub fn nav_selection_for(&self, id: segmented_button::Entity) -> Option<Vec<PathBuf>> {
+        let path = self.nav_node_path(id)?;
+        if self.nav_selection.len() < 2 || !self.nav_selection.contains(&path) {
+            return None;
+        }
+
+        Some(
+            self.nav_selection
+                .iter()
+                .filter(|p| {
+                    !self
+                        .nav_selection
+                        .iter()
+                        .any(|other| other != *p && p.starts_with(other))
+                })
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Path of the given node of the navbar
+    pub fn nav_node_path(&self, id: segmented_button::Entity) -> Option<PathBuf> {
+        self.nav_model
+            .data::<ProjectNode>(id)
+            .map(|node| node.path().to_path_buf())
+    }
+
+    /// Updates the icons of every node of the navbar after the selection changed
+    fn update_nav_icons(&mut self) {
+        let ids: Vec<_> = self.nav_model.iter().collect();
+
+        for id in ids {
+            if let Some(node) = self.nav_model.data::<ProjectNode>(id) {
+                let icon = self.node_icon(node);
+                self.nav_model.icon_set(id, icon);
+            }
+        }
+    }
+
+    /// Height of every node of the navbar
+    pub fn nav_button_height(&self) -> u16 {
+        let space_xxxs = self.core.system_theme().cosmic().spacing.space_xxxs;
+        space_xxxs + 20 /* line height */ + space_xxxs
+    }
+
+    /// Node of the navbar at the given height (relative to its first node)
+    fn nav_entity_at(&self, y: f32) -> Option<segmented_button::Entity> {
+        if y < 0.0 {
+            return None;
+        }
+        self.nav_model
+            .entity_at((y / f32::from(self.nav_button_height())) as u16)
+    }
+
+    /// Opens the given folder of the navbar if it's collapsed
+    pub fn expand_nav_folder(&mut self, id: segmented_button::Entity) {
+        let Some(ProjectNode::Folder { open, .. }) = self.nav_model.data_mut::<ProjectNode>(id)
+        else {
+            return;
+        };
+        if *open {
+            return;
+        }
+        *open = true;
+
+        let Some(node) = self.nav_model.data::<ProjectNode>(id).cloned() else {
+            return;
+        };
+        let icon = self.node_icon(&node);
+        self.nav_model.icon_set(id, icon);
+
+        let position = self.nav_model.position(id).unwrap_or(0);
+        let indent = self.nav_model.indent(id).unwrap_or(0);
+        self.open_folder(node.path(), position + 1, indent + 1);
+    }
+
+    /// Drags files and folders of the navbar onto other folders, moving them there when dropped
+    pub fn handle_nav_drag(&mut self, action: NavDragAction) -> Task<cosmic::Action<Message>> {
+        match action {
+            NavDragAction::CursorMoved(position) => {
+                self.nav_cursor = Some(position);
+
+                let target = self.nav_entity_at(position.y);
+                let Some(drag) = &mut self.nav_drag else {
+                    return Task::none();
+                };
+                if !drag.dragging && position.distance(drag.origin) < DRAG_THRESHOLD {
+                    return Task::none();
+                }
+                drag.dragging = true;
+
+                // nodes can only be dropped on folders, never on themselves nor inside of themselves
+                let source = drag.source;
+                let source_path = self.nav_node_path(source);
+                let target = target.filter(|&id| match self.nav_model.data::<ProjectNode>(id) {
+                    Some(ProjectNode::Folder { path, .. }) => source_path
+                        .as_ref()
+                        .is_some_and(|source| !path.starts_with(source)),
+                    _ => false,
+                });
+                self.set_nav_drag_target(target);
+            }
+            NavDragAction::CursorLeft => {
+                self.nav_cursor = None;
+                self.set_nav_drag_target(None);
+            }
+            NavDragAction::Pressed => {
+                // the root node can't be moved
+                self.nav_drag = self
+                    .nav_cursor
+                    .and_then(|origin| Some((self.nav_entity_at(origin.y)?, origin)))
+                    .filter(|(id, _)| self.nav_model.indent(*id).unwrap_or(0) > 0)
+                    .map(|(source, origin)| NavDrag {
+                        source,
+                        origin,
+                        dragging: false,
+                        target: None,
+                        previous_active: self.nav_model.active(),
+                    });
+            }
+            NavDragAction::Released => {
+                let Some(drag) = self.nav_drag.take() else {
+                    return Task::none();
+                };
+                if !drag.dragging {
+                    return Task::none();
+                }
+                self.nav_model.activate(drag.previous_active);
+
+                let Some(target_path) = drag
+                    .target
+                    .and_then(|(target, _)| self.nav_node_path(target))
+                else {
+                    return Task::none();
+                };
+
+                // dragging a selected node moves the whole selection
+                return match self.nav_selection_for(drag.source) {
+                    Some(paths) => self.handle_move_nodes(paths, target_path),
+                    None => self.handle_move_node(drag.source, target_path),
+                };
+            }
+            NavDragAction::Tick => {
+                if let Some(NavDrag {
+                    target: Some((target, since)),
+                    ..
+                }) = self.nav_drag
+                    && since.elapsed() >= DRAG_EXPAND_DELAY
+                {
+                    self.expand_nav_folder(target);
+                }
+            }
+        }
+
+        Task::none()
+    }
+
+    /// Highlights the folder the dragged node would be dropped on
+    fn set_nav_drag_target(&mut self, target: Option<segmented_button::Entity>) {
+        let Some(drag) = &mut self.nav_drag else {
+            return;
+        };
+        if drag.target.map(|(id, _)| id) == target {
+            return;
+        }
+
+        drag.target = target.map(|id| (id, Instant::now()));
+        self.nav_model
+            .activate(target.unwrap_or(drag.previous_active));
+    }
+
     pub fn selected_directory(&self) -> PathBuf {
         self.selected_nav_path
             .clone()
             .unwrap_or_else(|| self.config.vault_path())
     }
 
+    /// Every folder of the vault a node can be moved to, the given paths (and their children) are left out
     pub fn collect_all_folders(
         &self,
         vault_path: &PathBuf,
-        exclude_entity: cosmic::widget::segmented_button::Entity,
+        exclude_paths: &[PathBuf],
     ) -> Vec<(PathBuf, String, u16)> {
-        let exclude_path = self
-            .nav_model
-            .data::<ProjectNode>(exclude_entity)
-            .map(|n| match n {
-                ProjectNode::Folder { path, .. } | ProjectNode::File { path, .. } => path.clone(),
-            });
-
         let mut result = Vec::new();
 
         // root always first
@@ -398,7 +743,7 @@ impl AppModel {
             0u16,
         ));
 
-        self.collect_folders_recursive(vault_path, 1, &exclude_path, &mut result);
+        self.collect_folders_recursive(vault_path, 1, exclude_paths, &mut result);
         result
     }
 
@@ -406,11 +751,12 @@ impl AppModel {
         &self,
         dir: &PathBuf,
         indent: u16,
-        exclude_path: &Option<PathBuf>,
+        exclude_paths: &[PathBuf],
         result: &mut Vec<(PathBuf, String, u16)>,
     ) {
-        let mut nodes: Vec<PathBuf> = ignore::WalkBuilder::new(dir)
-            .hidden(false)
+        let mut nodes: Vec<PathBuf> = self
+            .vault_filter
+            .walker(dir)
             .max_depth(Some(1))
             .build()
             .filter_map(|e| e.ok())
@@ -426,10 +772,8 @@ impl AppModel {
         });
 
         for subdir in nodes {
-            if let Some(excl) = exclude_path {
-                if subdir == *excl || subdir.starts_with(excl) {
-                    continue;
-                }
+            if exclude_paths.iter().any(|excl| subdir.starts_with(excl)) {
+                continue;
             }
             let name = subdir
                 .file_name()
@@ -437,11 +781,36 @@ impl AppModel {
                 .unwrap_or("")
                 .to_string();
             result.push((subdir.clone(), name, indent - 1));
-            self.collect_folders_recursive(&subdir, indent + 1, exclude_path, result);
+            self.collect_folders_recursive(&subdir, indent + 1, exclude_paths, result);
         }
     }
 }
 
+/// Nodes of the entries (folders and shown files) directly inside of the given folder, sorted as shown in the navbar
+fn folder_nodes<P: AsRef<Path>>(filter: &VaultFilter, path: P) -> Vec<ProjectNode> {
+    let mut nodes = Vec::new();
+    for entry_res in filter.walker(path.as_ref()).max_depth(Some(1)).build() {
+        let entry = match entry_res {
+            Ok(ok) => ok,
+            Err(_) => continue,
+        };
+        if entry.depth() == 0 {
+            continue;
+        }
+        if entry.file_type().is_some_and(|ft| ft.is_file()) && !filter.is_shown_file(entry.path()) {
+            continue;
+        }
+        let node = match ProjectNode::new(entry.path()) {
+            Ok(ok) => ok,
+            Err(_) => continue,
+        };
+        nodes.push(node);
+    }
+
+    nodes.sort();
+    nodes
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ProjectNode {
     Folder {
@@ -502,6 +871,12 @@ impl ProjectNode {
             Self::File { name, .. } => name,
         }
     }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Folder { path, .. } | Self::File { path, .. } => path,
+        }
+    }
 }
 
 impl Ord for ProjectNode {
@@ -0,0 +1,515 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use cosmic::Task;
+use cosmic::iced::widget::scrollable::scroll_to;
+use cosmic::widget::{pane_grid, segmented_button};
+
+use crate::app::core::editor::EditorState;
+use crate::app::core::preview::MarkdownPreview;
+use crate::app::core::utils;
+use crate::app::{
+    AppModel, Message, PreviewState, State, create_default_panes, editor_scrollable_id,
+    preview_scrollable_id,
+};
+use crate::config::{Session, SessionFile, ShowState};
+use crate::fl;
+
+/// A document open in a tab, the document of the active tab lives in [`State::Ready`]
+/// while the rest are stored as data of their tab entity
+pub struct Document {
+    /// Holds the state for editor related fields
+    pub editor: EditorState,
+    /// Holds the state for preview related fields
+    pub preview: MarkdownPreview,
+}
+
+/// Title of the tab of the given editor
+pub fn tab_title(editor: &EditorState) -> String {
+    let name = editor
+        .path
+        .as_deref()
+        .and_then(Path::file_name)
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| fl!("new-file"));
+
+    let name = if editor.is_orphaned {
+        fl!("deleted-tab-title", name = name)
+    } else {
+        name
+    };
+
+    if editor.is_dirty && editor.needs_confirmation() {
+        format!("• {name}")
+    } else {
+        name
+    }
+}
+
+impl AppModel {
+    /// Shows the given [`Document`] in a new tab, the tab of an untouched new file is reused
+    /// and if the document path is already open its tab gets activated and it's content replaced
+    ///
+    /// Callers opening a file check [`Self::find_tab`] first, only reloading a file replaces its document
+    pub fn open_document(&mut self, mut document: Document) {
+        self.last_document_id += 1;
+        document.editor.id = self.last_document_id;
+
+        if let Some(path) = document.editor.path.clone()
+            && let Some(entity) = self.find_tab(&path)
+        {
+            let _ = self.activate_tab(entity);
+        }
+
+        let title = tab_title(&document.editor);
+
+        let State::Ready {
+            editor,
+            preview,
+            tabs,
+            ..
+        } = &mut self.state
+        else {
+            let mut tabs = segmented_button::SingleSelectModel::builder().build();
+            tabs.insert().text(title).closable().activate();
+
+            self.state = State::Ready {
+                editor: document.editor,
+                preview: document.preview,
+                panes: create_default_panes(),
+                preview_state: match self.config.last_preview_showstate {
+                    ShowState::Show => PreviewState::Shown,
+                    ShowState::Hide => PreviewState::Hidden,
+                },
+                tabs,
+            };
+            return;
+        };
+
+        let active = tabs.active();
+        let replace_active = tabs.position(active).is_none()
+            || editor.path == document.editor.path
+            || (editor.path.is_none() && !editor.needs_confirmation());
+
+        if replace_active {
+            *editor = document.editor;
+            *preview = document.preview;
+
+            if tabs.position(active).is_some() {
+                tabs.text_set(active, title);
+            } else {
+                tabs.insert().text(title).closable().activate();
+            }
+            return;
+        }
+
+        let previous = Document {
+            editor: std::mem::replace(editor, document.editor),
+            preview: std::mem::replace(preview, document.preview),
+        };
+        tabs.data_set(active, previous);
+
+        // new tabs are opened right after the active one
+        let position = tabs.position(active).unwrap_or(0) + 1;
+        tabs.insert()
+            .position(position)
+            .text(title)
+            .closable()
+            .activate();
+    }
+
+    /// Returns the tab entity of the document with the given path (if open)
+    pub fn find_tab(&self, path: &Path) -> Option<segmented_button::Entity> {
+        let State::Ready { editor, tabs, .. } = &self.state else {
+            return None;
+        };
+
+        if editor.path.as_deref() == Some(path) {
+            return Some(tabs.active());
+        }
+
+        tabs.iter().find(|&entity| {
+            tabs.data::<Document>(entity)
+                .is_some_and(|document| document.editor.path.as_deref() == Some(path))
+        })
+    }
+
+    /// Opens the given file in a new tab, or switches to its tab if it's already open
+    pub fn open_path(&mut self, path: PathBuf) -> Task<cosmic::Action<Message>> {
+        if let Some(entity) = self.find_tab(&path) {
+            return self.activate_tab(entity);
+        }
+
+        Task::perform(utils::files::load_file(path), |res| {
+            cosmic::action::app(Message::OpenFile(res))
+        })
+    }
+
+    /// Swaps the active document with the document of the given tab
+    pub fn activate_tab(
+        &mut self,
+        entity: segmented_button::Entity,
+    ) -> Task<cosmic::Action<Message>> {
+        let State::Ready {
+            editor,
+            preview,
+            tabs,
+            ..
+        } = &mut self.state
+        else {
+            return Task::none();
+        };
+
+        let active = tabs.active();
+        if active == entity {
+            return Task::none();
+        }
+
+        let Some(document) = tabs.data_remove::<Document>(entity) else {
+            return Task::none();
+        };
+
+        let previous = Document {
+            editor: std::mem::replace(editor, document.editor),
+            preview: std::mem::replace(preview, document.preview),
+        };
+        tabs.data_set(active, previous);
+        tabs.activate(entity);
+
+        restore_scroll(editor).chain(self.refresh_backlinks())
+    }
+
+    /// Activates the tab at the given offset of the active one (wrapping around)
+    pub fn cycle_tab(&mut self, offset: isize) -> Task<cosmic::Action<Message>> {
+        let State::Ready { tabs, .. } = &self.state else {
+            return Task::none();
+        };
+
+        let len = tabs.iter().count() as isize;
+        if len < 2 {
+            return Task::none();
+        }
+
+        let position = tabs.position(tabs.active()).unwrap_or(0) as isize;
+        let target = (position + offset).rem_euclid(len) as u16;
+
+        match tabs.entity_at(target) {
+            Some(entity) => self.activate_tab(entity),
+            None => Task::none(),
+        }
+    }
+
+    /// Closes the active tab without asking, the closest tab gets activated (or a new file is created)
+    pub fn close_active_tab(&mut self) -> Task<cosmic::Action<Message>> {
+        let State::Ready {
+            editor,
+            preview,
+            tabs,
+            ..
+        } = &mut self.state
+        else {
+            return Task::none();
+        };
+
+        let active = tabs.active();
+        let position = tabs.position(active).unwrap_or(0);
+        tabs.remove(active);
+
+        let next = tabs
+            .entity_at(position)
+            .or_else(|| position.checked_sub(1).and_then(|p| tabs.entity_at(p)));
+
+        let Some(next) = next else {
+            return self.handle_new_file();
+        };
+
+        let Some(document) = tabs.data_remove::<Document>(next) else {
+            return Task::none();
+        };
+
+        *editor = document.editor;
+        *preview = document.preview;
+        tabs.activate(next);
+
+        restore_scroll(editor).chain(self.refresh_backlinks())
+    }
+
+    /// Closes the given tab without asking, active or not
+    pub fn close_tab(&mut self, entity: segmented_button::Entity) -> Task<cosmic::Action<Message>> {
+        let State::Ready { tabs, .. } = &mut self.state else {
+            return Task::none();
+        };
+
+        if tabs.active() == entity {
+            return self.close_active_tab();
+        }
+
+        tabs.remove(entity);
+        Task::none()
+    }
+
+    /// Closes (without asking) the tabs of the documents inside of the given path
+    pub fn close_tabs_under(&mut self, path: &Path) -> Task<cosmic::Action<Message>> {
+        let State::Ready { editor, tabs, .. } = &mut self.state else {
+            return Task::none();
+        };
+
+        let inactive: Vec<segmented_button::Entity> = tabs
+            .iter()
+            .filter(|&entity| {
+                tabs.data::<Document>(entity).is_some_and(|document| {
+                    document
+                        .editor
+                        .path
+                        .as_deref()
+                        .is_some_and(|p| p.starts_with(path))
+                })
+            })
+            .collect();
+
+        for entity in inactive {
+            tabs.remove(entity);
+        }
+
+        if editor.path.as_deref().is_some_and(|p| p.starts_with(path)) {
+            self.close_active_tab()
+        } else {
+            Task::none()
+        }
+    }
+
+    /// Updates the path of every open document inside of `old_path` after it has been renamed/moved to `new_path`
+    pub fn rename_open_paths(&mut self, old_path: &Path, new_path: &Path) {
+        let State::Ready { editor, tabs, .. } = &mut self.state else {
+            return;
+        };
+
+        let renamed = |editor: &mut EditorState| {
+            if let Some(current) = editor.path.as_deref()
+                && let Ok(suffix) = current.strip_prefix(old_path)
+            {
+                editor.path = Some(if suffix.as_os_str().is_empty() {
+                    new_path.to_path_buf()
+                } else {
+                    new_path.join(suffix)
+                });
+                // it may have been marked as deleted before we learned where it went
+                editor.is_orphaned = false;
+            }
+        };
+
+        renamed(editor);
+        let entities: Vec<segmented_button::Entity> = tabs.iter().collect();
+        for entity in entities {
+            if let Some(document) = tabs.data_mut::<Document>(entity) {
+                renamed(&mut document.editor);
+                let title = tab_title(&document.editor);
+                tabs.text_set(entity, title);
+            }
+        }
+    }
+
+    /// Returns the tab of the first document with changes that would be lost, starting with the active one
+    pub fn first_unsaved_tab(&self) -> Option<segmented_button::Entity> {
+        let State::Ready { editor, tabs, .. } = &self.state else {
+            return None;
+        };
+
+        if editor.is_dirty && editor.needs_confirmation() {
+            return Some(tabs.active());
+        }
+
+        tabs.iter().find(|&entity| {
+            tabs.data::<Document>(entity).is_some_and(|document| {
+                document.editor.is_dirty && document.editor.needs_confirmation()
+            })
+        })
+    }
+
+    /// Path of the active document (if any)
+    pub fn active_path(&self) -> Option<PathBuf> {
+        let State::Ready { editor, .. } = &self.state else {
+            return None;
+        };
+
+        editor.path.clone()
+    }
+
+    /// Paths of every open document (to watch them for external changes)
+    pub fn open_paths(&self) -> Vec<PathBuf> {
+        let State::Ready { editor, tabs, .. } = &self.state else {
+            return Vec::new();
+        };
+
+        editor
+            .path
+            .iter()
+            .cloned()
+            .chain(tabs.iter().filter_map(|entity| {
+                tabs.data::<Document>(entity)
+                    .and_then(|document| document.editor.path.clone())
+            }))
+            .collect()
+    }
+
+    /// Text of every open document with a path, vault-wide changes use it instead of the content on disk
+    pub fn open_buffers(&self) -> HashMap<PathBuf, String> {
+        let State::Ready { editor, tabs, .. } = &self.state else {
+            return HashMap::new();
+        };
+
+        std::iter::once(editor)
+            .chain(
+                tabs.iter()
+                    .filter_map(|entity| tabs.data::<Document>(entity))
+                    .map(|document| &document.editor),
+            )
+            .filter_map(|editor| {
+                editor
+                    .path
+                    .clone()
+                    .map(|path| (path, editor.content.text()))
+            })
+            .collect()
+    }
+
+    /// Replaces the text of the document of the given tab (as a change that can be undone),
+    /// returns false and leaves it untouched if its text is no longer `original`
+    pub fn replace_tab_text(
+        &mut self,
+        entity: segmented_button::Entity,
+        original: &str,
+        text: &str,
+    ) -> bool {
+        let State::Ready {
+            editor,
+            preview,
+            tabs,
+            ..
+        } = &mut self.state
+        else {
+            return false;
+        };
+
+        let (editor, preview) = if tabs.active() == entity {
+            (editor, preview)
+        } else {
+            match tabs.data_mut::<Document>(entity) {
+                Some(document) => (&mut document.editor, &mut document.preview),
+                None => return false,
+            }
+        };
+
+        if editor.content.text() != original {
+            return false;
+        }
+
+        let cursor_before = editor.content.cursor().position;
+        editor.content.replace_text(text);
+        editor.restore_cursor(cursor_before.line, cursor_before.column);
+        preview.update_content(text);
+        editor.is_dirty = true;
+        editor.push_history((cursor_before.line, cursor_before.column));
+        if editor.search.clear_selection_range() {
+            editor.search.compute_matches(text);
+        }
+
+        let title = tab_title(editor);
+        tabs.text_set(entity, title);
+        true
+    }
+
+    /// Returns the current working session (unsaved new files are left out)
+    pub fn current_session(&self) -> Option<Session> {
+        let State::Ready {
+            editor,
+            panes,
+            tabs,
+            ..
+        } = &self.state
+        else {
+            return None;
+        };
+
+        let active = tabs.active();
+        let files = tabs
+            .iter()
+            .filter_map(|entity| {
+                let editor = if entity == active {
+                    editor
+                } else {
+                    &tabs.data::<Document>(entity)?.editor
+                };
+
+                let cursor = editor.content.cursor().position;
+                let (editor_scroll, preview_scroll) = scroll_offsets(editor);
+
+                Some(SessionFile {
+                    path: editor.path.clone()?,
+                    cursor: (cursor.line, cursor.column),
+                    editor_scroll,
+                    preview_scroll,
+                })
+            })
+            .collect();
+
+        let split_ratio = match panes.layout() {
+            pane_grid::Node::Split { ratio, .. } => Some(*ratio),
+            pane_grid::Node::Pane(_) => None,
+        };
+
+        Some(Session {
+            files,
+            active: editor.path.clone(),
+            split_ratio,
+        })
+    }
+
+    /// Keeps the title of the active tab in sync with its document (name & dirty state)
+    pub fn sync_active_tab_title(&mut self) {
+        let State::Ready { editor, tabs, .. } = &mut self.state else {
+            return;
+        };
+
+        let active = tabs.active();
+        let title = tab_title(editor);
+        if tabs.text(active) != Some(title.as_str()) {
+            tabs.text_set(active, title);
+        }
+    }
+}
+
+/// Returns the editor and preview scroll offsets of the document when it was last shown
+fn scroll_offsets(editor: &EditorState) -> (f32, f32) {
+    if let Some(offsets) = editor.scroll.restore_offsets {
+        return offsets;
+    }
+
+    let editor_y = editor
+        .scroll
+        .last_editor_viewport
+        .map_or(0.0, |vp| vp.absolute_offset().y);
+    let preview_y = editor
+        .scroll
+        .last_preview_viewport
+        .map_or(0.0, |vp| vp.absolute_offset().y);
+
+    (editor_y, preview_y)
+}
+
+/// Scrolls the editor and the preview back to where they were when the document was last shown
+pub fn restore_scroll(editor: &mut EditorState) -> Task<cosmic::Action<Message>> {
+    let (editor_y, preview_y) = scroll_offsets(editor);
+    editor.scroll.restore_offsets = None;
+
+    // pre-absorb the programmatic scrolls we're about to fire
+    editor.scroll.pending_editor_scrolls += 1;
+    editor.scroll.pending_preview_scrolls += 1;
+
+    Task::batch([
+        scroll_to(editor_scrollable_id(), utils::scroll::abs(editor_y)).map(cosmic::action::app),
+        scroll_to(preview_scrollable_id(), utils::scroll::abs(preview_y)).map(cosmic::action::app),
+    ])
+}
@@ -1,12 +1,19 @@
 // SPDX-License-Identifier: GPL-3.0
 
 pub mod files;
+pub mod git;
 pub mod images;
+pub mod links;
 pub mod markdown;
+pub mod merge;
 pub mod pdf;
+pub mod recovery;
 pub mod scroll;
 pub mod search;
+pub mod snapshots;
+pub mod templates;
 mod toast;
+pub mod trash;
 
 pub use images::Image;
 pub use markdown::SelectionAction;
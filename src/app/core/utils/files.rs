@@ -1,8 +1,13 @@
 // SPDX-License-Identifier: GPL-3.0
 
+use crate::config::{BoolState, CedillaConfig, Session, VaultConfig};
 use anywho::anywho;
 use cosmic::dialog::{ashpd::desktop::file_chooser::SelectedFiles, file_chooser::FileFilter};
-use std::{path::PathBuf, sync::Arc};
+use ignore::overrides::{Override, OverrideBuilder};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 pub async fn load_file(path: PathBuf) -> Result<(PathBuf, Arc<String>), anywho::Error> {
     let decoded = percent_encoding::percent_decode_str(path.to_str().unwrap_or_default())
@@ -18,6 +23,28 @@ pub async fn load_file(path: PathBuf) -> Result<(PathBuf, Arc<String>), anywho::
     Ok((path, contents))
 }
 
+/// Loads the files of the given [`Session`], files that no longer exist (or can't be read) are left out of the returned session
+pub async fn load_session(mut session: Session) -> (Session, Vec<Arc<String>>) {
+    let mut files = Vec::with_capacity(session.files.len());
+    let mut contents = Vec::with_capacity(session.files.len());
+
+    for file in session.files {
+        match load_file(file.path.clone()).await {
+            Ok((_path, content)) => {
+                files.push(file);
+                contents.push(content);
+            }
+            Err(e) => eprintln!(
+                "Skipping {} from the last session: {e}",
+                file.path.display()
+            ),
+        }
+    }
+
+    session.files = files;
+    (session, contents)
+}
+
 pub async fn save_file(path: PathBuf, content: String) -> Result<PathBuf, anywho::Error> {
     let decoded = percent_encoding::percent_decode_str(path.to_str().unwrap_or_default())
         .decode_utf8()
@@ -31,6 +58,153 @@ pub async fn save_file(path: PathBuf, content: String) -> Result<PathBuf, anywho
     Ok(path)
 }
 
+/// Hidden folder inside of the vault where Cedilla keeps its own data (file history...)
+pub const VAULT_DATA_DIR: &str = ".cedilla";
+
+/// 64-bit FNV-1a hash of the given bytes, used to name the files we persist
+///
+/// Unlike the [`std::hash::DefaultHasher`] it never changes between builds
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Returns false for the [`VAULT_DATA_DIR`] so it's skipped when walking the vault
+pub fn is_not_vault_data(entry: &ignore::DirEntry) -> bool {
+    entry.file_name() != VAULT_DATA_DIR
+}
+
+/// Names of the vault settings file inside of the [`VAULT_DATA_DIR`] (the first one found is used)
+const VAULT_CONFIG_FILES: [&str; 3] = ["config", "config.toml", "config.json"];
+
+/// Returns true if the given path is the settings file of the given vault
+pub fn is_vault_config_file(vault_path: &Path, path: &Path) -> bool {
+    let data_dir = vault_path.join(VAULT_DATA_DIR);
+    VAULT_CONFIG_FILES
+        .iter()
+        .any(|name| path == data_dir.join(name))
+}
+
+/// Loads the settings stored in the given vault (if any) with the errors found in them
+pub fn load_vault_config(vault_path: &Path) -> (VaultConfig, Vec<String>) {
+    let data_dir = vault_path.join(VAULT_DATA_DIR);
+    let Some(path) = VAULT_CONFIG_FILES
+        .iter()
+        .map(|name| data_dir.join(name))
+        .find(|p| p.is_file())
+    else {
+        return (VaultConfig::default(), Vec::new());
+    };
+
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => return (VaultConfig::default(), vec![e.to_string()]),
+    };
+
+    // without an extension the content tells the format
+    let is_json = match path.extension().and_then(std::ffi::OsStr::to_str) {
+        Some(extension) => extension == "json",
+        None => content.trim_start().starts_with('{'),
+    };
+
+    match VaultConfig::parse(&content, is_json) {
+        Ok(result) => result,
+        Err(e) => (VaultConfig::default(), vec![e]),
+    }
+}
+
+/// Decides which files and folders of the vault are shown in the navbar, and which files are notes
+#[derive(Clone, Debug)]
+pub struct VaultFilter {
+    /// Extensions (lowercase and without the dot) of the files treated as notes
+    note_extensions: Vec<String>,
+    /// Files and folders left out of the vault (from the exclude patterns)
+    excludes: Override,
+    /// Leave out what `.gitignore` files ignore
+    respect_gitignore: bool,
+    /// Show the files that are not notes (they are opened with the default app)
+    show_attachments: bool,
+}
+
+impl VaultFilter {
+    /// Builds the filter of the current vault from the settings, with the errors of the invalid exclude patterns
+    pub fn new(config: &CedillaConfig) -> (Self, Vec<String>) {
+        let vault_path = config.vault_path();
+        // the navbar paths are canonical, patterns are matched relative to the vault
+        let root = std::fs::canonicalize(&vault_path).unwrap_or(vault_path);
+
+        let mut errors = Vec::new();
+        let mut builder = OverrideBuilder::new(root);
+        for pattern in config.exclude_patterns.iter().map(|p| p.trim()) {
+            if pattern.is_empty() {
+                continue;
+            }
+            // overrides are include patterns, `!` turns them into exclude ones
+            if let Err(e) = builder.add(&format!("!{pattern}")) {
+                errors.push(e.to_string());
+            }
+        }
+
+        let excludes = builder.build().unwrap_or_else(|e| {
+            errors.push(e.to_string());
+            Override::empty()
+        });
+
+        let filter = Self {
+            note_extensions: config
+                .note_extensions
+                .iter()
+                .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect(),
+            excludes,
+            respect_gitignore: config.respect_gitignore == BoolState::Yes,
+            show_attachments: config.show_attachments == BoolState::Yes,
+        };
+
+        (filter, errors)
+    }
+
+    /// Returns true if the given path has one of the extensions the vault treats as notes
+    pub fn is_note(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(std::ffi::OsStr::to_str)
+            .is_some_and(|ext| self.note_extensions.contains(&ext.to_lowercase()))
+    }
+
+    /// Returns true if the given file is shown in the navbar
+    pub fn is_shown_file(&self, path: &Path) -> bool {
+        self.show_attachments || self.is_note(path)
+    }
+
+    /// Walker over the given folder of the vault, skipping what is excluded
+    pub fn walker(&self, path: &Path) -> ignore::WalkBuilder {
+        let mut walker = ignore::WalkBuilder::new(path);
+        walker
+            .hidden(false)
+            .filter_entry(is_not_vault_data)
+            .overrides(self.excludes.clone())
+            .git_ignore(self.respect_gitignore)
+            .git_exclude(self.respect_gitignore)
+            .git_global(self.respect_gitignore)
+            // a vault doesn't need to be a git repository for its .gitignore to count
+            .require_git(false);
+        walker
+    }
+}
+
+/// Recursively collects every note inside the vault
+pub fn vault_notes(filter: &VaultFilter, vault_path: &Path) -> Vec<PathBuf> {
+    filter
+        .walker(vault_path)
+        .build()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()) && filter.is_note(e.path()))
+        .map(|e| e.into_path())
+        .collect()
+}
+
 pub async fn move_vault(new_path: PathBuf, old_path: PathBuf) -> Result<PathBuf, anywho::Error> {
     if !old_path.exists() {
         return Err(anywho!(
@@ -84,7 +258,7 @@ pub async fn move_vault(new_path: PathBuf, old_path: PathBuf) -> Result<PathBuf,
 }
 
 // Recursively copies a directory tree from `src` to `dst`.
-async fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) -> Result<(), anywho::Error> {
+pub async fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) -> Result<(), anywho::Error> {
     tokio::fs::create_dir_all(dst)
         .await
         .map_err(|e| anywho!("Failed to create directory {}: {}", dst.display(), e))?;
@@ -130,6 +304,46 @@ async fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) -> Result<(), anywho::
     Ok(())
 }
 
+/// Copies the given file or folder next to it as `name (copy).ext`, returns the path of the copy
+pub async fn duplicate_node(path: PathBuf) -> Result<PathBuf, anywho::Error> {
+    let destination = duplicate_path(&path);
+
+    if path.is_dir() {
+        copy_dir_recursive(&path, &destination).await?;
+    } else {
+        tokio::fs::copy(&path, &destination)
+            .await
+            .map_err(|e| anywho!("Failed to duplicate {}: {}", path.display(), e))?;
+    }
+
+    Ok(destination)
+}
+
+/// The first `name (copy).ext`, `name (copy 2).ext`... next to the given path that doesn't exist yet
+fn duplicate_path(path: &Path) -> PathBuf {
+    // folder names are kept whole, even if they contain a dot
+    let (stem, extension) = match (path.is_dir(), path.file_stem(), path.extension()) {
+        (false, Some(stem), Some(extension)) => (
+            stem.to_string_lossy().into_owned(),
+            format!(".{}", extension.to_string_lossy()),
+        ),
+        _ => (
+            path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            String::new(),
+        ),
+    };
+
+    let mut candidate = path.with_file_name(format!("{stem} (copy){extension}"));
+    let mut counter = 2;
+    while candidate.exists() {
+        candidate = path.with_file_name(format!("{stem} (copy {counter}){extension}"));
+        counter += 1;
+    }
+    candidate
+}
+
 /// Open a system dialog to select a markdown file, returns the selected file (if any)
 pub async fn open_markdown_file_picker() -> Option<String> {
     let result = SelectedFiles::open_file()
@@ -0,0 +1,410 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use anywho::anywho;
+use cosmic::Task;
+use dissimilar::Chunk;
+use widgets::text_editor::LineChange;
+
+use crate::app::Message;
+
+/// Max commits shown in the log of a file
+const MAX_LOG_ENTRIES: usize = 50;
+
+/// Actions related to the git integration
+#[derive(Debug, Clone)]
+pub enum GitAction {
+    /// Look again for the repository of the vault and load its status
+    Refresh,
+    /// The status of the repository has been loaded, `None` if the vault is not a git working tree
+    StatusLoaded(Result<Option<RepoStatus>, anywho::Error>),
+    /// The content in `HEAD` of the given file has been loaded, `None` if it has never been committed
+    HeadLoaded(PathBuf, Option<String>),
+    /// The log of the given file has been loaded
+    LogLoaded(PathBuf, Result<Vec<GitCommit>, anywho::Error>),
+    /// Stage the changes of the given file
+    Stage(PathBuf),
+    /// Unstage the changes of the given file
+    Unstage(PathBuf),
+    /// Stage every change of the repository
+    StageAll,
+    /// Update the value of the commit message field
+    UpdateCommitMessage(String),
+    /// Commit the staged changes with the current message
+    Commit,
+    /// Callback after staging or unstaging changes
+    Staged(Result<(), anywho::Error>),
+    /// Callback after committing, with the summary of the commit
+    Committed(Result<String, anywho::Error>),
+}
+
+/// Kind of change of a file of the repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    Modified,
+    /// Added to the index but never committed
+    New,
+    Untracked,
+}
+
+impl FileChange {
+    /// Icon shown for the file in the navbar
+    pub fn icon_name(&self) -> &'static str {
+        match self {
+            FileChange::Modified => "document-edit-symbolic",
+            FileChange::New => "document-new-symbolic",
+            FileChange::Untracked => "dialog-question-symbolic",
+        }
+    }
+}
+
+/// Status of a changed file of the repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStatus {
+    pub change: FileChange,
+    /// Some of the changes are staged
+    pub is_staged: bool,
+    /// Some of the changes are not staged
+    pub has_unstaged: bool,
+}
+
+/// Status of the repository the vault belongs to
+#[derive(Debug, Clone)]
+pub struct RepoStatus {
+    /// Root of the working tree
+    pub root: PathBuf,
+    /// Every changed file (by absolute path)
+    pub files: HashMap<PathBuf, FileStatus>,
+}
+
+/// A commit of the log of a file
+#[derive(Debug, Clone)]
+pub struct GitCommit {
+    pub hash: String,
+    pub author: String,
+    /// Unix timestamp (in seconds) of the commit
+    pub timestamp: u64,
+    pub summary: String,
+}
+
+/// Holds the state of the git integration
+#[derive(Default)]
+pub struct GitState {
+    /// Status of the repository, `None` if the vault is not a git working tree
+    pub repo: Option<RepoStatus>,
+    /// File whose content in `HEAD` has been loaded
+    pub head_target: Option<PathBuf>,
+    /// Content in `HEAD` of the `head_target`, `None` if it has never been committed
+    pub head: Option<String>,
+    /// Lines of the open file changed against `HEAD`
+    pub line_changes: Vec<(usize, LineChange)>,
+    /// File whose log has been loaded
+    pub log_target: Option<PathBuf>,
+    /// Log of the `log_target`, newest first
+    pub log: Vec<GitCommit>,
+    /// State of the commit message field
+    pub commit_message: String,
+    /// True while a command changing the repository is running
+    pub is_busy: bool,
+}
+
+impl GitState {
+    /// Status of the given file, `None` if it has no changes (or there is no repository)
+    pub fn status(&self, path: &Path) -> Option<&FileStatus> {
+        self.repo.as_ref()?.files.get(path)
+    }
+
+    /// Loads the status of the repository of the vault, sent back with [`GitAction::StatusLoaded`]
+    pub fn refresh(&mut self, vault_path: PathBuf) -> Task<cosmic::Action<Message>> {
+        Task::perform(load_status(vault_path), |res| {
+            cosmic::action::app(Message::Git(GitAction::StatusLoaded(res)))
+        })
+    }
+
+    /// Loads the content in `HEAD` of `target`, sent back with [`GitAction::HeadLoaded`]
+    pub fn load_head(&mut self, target: Option<PathBuf>) -> Task<cosmic::Action<Message>> {
+        if self.head_target != target {
+            self.head = None;
+            self.line_changes.clear();
+        }
+        self.head_target = target.clone();
+
+        let Some(target) = target.filter(|t| self.is_in_repo(t)) else {
+            self.head = None;
+            self.line_changes.clear();
+            return Task::none();
+        };
+
+        Task::perform(head_content(target.clone()), move |content| {
+            cosmic::action::app(Message::Git(GitAction::HeadLoaded(target.clone(), content)))
+        })
+    }
+
+    /// Loads the log of `target`, sent back with [`GitAction::LogLoaded`]
+    pub fn load_log(&mut self, target: Option<PathBuf>) -> Task<cosmic::Action<Message>> {
+        if self.log_target != target {
+            self.log.clear();
+        }
+        self.log_target = target.clone();
+
+        let (Some(repo), Some(target)) = (&self.repo, target.filter(|t| self.is_in_repo(t))) else {
+            return Task::none();
+        };
+
+        Task::perform(load_log(repo.root.clone(), target.clone()), move |res| {
+            cosmic::action::app(Message::Git(GitAction::LogLoaded(target.clone(), res)))
+        })
+    }
+
+    /// Computes the changed lines of the given content of `path` against its content in `HEAD`
+    pub fn update_line_changes(&mut self, path: Option<&Path>, content: &str) {
+        self.line_changes = match &self.head {
+            Some(head) if path.is_some() && self.head_target.as_deref() == path => {
+                line_changes(head, content)
+            }
+            _ => Vec::new(),
+        };
+    }
+
+    fn is_in_repo(&self, path: &Path) -> bool {
+        self.repo
+            .as_ref()
+            .is_some_and(|repo| path.starts_with(&repo.root))
+    }
+}
+
+/// Lines of `current` changed against `base`, a removal is marked on the line that follows it
+pub fn line_changes(base: &str, current: &str) -> Vec<(usize, LineChange)> {
+    // every distinct line is mapped to a char so the diff works line by line
+    let mut ids = HashMap::new();
+    let (Some(base), Some(current)) = (
+        encode_lines(base, &mut ids),
+        encode_lines(current, &mut ids),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut changes = Vec::new();
+    let mut line = 0;
+    // removed lines not yet paired with added ones (which makes them modified)
+    let mut removed = 0;
+
+    for chunk in dissimilar::diff(&base, &current) {
+        match chunk {
+            Chunk::Equal(lines) => {
+                if removed > 0 {
+                    changes.push((line, LineChange::Removed));
+                    removed = 0;
+                }
+                line += lines.chars().count();
+            }
+            Chunk::Delete(lines) => removed += lines.chars().count(),
+            Chunk::Insert(lines) => {
+                for _ in 0..lines.chars().count() {
+                    let change = if removed > 0 {
+                        removed -= 1;
+                        LineChange::Modified
+                    } else {
+                        LineChange::Added
+                    };
+                    changes.push((line, change));
+                    line += 1;
+                }
+            }
+        }
+    }
+
+    if removed > 0 {
+        changes.push((line, LineChange::Removed));
+    }
+
+    changes
+}
+
+/// Maps every line of `text` to the char of `ids` for that line, `None` if there are too many distinct lines
+fn encode_lines<'a>(text: &'a str, ids: &mut HashMap<&'a str, char>) -> Option<String> {
+    text.split('\n')
+        .map(|line| {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            match ids.get(line) {
+                Some(id) => Some(*id),
+                None => {
+                    // chars outside of the basic plane, so they never clash with the text
+                    let id = char::from_u32(0x10000 + ids.len() as u32)?;
+                    ids.insert(line, id);
+                    Some(id)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Runs git with the given arguments inside of `dir`, returns its output
+async fn git<I, S>(dir: &Path, args: I) -> Result<String, anywho::Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        // never wait for credentials, everything works against the local repository
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| anywho!("Failed to run git: {}", e))?;
+
+    if !output.status.success() {
+        return Err(anywho!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Finds the repository of the vault and loads its status, `None` if the vault is not a git working tree
+pub async fn load_status(vault_path: PathBuf) -> Result<Option<RepoStatus>, anywho::Error> {
+    // not a git working tree (or git is not installed)
+    let Ok(root) = git(&vault_path, ["rev-parse", "--show-toplevel"]).await else {
+        return Ok(None);
+    };
+    let root = PathBuf::from(root.trim());
+
+    let output = git(
+        &root,
+        ["status", "--porcelain=v1", "-z", "--untracked-files=all"],
+    )
+    .await?;
+
+    let mut files = HashMap::new();
+    let mut entries = output.split('\0');
+    while let Some(entry) = entries.next() {
+        // "XY path", X is the status of the index and Y the one of the working tree
+        let mut status = entry.chars();
+        let (Some(x), Some(y), Some(path)) = (status.next(), status.next(), entry.get(3..)) else {
+            continue;
+        };
+
+        // renames and copies are followed by their original path
+        if matches!(x, 'R' | 'C') {
+            entries.next();
+        }
+
+        let change = match (x, y) {
+            ('?', '?') => FileChange::Untracked,
+            ('!', '!') => continue,
+            ('A', _) => FileChange::New,
+            _ => FileChange::Modified,
+        };
+
+        files.insert(
+            root.join(path),
+            FileStatus {
+                change,
+                is_staged: !matches!(x, ' ' | '?'),
+                has_unstaged: !matches!(y, ' '),
+            },
+        );
+    }
+
+    Ok(Some(RepoStatus { root, files }))
+}
+
+/// Content of the given file in `HEAD`, `None` if it has never been committed
+pub async fn head_content(path: PathBuf) -> Option<String> {
+    let dir = path.parent()?;
+    let name = path.file_name()?.to_str()?;
+
+    let object = format!("HEAD:./{name}");
+    git(dir, ["show", object.as_str()]).await.ok()
+}
+
+/// Log of the given file, newest first
+pub async fn load_log(root: PathBuf, path: PathBuf) -> Result<Vec<GitCommit>, anywho::Error> {
+    let max_count = format!("--max-count={MAX_LOG_ENTRIES}");
+    let output = git(
+        &root,
+        [
+            OsStr::new("log"),
+            OsStr::new("--follow"),
+            OsStr::new(&max_count),
+            OsStr::new("--format=%h%x1f%an%x1f%at%x1f%s"),
+            OsStr::new("--"),
+            path.as_os_str(),
+        ],
+    )
+    .await?;
+
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\u{1f}');
+            Some(GitCommit {
+                hash: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                timestamp: fields.next()?.parse().ok()?,
+                summary: fields.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Stages the changes of the given file
+pub async fn stage(root: PathBuf, path: PathBuf) -> Result<(), anywho::Error> {
+    git(
+        &root,
+        [OsStr::new("add"), OsStr::new("--"), path.as_os_str()],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Stages every change of the repository
+pub async fn stage_all(root: PathBuf) -> Result<(), anywho::Error> {
+    git(&root, ["add", "--all"]).await?;
+    Ok(())
+}
+
+/// Unstages the changes of the given file
+pub async fn unstage(root: PathBuf, path: PathBuf) -> Result<(), anywho::Error> {
+    let restore = [
+        OsStr::new("restore"),
+        OsStr::new("--staged"),
+        OsStr::new("--"),
+        path.as_os_str(),
+    ];
+
+    // `restore` needs a `HEAD`, which a repository without commits doesn't have
+    if git(&root, restore).await.is_err() {
+        git(
+            &root,
+            [
+                OsStr::new("rm"),
+                OsStr::new("--cached"),
+                OsStr::new("--quiet"),
+                OsStr::new("--"),
+                path.as_os_str(),
+            ],
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Commits the staged changes, returns the short hash and summary of the new commit
+pub async fn commit(root: PathBuf, message: String) -> Result<String, anywho::Error> {
+    git(&root, ["commit", "--quiet", "-m", &message]).await?;
+    let summary = git(&root, ["log", "-1", "--format=%h %s"]).await?;
+    Ok(summary.trim().to_string())
+}
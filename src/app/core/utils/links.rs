@@ -0,0 +1,626 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::{Component, Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anywho::anywho;
+use cosmic::Task;
+use percent_encoding::{AsciiSet, CONTROLS};
+
+use crate::app::Message;
+use crate::app::core::utils::search::{
+    SNIPPET_MAX_CHARS, SearchMatch, VaultSearchMatch, VaultSearchResult,
+};
+
+// We do this so that we don't have to recompile the regex every time
+static MD_LINK_RE: OnceLock<regex::Regex> = OnceLock::new();
+static WIKI_LINK_RE: OnceLock<regex::Regex> = OnceLock::new();
+static HTML_IMG_RE: OnceLock<regex::Regex> = OnceLock::new();
+
+/// Characters we percent-encode when writing markdown link targets
+const LINK_TARGET_ENCODE_SET: &AsciiSet =
+    &CONTROLS.add(b' ').add(b'(').add(b')').add(b'<').add(b'>');
+
+fn md_link_re() -> &'static regex::Regex {
+    MD_LINK_RE.get_or_init(|| {
+        regex::Regex::new(r"!?\[[^\]]*\]\(\s*(?:<([^>]*)>|([^)\s]+))(?:\s+[^)]*)?\)").unwrap()
+    })
+}
+
+fn wiki_link_re() -> &'static regex::Regex {
+    WIKI_LINK_RE.get_or_init(|| regex::Regex::new(r"\[\[([^\[\]|]+)(?:\|[^\[\]]*)?\]\]").unwrap())
+}
+
+fn html_img_re() -> &'static regex::Regex {
+    HTML_IMG_RE.get_or_init(|| {
+        regex::Regex::new(r#"<img\b[^>]*?\ssrc\s*=\s*["']([^"']+)["'][^>]*>"#).unwrap()
+    })
+}
+
+/// Actions related to the backlinks panel
+#[derive(Debug, Clone)]
+pub enum BacklinksAction {
+    /// Search again the notes linking to the open file
+    Refresh,
+    /// The backlinks for the given search generation have been found
+    Found(u64, Vec<VaultSearchResult>),
+    /// Open the referring file and go to the link
+    Open(PathBuf, SearchMatch),
+}
+
+/// Kind of link found in a note
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// `[label](target)` or `![alt](target)`
+    Markdown,
+    /// `[[target]]` or `[[target|label]]`
+    Wiki,
+    /// `<img src="target">`
+    Image,
+}
+
+/// A link found in a note
+#[derive(Debug, Clone)]
+pub struct NoteLink {
+    pub kind: LinkKind,
+    pub line: usize,
+    /// Byte range of the whole link inside the line
+    pub range: Range<usize>,
+    /// Byte range of the link target inside the line
+    pub target_range: Range<usize>,
+    pub target: String,
+}
+
+/// The new content of a note after rewriting the links broken by a rename/move
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkRewrite {
+    pub path: PathBuf,
+    /// Content the rewrites were computed against
+    pub original: String,
+    /// Content with all the links rewritten
+    pub rewritten: String,
+    /// Number of links rewritten in this note
+    pub count: usize,
+}
+
+/// Holds the state of the backlinks panel
+#[derive(Default)]
+pub struct BacklinksState {
+    /// Note the backlinks have been searched for
+    pub target: Option<PathBuf>,
+    /// Files linking to the target, with every link found
+    pub results: Vec<VaultSearchResult>,
+    /// True while the vault is being scanned
+    pub is_searching: bool,
+    /// Increased on every search so results of older searches can be discarded
+    pub generation: u64,
+}
+
+impl BacklinksState {
+    /// Scans the given vault notes for links pointing to `target`, results are sent back with [`BacklinksAction::Found`]
+    pub fn refresh(
+        &mut self,
+        vault_path: PathBuf,
+        notes: Vec<PathBuf>,
+        target: Option<PathBuf>,
+    ) -> Task<cosmic::Action<Message>> {
+        self.generation += 1;
+
+        // keep showing the previous results while refreshing the same note
+        if self.target != target {
+            self.results.clear();
+        }
+        self.target = target.clone();
+
+        let Some(target) = target else {
+            self.is_searching = false;
+            return Task::none();
+        };
+
+        self.is_searching = true;
+        let generation = self.generation;
+
+        Task::perform(find_backlinks(vault_path, notes, target), move |results| {
+            cosmic::action::app(Message::Backlinks(BacklinksAction::Found(
+                generation, results,
+            )))
+        })
+    }
+
+    /// Total number of links found
+    pub fn link_count(&self) -> usize {
+        self.results.iter().map(|r| r.matches.len()).sum()
+    }
+}
+
+/// Finds every markdown and wiki link of the given text
+pub fn find_links(text: &str) -> Vec<NoteLink> {
+    let mut links = Vec::new();
+
+    for (line_idx, line) in text.lines().enumerate() {
+        for caps in md_link_re().captures_iter(line) {
+            let (Some(whole), Some(target)) = (caps.get(0), caps.get(1).or_else(|| caps.get(2)))
+            else {
+                continue;
+            };
+            links.push(NoteLink {
+                kind: LinkKind::Markdown,
+                line: line_idx,
+                range: whole.range(),
+                target_range: target.range(),
+                target: target.as_str().to_string(),
+            });
+        }
+
+        for caps in wiki_link_re().captures_iter(line) {
+            let (Some(whole), Some(target)) = (caps.get(0), caps.get(1)) else {
+                continue;
+            };
+            links.push(NoteLink {
+                kind: LinkKind::Wiki,
+                line: line_idx,
+                range: whole.range(),
+                target_range: target.range(),
+                target: target.as_str().to_string(),
+            });
+        }
+
+        for caps in html_img_re().captures_iter(line) {
+            let (Some(whole), Some(target)) = (caps.get(0), caps.get(1)) else {
+                continue;
+            };
+            links.push(NoteLink {
+                kind: LinkKind::Image,
+                line: line_idx,
+                range: whole.range(),
+                target_range: target.range(),
+                target: target.as_str().to_string(),
+            });
+        }
+    }
+
+    links.sort_by_key(|link| (link.line, link.range.start));
+    links
+}
+
+/// Returns the file the given link of `note_path` points to (None for external links)
+pub fn resolve_link(
+    vault_path: &Path,
+    notes: &[PathBuf],
+    note_path: &Path,
+    link: &NoteLink,
+) -> Option<PathBuf> {
+    match link.kind {
+        LinkKind::Markdown | LinkKind::Image => resolve_markdown_link(note_path, &link.target),
+        LinkKind::Wiki => resolve_wikilink(vault_path, notes, &link.target),
+    }
+}
+
+/// Resolves a markdown link target relative to the note containing it,
+/// None for links that don't point to a local file (urls, anchors...)
+pub fn resolve_markdown_link(note_path: &Path, target: &str) -> Option<PathBuf> {
+    if target.is_empty()
+        || target.starts_with('#')
+        || target.contains("://")
+        || target.starts_with("mailto:")
+    {
+        return None;
+    }
+
+    let target = target.split('#').next().unwrap_or_default();
+    let decoded = percent_encoding::percent_decode_str(target).decode_utf8_lossy();
+
+    Some(normalize_path(&note_path.parent()?.join(decoded.as_ref())))
+}
+
+/// Lexically resolves the `.` and `..` components of a path
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Returns the path of `to` relative to the `from_dir` directory (both must be absolute)
+pub fn relative_path(from_dir: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &to[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+/// Markdown link to the given file or folder, relative to the `from_dir` directory (both must be absolute)
+pub fn markdown_link(from_dir: &Path, to: &Path) -> String {
+    let name = if to.is_dir() {
+        to.file_name()
+    } else {
+        to.file_stem()
+    };
+    let label = name
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let target = relative_path(from_dir, to)
+        .to_string_lossy()
+        .replace('\\', "/");
+    let target = percent_encoding::utf8_percent_encode(&target, LINK_TARGET_ENCODE_SET);
+
+    format!("[{label}]({target})")
+}
+
+/// Wiki link to the given note, by its name unless another note of the vault with the same name
+/// would be picked instead (then by its path in the vault)
+pub fn wikilink(vault_path: &Path, notes: &[PathBuf], to: &Path) -> String {
+    let name = to
+        .file_stem()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if resolve_wikilink(vault_path, notes, &name).as_deref() == Some(to) {
+        return format!("[[{name}]]");
+    }
+
+    let target = to
+        .strip_prefix(vault_path)
+        .unwrap_or(to)
+        .with_extension("")
+        .to_string_lossy()
+        .replace('\\', "/");
+    format!("[[{target}]]")
+}
+
+/// Returns the note part of a wiki link target, without the label (`[[target|label]]`)
+/// or the heading (`[[target#heading]]`)
+pub fn wikilink_target(target: &str) -> &str {
+    let target = target.split('|').next().unwrap_or_default();
+    target.split('#').next().unwrap_or_default().trim()
+}
+
+/// Resolves the target of a wiki link against the given vault notes.
+///
+/// The target can be a note name (`[[Note]]`) or a path relative to the vault (`[[folder/Note]]`),
+/// with or without extension and ignoring case. If many notes share the same name the one closest
+/// to the vault root wins.
+pub fn resolve_wikilink(vault_path: &Path, notes: &[PathBuf], target: &str) -> Option<PathBuf> {
+    let target = wikilink_target(target)
+        .trim_start_matches('/')
+        .to_lowercase();
+    if target.is_empty() {
+        return None;
+    }
+
+    let by_path = target.contains('/');
+
+    notes
+        .iter()
+        .filter(|note| {
+            let key = if by_path {
+                note.strip_prefix(vault_path)
+                    .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_default()
+            } else {
+                note.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            };
+            // the note matches with or without its extension
+            let key = key.to_lowercase();
+            key == target || key.rsplit_once('.').is_some_and(|(stem, _)| stem == target)
+        })
+        .min_by_key(|note| (note.components().count(), note.as_path()))
+        .cloned()
+}
+
+/// Scans every note (but the target itself) for links pointing to `target` in a background thread
+async fn find_backlinks(
+    vault_path: PathBuf,
+    notes: Vec<PathBuf>,
+    target: PathBuf,
+) -> Vec<VaultSearchResult> {
+    tokio::task::spawn_blocking(move || {
+        notes
+            .iter()
+            .filter(|path| **path != target)
+            .filter_map(|path| {
+                let text = std::fs::read_to_string(path).ok()?;
+                let lines: Vec<&str> = text.lines().collect();
+
+                let matches: Vec<VaultSearchMatch> = find_links(&text)
+                    .into_iter()
+                    .filter(|link| {
+                        resolve_link(&vault_path, &notes, path, link).as_ref() == Some(&target)
+                    })
+                    .map(|link| VaultSearchMatch {
+                        snippet: lines
+                            .get(link.line)
+                            .map(|line| line.trim().chars().take(SNIPPET_MAX_CHARS).collect())
+                            .unwrap_or_default(),
+                        search_match: SearchMatch {
+                            line: link.line,
+                            col_start: link.range.start,
+                            col_end: link.range.end,
+                        },
+                    })
+                    .collect();
+
+                (!matches.is_empty()).then(|| VaultSearchResult {
+                    path: path.clone(),
+                    matches,
+                })
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Maps a path from before the renames/moves (`(old_path, new_path)` pairs) to where it is now
+fn moved_path(path: &Path, moves: &[(PathBuf, PathBuf)]) -> PathBuf {
+    for (old_path, new_path) in moves {
+        match path.strip_prefix(old_path) {
+            Ok(suffix) if suffix.as_os_str().is_empty() => return new_path.clone(),
+            Ok(suffix) => return new_path.join(suffix),
+            Err(_) => {}
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Maps a path from after the renames/moves (`(old_path, new_path)` pairs) to where it was before
+fn original_path(path: &Path, moves: &[(PathBuf, PathBuf)]) -> PathBuf {
+    for (old_path, new_path) in moves {
+        match path.strip_prefix(new_path) {
+            Ok(suffix) if suffix.as_os_str().is_empty() => return old_path.clone(),
+            Ok(suffix) => return old_path.join(suffix),
+            Err(_) => {}
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Everything needed to know how a link must change after a rename/move
+struct RenameContext<'a> {
+    vault_path: &'a Path,
+    /// Vault notes before the rename/move
+    old_notes: &'a [PathBuf],
+    /// Vault notes after the rename/move
+    new_notes: &'a [PathBuf],
+    /// Every rename/move as `(old_path, new_path)`
+    moves: &'a [(PathBuf, PathBuf)],
+}
+
+impl RenameContext<'_> {
+    /// Returns the new target text of `link` (found in the note now at `note_path`), None if it doesn't need to change
+    fn rewrite_target(&self, note_path: &Path, link: &NoteLink, line: &str) -> Option<String> {
+        match link.kind {
+            LinkKind::Markdown | LinkKind::Image => {
+                self.rewrite_relative_link(note_path, link, line)
+            }
+            LinkKind::Wiki => self.rewrite_wikilink(link),
+        }
+    }
+
+    fn rewrite_relative_link(
+        &self,
+        note_path: &Path,
+        link: &NoteLink,
+        line: &str,
+    ) -> Option<String> {
+        // where the note was before the rename/move
+        let old_note_path = original_path(note_path, self.moves);
+
+        let old_target = resolve_markdown_link(&old_note_path, &link.target)?;
+        let new_target = moved_path(&old_target, self.moves);
+
+        // neither the note nor its target moved, or the link was already broken
+        if (old_note_path == note_path && old_target == new_target) || !new_target.exists() {
+            return None;
+        }
+
+        let mut target = relative_path(note_path.parent()?, &new_target)
+            .to_string_lossy()
+            .replace('\\', "/");
+        if link.target.starts_with("./") && !target.starts_with("../") {
+            target = format!("./{target}");
+        }
+
+        // targets between angle brackets can contain spaces
+        let bracketed = link.target_range.start > 0
+            && line.as_bytes().get(link.target_range.start - 1) == Some(&b'<');
+        if !bracketed && link.kind == LinkKind::Markdown {
+            target =
+                percent_encoding::utf8_percent_encode(&target, LINK_TARGET_ENCODE_SET).to_string();
+        }
+
+        if let Some((_, fragment)) = link.target.split_once('#') {
+            target = format!("{target}#{fragment}");
+        }
+
+        (target != link.target).then_some(target)
+    }
+
+    fn rewrite_wikilink(&self, link: &NoteLink) -> Option<String> {
+        let old_target = resolve_wikilink(self.vault_path, self.old_notes, &link.target)?;
+        let new_target = moved_path(&old_target, self.moves);
+
+        // the link still points to the same note (eg: `[[Note]]` after moving its folder)
+        if resolve_wikilink(self.vault_path, self.new_notes, &link.target).as_ref()
+            == Some(&new_target)
+        {
+            return None;
+        }
+
+        let note_part = wikilink_target(&link.target);
+        // the link was written with the extension of the note
+        let keep_extension = Path::new(note_part)
+            .file_name()
+            .zip(old_target.file_name())
+            .is_some_and(|(written, note)| {
+                written.to_string_lossy().to_lowercase() == note.to_string_lossy().to_lowercase()
+            });
+
+        let name = if keep_extension {
+            new_target.file_name()?.to_string_lossy().into_owned()
+        } else {
+            new_target.file_stem()?.to_string_lossy().into_owned()
+        };
+
+        // prefer the note name, use the path inside the vault when the name is ambiguous
+        let mut target = if !note_part.contains('/')
+            && resolve_wikilink(self.vault_path, self.new_notes, &name).as_ref()
+                == Some(&new_target)
+        {
+            name
+        } else {
+            let relative = new_target.strip_prefix(self.vault_path).ok()?;
+            let relative = if keep_extension {
+                relative.to_path_buf()
+            } else {
+                relative.with_extension("")
+            };
+            relative.to_string_lossy().replace('\\', "/")
+        };
+
+        if let Some((_, heading)) = link.target.split_once('#') {
+            target = format!("{target}#{heading}");
+        }
+
+        Some(target)
+    }
+}
+
+/// Computes, in a background thread, the rewrites of every link broken by the given renames/moves (`(old_path, new_path)` pairs).
+///
+/// Links pointing into the moved path are updated as well as the relative links of the moved notes themselves,
+/// `open_buffers` (text by path) are used instead of the disk content of the open files.
+pub async fn plan_link_rewrites(
+    vault_path: PathBuf,
+    old_notes: Vec<PathBuf>,
+    new_notes: Vec<PathBuf>,
+    moves: Vec<(PathBuf, PathBuf)>,
+    open_buffers: HashMap<PathBuf, String>,
+) -> Vec<LinkRewrite> {
+    tokio::task::spawn_blocking(move || {
+        let context = RenameContext {
+            vault_path: &vault_path,
+            old_notes: &old_notes,
+            new_notes: &new_notes,
+            moves: &moves,
+        };
+
+        new_notes
+            .iter()
+            .filter_map(|path| {
+                let original = match open_buffers.get(path) {
+                    Some(text) => text.clone(),
+                    None => std::fs::read_to_string(path).ok()?,
+                };
+
+                let mut rewritten = String::with_capacity(original.len());
+                let mut count = 0;
+                let links = find_links(&original);
+
+                for (line_idx, raw_line) in original.split_inclusive('\n').enumerate() {
+                    let mut line = raw_line.to_string();
+
+                    // replace from the end so the ranges of the previous links stay valid
+                    for link in links.iter().rev().filter(|l| l.line == line_idx) {
+                        if let Some(target) = context.rewrite_target(path, link, raw_line) {
+                            line.replace_range(link.target_range.clone(), &target);
+                            count += 1;
+                        }
+                    }
+                    rewritten.push_str(&line);
+                }
+
+                (count > 0).then(|| LinkRewrite {
+                    path: path.clone(),
+                    original,
+                    rewritten,
+                    count,
+                })
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Writes all the given rewrites to disk or none of them.
+///
+/// Nothing is written if any note changed since the rewrites were planned, the new contents are
+/// written to temporary files first and then moved over the notes. If moving one of them fails the
+/// remaining temporary files are removed and the error says the links were only partly updated.
+pub async fn apply_link_rewrites(rewrites: Vec<LinkRewrite>) -> Result<usize, anywho::Error> {
+    tokio::task::spawn_blocking(move || {
+        for rewrite in &rewrites {
+            let current = std::fs::read_to_string(&rewrite.path)
+                .map_err(|e| anywho!("{}: {}", rewrite.path.display(), e))?;
+            if current != rewrite.original {
+                return Err(anywho!(
+                    "{} was modified before the links could be updated",
+                    rewrite.path.display()
+                ));
+            }
+        }
+
+        let mut staged = Vec::with_capacity(rewrites.len());
+        for rewrite in &rewrites {
+            let file_name = rewrite
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            let tmp_path = rewrite
+                .path
+                .with_file_name(format!(".{file_name}.cedilla-tmp"));
+
+            if let Err(e) = std::fs::write(&tmp_path, &rewrite.rewritten) {
+                let _ = std::fs::remove_file(&tmp_path);
+                for path in &staged {
+                    let _ = std::fs::remove_file(path);
+                }
+                return Err(anywho!("{}: {}", rewrite.path.display(), e));
+            }
+            staged.push(tmp_path);
+        }
+
+        for (index, (tmp_path, rewrite)) in staged.iter().zip(&rewrites).enumerate() {
+            if let Err(e) = std::fs::rename(tmp_path, &rewrite.path) {
+                // the notes already renamed keep their new links, the rest are left as they were
+                for path in &staged[index..] {
+                    let _ = std::fs::remove_file(path);
+                }
+                return Err(anywho!(
+                    "{}: {}, the links were only updated in {} of {} files",
+                    rewrite.path.display(),
+                    e,
+                    index,
+                    rewrites.len()
+                ));
+            }
+        }
+
+        Ok(rewrites.iter().map(|r| r.count).sum())
+    })
+    .await
+    .map_err(|e| anywho!("{e}"))?
+}
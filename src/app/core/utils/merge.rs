@@ -0,0 +1,369 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use std::{collections::HashMap, path::PathBuf};
+
+use dissimilar::Chunk;
+
+use crate::fl;
+
+/// How a [`Conflict`] of a merge is resolved
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Resolution {
+    /// Keep the lines of the open buffer
+    Ours,
+    /// Keep the lines of the file on disk
+    Theirs,
+    /// Keep the lines of the buffer followed by the ones on disk
+    Both,
+    /// Keep both versions between conflict markers to edit them by hand
+    Manual,
+}
+
+/// Lines changed differently on the open buffer and on the file on disk
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Conflict {
+    pub ours: String,
+    pub theirs: String,
+    pub resolution: Option<Resolution>,
+}
+
+impl Conflict {
+    /// Text the conflict is replaced with, `None` until it's resolved
+    pub fn resolved(&self) -> Option<String> {
+        Some(match self.resolution? {
+            Resolution::Ours => self.ours.clone(),
+            Resolution::Theirs => self.theirs.clone(),
+            Resolution::Both => format!("{}{}", with_newline(&self.ours), self.theirs),
+            Resolution::Manual => format!(
+                "<<<<<<< {}\n{}=======\n{}>>>>>>> {}\n",
+                fl!("merge-marker-mine"),
+                with_newline(&self.ours),
+                with_newline(&self.theirs),
+                fl!("merge-marker-disk"),
+            ),
+        })
+    }
+}
+
+/// Piece of a merged text
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MergeHunk {
+    /// Lines unchanged, changed only on one side or changed the same way on both
+    Resolved(String),
+    Conflict(Conflict),
+}
+
+/// Merge of the open buffer of a file with the content that file has now on disk
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MergeState {
+    pub path: PathBuf,
+    /// Content of the file on disk, the base of the next merge once this one is applied
+    pub theirs: String,
+    pub hunks: Vec<MergeHunk>,
+}
+
+impl MergeState {
+    pub fn new(path: PathBuf, base: &str, ours: &str, theirs: String) -> Self {
+        let hunks = merge(base, ours, &theirs);
+        Self {
+            path,
+            theirs,
+            hunks,
+        }
+    }
+
+    pub fn conflicts(&self) -> impl Iterator<Item = &Conflict> {
+        self.hunks.iter().filter_map(|hunk| match hunk {
+            MergeHunk::Conflict(conflict) => Some(conflict),
+            MergeHunk::Resolved(_) => None,
+        })
+    }
+
+    /// Sets the resolution of the conflict at the given index (counting only conflicts)
+    pub fn resolve(&mut self, index: usize, resolution: Resolution) {
+        let conflict = self
+            .hunks
+            .iter_mut()
+            .filter_map(|hunk| match hunk {
+                MergeHunk::Conflict(conflict) => Some(conflict),
+                MergeHunk::Resolved(_) => None,
+            })
+            .nth(index);
+
+        if let Some(conflict) = conflict {
+            conflict.resolution = Some(resolution);
+        }
+    }
+
+    /// The merged text, `None` while there are conflicts without a resolution
+    pub fn merged(&self) -> Option<String> {
+        self.hunks
+            .iter()
+            .map(|hunk| match hunk {
+                MergeHunk::Resolved(text) => Some(text.clone()),
+                MergeHunk::Conflict(conflict) => conflict.resolved(),
+            })
+            .collect()
+    }
+}
+
+/// Lines of `base` from `start` to `end` replaced by other lines on one side of the merge
+struct Change<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a str>,
+}
+
+/// Three-way merge (line by line) of the changes made to `base` on `ours` and on `theirs`
+pub fn merge(base: &str, ours: &str, theirs: &str) -> Vec<MergeHunk> {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours_lines: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs_lines: Vec<&str> = theirs.split_inclusive('\n').collect();
+
+    // every distinct line is mapped to a char so the diff works line by line
+    let mut ids = HashMap::new();
+    let (Some(base_ids), Some(ours_ids), Some(theirs_ids)) = (
+        encode_lines(&base_lines, &mut ids),
+        encode_lines(&ours_lines, &mut ids),
+        encode_lines(&theirs_lines, &mut ids),
+    ) else {
+        // too many distinct lines, the whole text is a single conflict
+        return vec![conflict_or_resolved(ours.to_string(), theirs.to_string())];
+    };
+
+    let ours_changes = changes(&base_ids, &ours_ids, &ours_lines);
+    let theirs_changes = changes(&base_ids, &theirs_ids, &theirs_lines);
+
+    let mut hunks = Vec::new();
+    let mut resolved = String::new();
+    let mut pos = 0;
+    let (mut i, mut j) = (0, 0);
+
+    while i < ours_changes.len() || j < theirs_changes.len() {
+        let start = match (ours_changes.get(i), theirs_changes.get(j)) {
+            (Some(a), Some(b)) => a.start.min(b.start),
+            (Some(a), None) => a.start,
+            (None, Some(b)) => b.start,
+            (None, None) => unreachable!(),
+        };
+
+        // overlapping changes (or changes at the same place) are merged as a single region
+        let (first_ours, first_theirs) = (i, j);
+        let mut end = start;
+        loop {
+            if let Some(change) = ours_changes
+                .get(i)
+                .filter(|c| c.start < end || c.start == start)
+            {
+                end = end.max(change.end);
+                i += 1;
+            } else if let Some(change) = theirs_changes
+                .get(j)
+                .filter(|c| c.start < end || c.start == start)
+            {
+                end = end.max(change.end);
+                j += 1;
+            } else {
+                break;
+            }
+        }
+
+        resolved.extend(base_lines[pos..start].iter().copied());
+        pos = end;
+
+        let ours_region = &ours_changes[first_ours..i];
+        let theirs_region = &theirs_changes[first_theirs..j];
+
+        if theirs_region.is_empty() {
+            resolved.push_str(&apply(&base_lines, start, end, ours_region));
+        } else if ours_region.is_empty() {
+            resolved.push_str(&apply(&base_lines, start, end, theirs_region));
+        } else {
+            let ours_text = apply(&base_lines, start, end, ours_region);
+            let theirs_text = apply(&base_lines, start, end, theirs_region);
+
+            match conflict_or_resolved(ours_text, theirs_text) {
+                MergeHunk::Resolved(text) => resolved.push_str(&text),
+                conflict => {
+                    if !resolved.is_empty() {
+                        hunks.push(MergeHunk::Resolved(std::mem::take(&mut resolved)));
+                    }
+                    hunks.push(conflict);
+                }
+            }
+        }
+    }
+
+    resolved.extend(base_lines[pos..].iter().copied());
+    if !resolved.is_empty() {
+        hunks.push(MergeHunk::Resolved(resolved));
+    }
+
+    hunks
+}
+
+/// Maps every line to the char of `ids` for that line, `None` if there are too many distinct lines
+fn encode_lines<'a>(lines: &[&'a str], ids: &mut HashMap<&'a str, char>) -> Option<String> {
+    lines
+        .iter()
+        .map(|line| match ids.get(line) {
+            Some(id) => Some(*id),
+            None => {
+                // chars outside of the basic plane, so they never clash with the text
+                let id = char::from_u32(0x10000 + ids.len() as u32)?;
+                ids.insert(*line, id);
+                Some(id)
+            }
+        })
+        .collect()
+}
+
+/// Regions of `base` replaced on `other` (both encoded with [`encode_lines`])
+fn changes<'a>(base: &str, other: &str, other_lines: &[&'a str]) -> Vec<Change<'a>> {
+    let mut changes: Vec<Change> = Vec::new();
+    let (mut base_pos, mut other_pos) = (0, 0);
+    // the last change is still growing
+    let mut open = false;
+
+    for chunk in dissimilar::diff(base, other) {
+        match chunk {
+            Chunk::Equal(lines) => {
+                let count = lines.chars().count();
+                base_pos += count;
+                other_pos += count;
+                open = false;
+            }
+            Chunk::Delete(lines) => {
+                let count = lines.chars().count();
+                if !open {
+                    changes.push(Change {
+                        start: base_pos,
+                        end: base_pos,
+                        lines: Vec::new(),
+                    });
+                    open = true;
+                }
+                base_pos += count;
+                if let Some(change) = changes.last_mut() {
+                    change.end = base_pos;
+                }
+            }
+            Chunk::Insert(lines) => {
+                let count = lines.chars().count();
+                if !open {
+                    changes.push(Change {
+                        start: base_pos,
+                        end: base_pos,
+                        lines: Vec::new(),
+                    });
+                    open = true;
+                }
+                if let Some(change) = changes.last_mut() {
+                    change
+                        .lines
+                        .extend_from_slice(&other_lines[other_pos..other_pos + count]);
+                }
+                other_pos += count;
+            }
+        }
+    }
+
+    changes
+}
+
+/// Text of the lines of `base` from `start` to `end` with the given changes applied
+fn apply(base: &[&str], start: usize, end: usize, changes: &[Change]) -> String {
+    let mut text = String::new();
+    let mut pos = start;
+
+    for change in changes {
+        text.extend(base[pos..change.start].iter().copied());
+        text.extend(change.lines.iter().copied());
+        pos = change.end;
+    }
+
+    text.extend(base[pos..end].iter().copied());
+    text
+}
+
+fn conflict_or_resolved(ours: String, theirs: String) -> MergeHunk {
+    if ours == theirs {
+        MergeHunk::Resolved(ours)
+    } else {
+        MergeHunk::Conflict(Conflict {
+            ours,
+            theirs,
+            resolution: None,
+        })
+    }
+}
+
+/// The text ending with a line break, so the lines that follow it don't join its last line
+fn with_newline(text: &str) -> String {
+    if text.is_empty() || text.ends_with('\n') {
+        text.to_string()
+    } else {
+        format!("{text}\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge_state(base: &str, ours: &str, theirs: &str) -> MergeState {
+        MergeState::new(PathBuf::from("note.md"), base, ours, theirs.to_string())
+    }
+
+    #[test]
+    fn adjacent_edits() {
+        let merge = merge_state("a\nb\nc\n", "A\nb\nc\n", "a\nB\nc\n");
+        assert_eq!(merge.conflicts().count(), 0);
+        assert_eq!(merge.merged().as_deref(), Some("A\nB\nc\n"));
+    }
+
+    #[test]
+    fn same_line_edits() {
+        let mut merge = merge_state("a\nb\nc\n", "a\nours\nc\n", "a\ntheirs\nc\n");
+        assert_eq!(
+            merge.conflicts().collect::<Vec<_>>(),
+            [&Conflict {
+                ours: "ours\n".to_string(),
+                theirs: "theirs\n".to_string(),
+                resolution: None,
+            }]
+        );
+        assert_eq!(merge.merged(), None);
+
+        merge.resolve(0, Resolution::Theirs);
+        assert_eq!(merge.merged().as_deref(), Some("a\ntheirs\nc\n"));
+    }
+
+    #[test]
+    fn insert_at_same_position() {
+        let mut merge = merge_state("a\nb\n", "a\nours\nb\n", "a\ntheirs\nb\n");
+        assert_eq!(merge.conflicts().count(), 1);
+
+        merge.resolve(0, Resolution::Both);
+        assert_eq!(merge.merged().as_deref(), Some("a\nours\ntheirs\nb\n"));
+    }
+
+    #[test]
+    fn identical_edits() {
+        let merge = merge_state("a\nb\nc\n", "a\nB\nc\nd\n", "a\nB\nc\nd\n");
+        assert_eq!(merge.conflicts().count(), 0);
+        assert_eq!(merge.merged().as_deref(), Some("a\nB\nc\nd\n"));
+    }
+
+    #[test]
+    fn no_trailing_newline() {
+        let merge = merge_state("a\nb", "A\nb", "a\nb c");
+        assert_eq!(merge.conflicts().count(), 0);
+        assert_eq!(merge.merged().as_deref(), Some("A\nb c"));
+
+        // the last line of our side doesn't join the first one of theirs
+        let mut merge = merge_state("a\nb", "a\nours", "a\ntheirs");
+        merge.resolve(0, Resolution::Both);
+        assert_eq!(merge.merged().as_deref(), Some("a\nours\ntheirs"));
+    }
+}
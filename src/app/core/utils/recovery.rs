@@ -0,0 +1,169 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use std::path::{Path, PathBuf};
+
+use anywho::anywho;
+use cosmic::Application;
+
+use crate::app::AppModel;
+use crate::app::core::utils::files;
+use crate::app::core::utils::search::{self, DiffChunk};
+
+/// Seconds between every autosave of the documents with unsaved changes
+pub const RECOVERY_INTERVAL_SECS: u64 = 30;
+
+/// Unsaved content of a document to write in the recovery directory
+#[derive(Debug, Clone)]
+pub struct RecoverySnapshot {
+    pub key: String,
+    /// Path of the document, `None` for new files
+    pub path: Option<PathBuf>,
+    pub content: String,
+}
+
+/// A document found in the recovery directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveredDocument {
+    pub key: String,
+    /// Path of the document, `None` for new files
+    pub path: Option<PathBuf>,
+    /// Content of the file on disk (empty for new files or files that no longer exist)
+    pub original: String,
+    /// Recovered (unsaved) content
+    pub content: String,
+    /// Diff between the original and the recovered content (long unchanged parts collapsed)
+    pub diff: Vec<DiffChunk>,
+}
+
+/// Directory where the unsaved documents are written to
+pub fn recovery_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap()
+        .join(AppModel::APP_ID)
+        .join("recovery")
+}
+
+/// Name of the recovery files of a document, the document `id` only distinguishes between new files
+pub fn recovery_key(path: Option<&Path>, id: u64) -> String {
+    match path {
+        Some(path) => format!(
+            "{:016x}",
+            files::stable_hash(path.as_os_str().as_encoded_bytes())
+        ),
+        None => format!("untitled-{}-{id}", std::process::id()),
+    }
+}
+
+/// Hash of a document content, used to avoid writing the same content twice
+///
+/// It's stored with the undo histories, so it has to stay the same between builds
+pub fn content_hash(content: &str) -> u64 {
+    files::stable_hash(content.as_bytes())
+}
+
+/// Writes the given snapshots in the recovery directory and removes the recovery files of the `stale` keys
+pub async fn write_recovery(
+    snapshots: Vec<RecoverySnapshot>,
+    stale: Vec<String>,
+) -> Result<(), anywho::Error> {
+    let dir = recovery_dir();
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| anywho!("Failed to create recovery directory: {}", e))?;
+
+    for snapshot in snapshots {
+        // write to a temporary file first so a crash while writing never leaves a half written copy
+        let tmp_path = dir.join(format!(".{}.tmp", snapshot.key));
+        tokio::fs::write(&tmp_path, &snapshot.content)
+            .await
+            .map_err(|e| anywho!("Failed to write recovery file: {}", e))?;
+
+        let path_file = dir.join(format!("{}.path", snapshot.key));
+        match &snapshot.path {
+            Some(path) => tokio::fs::write(&path_file, path.to_string_lossy().as_bytes())
+                .await
+                .map_err(|e| anywho!("Failed to write recovery file: {}", e))?,
+            None => {
+                let _ = tokio::fs::remove_file(&path_file).await;
+            }
+        }
+
+        tokio::fs::rename(&tmp_path, dir.join(format!("{}.md", snapshot.key)))
+            .await
+            .map_err(|e| anywho!("Failed to write recovery file: {}", e))?;
+    }
+
+    for key in stale {
+        remove_recovery_files(&dir, &key).await;
+    }
+
+    Ok(())
+}
+
+/// Removes the recovery files of the given key
+pub async fn discard_recovery(key: String) {
+    remove_recovery_files(&recovery_dir(), &key).await;
+}
+
+/// Removes the recovery files of the given keys right away (used when closing the app)
+pub fn discard_recovery_blocking<'a>(keys: impl IntoIterator<Item = &'a String>) {
+    let dir = recovery_dir();
+    for key in keys {
+        let _ = std::fs::remove_file(dir.join(format!("{key}.md")));
+        let _ = std::fs::remove_file(dir.join(format!("{key}.path")));
+    }
+}
+
+async fn remove_recovery_files(dir: &Path, key: &str) {
+    let _ = tokio::fs::remove_file(dir.join(format!("{key}.md"))).await;
+    let _ = tokio::fs::remove_file(dir.join(format!("{key}.path"))).await;
+}
+
+/// Loads every document of the recovery directory, documents without changes against the disk are discarded
+pub async fn load_recovery() -> Vec<RecoveredDocument> {
+    let dir = recovery_dir();
+    let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+        return Vec::new();
+    };
+
+    let mut documents = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let file = entry.path();
+        if file.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+
+        let Some(key) = file.file_stem().map(|s| s.to_string_lossy().into_owned()) else {
+            continue;
+        };
+
+        let Ok(content) = tokio::fs::read_to_string(&file).await else {
+            continue;
+        };
+
+        let path = tokio::fs::read_to_string(dir.join(format!("{key}.path")))
+            .await
+            .ok()
+            .map(PathBuf::from);
+
+        let original = match &path {
+            Some(path) => tokio::fs::read_to_string(path).await.unwrap_or_default(),
+            None => String::new(),
+        };
+
+        if content == original || (path.is_none() && content.trim().is_empty()) {
+            remove_recovery_files(&dir, &key).await;
+            continue;
+        }
+
+        documents.push(RecoveredDocument {
+            diff: search::collapsed_diff(&original, &content),
+            key,
+            path,
+            original,
+            content,
+        });
+    }
+
+    documents
+}
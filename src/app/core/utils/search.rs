@@ -1,9 +1,23 @@
-use grep::matcher::{Match, Matcher};
-use grep::regex::RegexMatcherBuilder;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anywho::anywho;
+use dissimilar::Chunk;
+use grep::matcher::{Captures, Match, Matcher};
+use grep::regex::{RegexCaptures, RegexMatcher, RegexMatcherBuilder};
 use grep::searcher::{Searcher, sinks::UTF8};
 
+use crate::app::Message;
 use crate::app::core::editor::EditorSearchState;
+use cosmic::Task;
 use cosmic::iced::core::text::editor::Cursor;
+use cosmic::iced::futures::{SinkExt, Stream};
+
+/// Max length of the line snippets shown in the vault search results
+pub const SNIPPET_MAX_CHARS: usize = 120;
+
+/// Unchanged lines kept around every change in a collapsed diff
+const DIFF_CONTEXT_LINES: usize = 2;
 
 /// Actions related to the editor search feature
 #[derive(Debug, Clone)]
@@ -11,12 +25,52 @@ pub enum SearchAction {
     ToggleSearch,
     UpdateSearchValue(String),
     ToggleRegex,
+    ToggleCaseInsensitive,
+    ToggleWholeWord,
+    ToggleInSelection,
     NextResult,
     PrevResult,
+    ToggleReplace,
+    UpdateReplaceValue(String),
+    Replace,
+    ReplaceAll,
 
     FocusSearchField,
 }
 
+/// Actions related to the vault wide search feature
+#[derive(Debug, Clone)]
+pub enum VaultSearchAction {
+    /// Update the vault search field
+    UpdateSearchValue(String),
+    /// Toggle regex searching
+    ToggleRegex,
+    /// Start (or restart) searching the vault
+    StartSearch,
+    /// A file with matches has been found for the given search generation
+    ResultFound(u64, VaultSearchResult),
+    /// The search with the given generation has finished
+    SearchFinished(u64),
+    /// Open the file of a result and go to the match
+    OpenResult(PathBuf, SearchMatch),
+    /// Select the given match if the given file is the open one
+    RevealMatch(PathBuf, SearchMatch),
+    /// Switch between find and replace modes
+    ToggleReplaceMode,
+    /// Update the vault replace field
+    UpdateReplaceValue(String),
+    /// Compute the preview of all the replacements
+    PreviewReplace,
+    /// The replacement preview for the given search generation is ready
+    ReplacePreviewReady(u64, Vec<VaultReplacePreview>),
+    /// Check or uncheck the file at the given index of the preview
+    ToggleReplaceFile(usize),
+    /// Apply the checked replacements
+    ApplyReplace,
+    /// Callback after applying the replacements
+    ReplaceApplied(Vec<Result<PathBuf, anywho::Error>>),
+}
+
 #[derive(Debug, Clone)]
 pub struct SearchMatch {
     pub line: usize,
@@ -24,6 +78,105 @@ pub struct SearchMatch {
     pub col_end: usize,
 }
 
+/// Options used to build a search matcher
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatcherOptions {
+    pub use_regex: bool,
+    pub case_insensitive: bool,
+    pub whole_word: bool,
+}
+
+/// Part of the text (as `(line, column)` positions) the editor search is restricted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchRange {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+impl SearchRange {
+    /// Returns true if the given span of `line` is fully inside the range
+    pub fn contains(&self, line: usize, col_start: usize, col_end: usize) -> bool {
+        (line, col_start) >= self.start && (line, col_end) <= self.end
+    }
+}
+
+/// All the matches found in a single vault file
+#[derive(Debug, Clone)]
+pub struct VaultSearchResult {
+    pub path: PathBuf,
+    pub matches: Vec<VaultSearchMatch>,
+}
+
+/// A match found in a vault file, with the text of the line it was found in
+#[derive(Debug, Clone)]
+pub struct VaultSearchMatch {
+    pub search_match: SearchMatch,
+    pub snippet: String,
+}
+
+/// A piece of a line diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffChunk {
+    Equal(String),
+    Insert(String),
+    Delete(String),
+}
+
+/// A line changed by a replacement
+#[derive(Debug, Clone)]
+pub struct ReplacedLine {
+    pub line: usize,
+    /// Number of replacements done in this line
+    pub count: usize,
+    /// Diff of the line before and after the replacement
+    pub chunks: Vec<DiffChunk>,
+}
+
+/// Preview of the replacements that would be done in a single vault file
+#[derive(Debug, Clone)]
+pub struct VaultReplacePreview {
+    pub path: PathBuf,
+    /// Content the preview was computed against
+    pub original: String,
+    /// Content after all the replacements
+    pub replaced: String,
+    pub lines: Vec<ReplacedLine>,
+    /// Wether the user wants to apply the replacements of this file
+    pub checked: bool,
+}
+
+impl VaultReplacePreview {
+    /// Total number of replacements in this file
+    pub fn count(&self) -> usize {
+        self.lines.iter().map(|l| l.count).sum()
+    }
+}
+
+/// Holds the state of the vault wide search
+#[derive(Default)]
+pub struct VaultSearchState {
+    /// State of the search field
+    pub search_value: String,
+    /// Wether to use regex or not for searching
+    pub use_regex: bool,
+    /// Files with matches found (if any)
+    pub results: Vec<VaultSearchResult>,
+    /// Errors parsing regex
+    pub regex_error: Option<String>,
+    /// True while the vault is being walked
+    pub is_searching: bool,
+    /// Increased on every search so results of older searches can be discarded
+    pub generation: u64,
+    /// Allows us to abort a search still in progress
+    pub handle: Option<cosmic::iced::task::Handle>,
+    /// Wether the panel is in replace mode
+    pub replace_mode: bool,
+    /// State of the replace field
+    pub replace_value: String,
+    /// Per file preview of the replacements
+    pub replace_previews: Vec<VaultReplacePreview>,
+}
+
 impl From<&SearchMatch> for Cursor {
     fn from(value: &SearchMatch) -> Self {
         use cosmic::widget::text_editor::Position;
@@ -41,7 +194,232 @@ impl From<&SearchMatch> for Cursor {
     }
 }
 
+/// Builds the matcher used by both the editor and the vault search
+pub fn build_matcher(
+    pattern: &str,
+    options: MatcherOptions,
+) -> Result<RegexMatcher, grep::regex::Error> {
+    // fixed_strings(true) gives literal search; false gives full regex.
+    RegexMatcherBuilder::new()
+        .fixed_strings(!options.use_regex)
+        .case_insensitive(options.case_insensitive)
+        .word(options.whole_word)
+        .build(pattern)
+}
+
+/// Runs the matcher over a single line, calling `on_match` for each match found
+fn for_each_match<F>(matcher: &RegexMatcher, line_idx: usize, line_text: &str, mut on_match: F)
+where
+    F: FnMut(SearchMatch),
+{
+    let _ = matcher.find_iter(line_text.as_bytes(), |m: Match| {
+        on_match(SearchMatch {
+            line: line_idx,
+            col_start: m.start(),
+            col_end: m.end(),
+        });
+
+        true // keep iterating
+    });
+}
+
+/// Writes the replacement of a single match into `dst`, capture groups like `$1` are only expanded when using regex
+fn expand_replacement(
+    matcher: &RegexMatcher,
+    caps: &RegexCaptures,
+    haystack: &[u8],
+    replacement: &str,
+    use_regex: bool,
+    dst: &mut Vec<u8>,
+) {
+    if use_regex {
+        caps.interpolate(
+            |name| matcher.capture_index(name),
+            haystack,
+            replacement.as_bytes(),
+            dst,
+        );
+    } else {
+        dst.extend_from_slice(replacement.as_bytes());
+    }
+}
+
+/// Replaces every match of a single line accepted by `should_replace` (called with the match start and end columns),
+/// returns the new line and the number of replacements (None if nothing was replaced)
+pub fn replace_line<F>(
+    matcher: &RegexMatcher,
+    line: &str,
+    replacement: &str,
+    use_regex: bool,
+    mut should_replace: F,
+) -> Option<(String, usize)>
+where
+    F: FnMut(usize, usize) -> bool,
+{
+    let mut caps = matcher.new_captures().ok()?;
+    let mut dst = Vec::with_capacity(line.len());
+    let mut count = 0;
+
+    matcher
+        .replace_with_captures(line.as_bytes(), &mut caps, &mut dst, |caps, dst| {
+            match caps.get(0) {
+                Some(m) if !should_replace(m.start(), m.end()) => {
+                    // keep the original text of the skipped match
+                    dst.extend_from_slice(&line.as_bytes()[m.start()..m.end()]);
+                }
+                _ => {
+                    count += 1;
+                    expand_replacement(matcher, caps, line.as_bytes(), replacement, use_regex, dst);
+                }
+            }
+            true // keep replacing
+        })
+        .ok()?;
+
+    (count > 0).then(|| (String::from_utf8_lossy(&dst).into_owned(), count))
+}
+
+/// Returns the replacement text for a single match of `line`, capture groups like `$1` are only expanded when using regex
+pub fn replacement_for_match(
+    matcher: &RegexMatcher,
+    line: &str,
+    search_match: &SearchMatch,
+    replacement: &str,
+    use_regex: bool,
+) -> Option<String> {
+    let mut caps = matcher.new_captures().ok()?;
+    if !matcher
+        .captures_at(line.as_bytes(), search_match.col_start, &mut caps)
+        .ok()?
+    {
+        return None;
+    }
+
+    // make sure the text has not changed since the match was computed
+    let m = caps.get(0)?;
+    if m.start() != search_match.col_start || m.end() != search_match.col_end {
+        return None;
+    }
+
+    let mut dst = Vec::new();
+    expand_replacement(
+        matcher,
+        &caps,
+        line.as_bytes(),
+        replacement,
+        use_regex,
+        &mut dst,
+    );
+    Some(String::from_utf8_lossy(&dst).into_owned())
+}
+
+/// Replaces every match in `text` line by line (the same way matches are searched), only inside `range` if given,
+/// returns the resulting text and the lines that changed
+pub fn replace_in_text(
+    matcher: &RegexMatcher,
+    text: &str,
+    replacement: &str,
+    use_regex: bool,
+    range: Option<SearchRange>,
+) -> (String, Vec<ReplacedLine>) {
+    let mut result = String::with_capacity(text.len());
+    let mut lines = Vec::new();
+
+    for (line_idx, raw_line) in text.split_inclusive('\n').enumerate() {
+        let content = raw_line.trim_end_matches(['\n', '\r']);
+        let ending = &raw_line[content.len()..];
+
+        let replaced = replace_line(matcher, content, replacement, use_regex, |start, end| {
+            range.is_none_or(|r| r.contains(line_idx, start, end))
+        });
+
+        match replaced {
+            Some((new_line, count)) => {
+                lines.push(ReplacedLine {
+                    line: line_idx,
+                    count,
+                    chunks: diff_chunks(content, &new_line),
+                });
+                result.push_str(&new_line);
+            }
+            None => result.push_str(content),
+        }
+        result.push_str(ending);
+    }
+
+    (result, lines)
+}
+
+/// Owned diff between two texts with the long unchanged parts collapsed (only a few lines around every change are kept)
+pub fn collapsed_diff(old: &str, new: &str) -> Vec<DiffChunk> {
+    dissimilar::diff(old, new)
+        .into_iter()
+        .map(|chunk| match chunk {
+            Chunk::Equal(s) => DiffChunk::Equal(collapse_unchanged(s)),
+            Chunk::Insert(s) => DiffChunk::Insert(s.to_string()),
+            Chunk::Delete(s) => DiffChunk::Delete(s.to_string()),
+        })
+        .collect()
+}
+
+/// Keeps only a few lines at both ends of an unchanged part
+fn collapse_unchanged(text: &str) -> String {
+    let lines: Vec<&str> = text.split('\n').collect();
+    if lines.len() <= DIFF_CONTEXT_LINES * 2 + 1 {
+        return text.to_string();
+    }
+
+    format!(
+        "{}\n…\n{}",
+        lines[..DIFF_CONTEXT_LINES].join("\n"),
+        lines[lines.len() - DIFF_CONTEXT_LINES..].join("\n")
+    )
+}
+
+/// Owned diff between two lines
+fn diff_chunks(old: &str, new: &str) -> Vec<DiffChunk> {
+    dissimilar::diff(old, new)
+        .into_iter()
+        .map(|chunk| match chunk {
+            Chunk::Equal(s) => DiffChunk::Equal(s.to_string()),
+            Chunk::Insert(s) => DiffChunk::Insert(s.to_string()),
+            Chunk::Delete(s) => DiffChunk::Delete(s.to_string()),
+        })
+        .collect()
+}
+
 impl EditorSearchState {
+    /// Search options chosen by the user
+    pub fn options(&self) -> MatcherOptions {
+        MatcherOptions {
+            use_regex: self.use_regex,
+            case_insensitive: self.case_insensitive,
+            whole_word: self.whole_word,
+        }
+    }
+
+    /// Builds the matcher for the current search value and options
+    pub fn matcher(&self) -> Result<RegexMatcher, grep::regex::Error> {
+        build_matcher(&self.search_value, self.options())
+    }
+
+    /// Returns a closed search state keeping the options chosen during this session
+    pub fn persisted(&self) -> Self {
+        Self {
+            use_regex: self.use_regex,
+            case_insensitive: self.case_insensitive,
+            whole_word: self.whole_word,
+            ..Default::default()
+        }
+    }
+
+    /// Forgets the selection the search is restricted to, its positions are no longer valid once the text
+    /// is edited. Returns true if the search was restricted to it.
+    pub fn clear_selection_range(&mut self) -> bool {
+        self.selection_range = None;
+        std::mem::take(&mut self.in_selection)
+    }
+
     /// Recomputes all matches against `text`
     pub fn compute_matches(&mut self, text: &str) {
         self.matches.clear();
@@ -52,11 +430,7 @@ impl EditorSearchState {
             return;
         }
 
-        // fixed_strings(true) gives literal search; false gives full regex.
-        let matcher = match RegexMatcherBuilder::new()
-            .fixed_strings(!self.use_regex)
-            .build(&self.search_value)
-        {
+        let matcher = match self.matcher() {
             Ok(m) => m,
             Err(e) => {
                 self.regex_error = Some(e.to_string());
@@ -67,21 +441,17 @@ impl EditorSearchState {
 
         let mut searcher = Searcher::new();
         let mut new_matches: Vec<SearchMatch> = Vec::new();
+        let range = self.selection_range.filter(|_| self.in_selection);
 
         let _ = searcher.search_slice(
             &matcher,
             text.as_bytes(),
             UTF8(|line_number, line_text| {
                 let line_idx = (line_number as usize).saturating_sub(1);
-
-                let _ = matcher.find_iter(line_text.as_bytes(), |m: Match| {
-                    new_matches.push(SearchMatch {
-                        line: line_idx,
-                        col_start: m.start(),
-                        col_end: m.end(),
-                    });
-
-                    true // keep iterating
+                for_each_match(&matcher, line_idx, line_text, |m| {
+                    if range.is_none_or(|r| r.contains(m.line, m.col_start, m.col_end)) {
+                        new_matches.push(m);
+                    }
                 });
 
                 Ok(true) // keep searching further lines
@@ -125,3 +495,240 @@ impl EditorSearchState {
         self.matches.get(prev)
     }
 }
+
+impl VaultSearchState {
+    /// Search options chosen by the user
+    fn options(&self) -> MatcherOptions {
+        MatcherOptions {
+            use_regex: self.use_regex,
+            ..Default::default()
+        }
+    }
+
+    /// Aborts any search in progress and starts searching the given vault notes, results are streamed back
+    pub fn start(&mut self, notes: Vec<PathBuf>) -> Task<cosmic::Action<Message>> {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+
+        self.generation += 1;
+        self.results.clear();
+        self.regex_error = None;
+        self.is_searching = false;
+
+        if self.search_value.is_empty() {
+            return Task::none();
+        }
+
+        let matcher = match build_matcher(&self.search_value, self.options()) {
+            Ok(m) => m,
+            Err(e) => {
+                self.regex_error = Some(e.to_string());
+                return Task::none();
+            }
+        };
+
+        self.is_searching = true;
+        let generation = self.generation;
+
+        let (task, handle) = Task::run(search_vault(notes, matcher), move |result| {
+            cosmic::action::app(Message::VaultSearch(VaultSearchAction::ResultFound(
+                generation, result,
+            )))
+        })
+        .chain(Task::done(cosmic::action::app(Message::VaultSearch(
+            VaultSearchAction::SearchFinished(generation),
+        ))))
+        .abortable();
+
+        self.handle = Some(handle);
+        task
+    }
+
+    /// Aborts any search in progress and computes the replacements of the given vault notes,
+    /// `open_buffers` (text by path) are used instead of the disk content of the open files
+    pub fn preview_replace(
+        &mut self,
+        notes: Vec<PathBuf>,
+        open_buffers: HashMap<PathBuf, String>,
+    ) -> Task<cosmic::Action<Message>> {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+
+        self.generation += 1;
+        self.replace_previews.clear();
+        self.regex_error = None;
+        self.is_searching = false;
+
+        if self.search_value.is_empty() {
+            return Task::none();
+        }
+
+        let matcher = match build_matcher(&self.search_value, self.options()) {
+            Ok(m) => m,
+            Err(e) => {
+                self.regex_error = Some(e.to_string());
+                return Task::none();
+            }
+        };
+
+        self.is_searching = true;
+        let generation = self.generation;
+        let replacement = self.replace_value.clone();
+        let use_regex = self.use_regex;
+
+        let (task, handle) = Task::perform(
+            preview_vault_replace(notes, matcher, replacement, use_regex, open_buffers),
+            move |previews| {
+                cosmic::action::app(Message::VaultSearch(
+                    VaultSearchAction::ReplacePreviewReady(generation, previews),
+                ))
+            },
+        )
+        .abortable();
+
+        self.handle = Some(handle);
+        task
+    }
+
+    /// Total number of matches found across all files
+    pub fn match_count(&self) -> usize {
+        self.results.iter().map(|r| r.matches.len()).sum()
+    }
+
+    /// Total number of replacements of the checked files
+    pub fn checked_replace_count(&self) -> usize {
+        self.replace_previews
+            .iter()
+            .filter(|p| p.checked)
+            .map(VaultReplacePreview::count)
+            .sum()
+    }
+}
+
+/// Searches the given notes in a background thread, yielding every file with at least one match
+fn search_vault(
+    notes: Vec<PathBuf>,
+    matcher: RegexMatcher,
+) -> impl Stream<Item = VaultSearchResult> {
+    use cosmic::iced::futures::channel::mpsc as iced_mpsc;
+
+    cosmic::iced::stream::channel(
+        16,
+        move |mut output: iced_mpsc::Sender<VaultSearchResult>| async move {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<VaultSearchResult>();
+
+            tokio::task::spawn_blocking(move || {
+                for path in notes {
+                    let matches = search_file(&matcher, &path);
+                    // if the receiver is gone the search has been aborted
+                    if !matches.is_empty() && tx.send(VaultSearchResult { path, matches }).is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+
+            while let Some(result) = rx.recv().await {
+                let _ = output.send(result).await;
+            }
+        },
+    )
+}
+
+/// Returns all the matches (with their line snippet) found in the given file
+fn search_file(matcher: &RegexMatcher, path: &Path) -> Vec<VaultSearchMatch> {
+    let mut matches = Vec::new();
+
+    let _ = Searcher::new().search_path(
+        matcher,
+        path,
+        UTF8(|line_number, line_text| {
+            let line_idx = (line_number as usize).saturating_sub(1);
+            let snippet: String = line_text.trim().chars().take(SNIPPET_MAX_CHARS).collect();
+
+            for_each_match(matcher, line_idx, line_text, |m| {
+                matches.push(VaultSearchMatch {
+                    search_match: m,
+                    snippet: snippet.clone(),
+                })
+            });
+
+            Ok(true) // keep searching further lines
+        }),
+    );
+
+    matches
+}
+
+/// Computes the replacements of the given notes in a background thread
+async fn preview_vault_replace(
+    notes: Vec<PathBuf>,
+    matcher: RegexMatcher,
+    replacement: String,
+    use_regex: bool,
+    open_buffers: HashMap<PathBuf, String>,
+) -> Vec<VaultReplacePreview> {
+    tokio::task::spawn_blocking(move || {
+        notes
+            .into_iter()
+            .filter_map(|path| {
+                let original = match open_buffers.get(&path) {
+                    Some(text) => text.clone(),
+                    None => std::fs::read_to_string(&path).ok()?,
+                };
+
+                let (replaced, lines) =
+                    replace_in_text(&matcher, &original, &replacement, use_regex, None);
+                if lines.is_empty() {
+                    return None;
+                }
+
+                Some(VaultReplacePreview {
+                    path,
+                    original,
+                    replaced,
+                    lines,
+                    checked: true,
+                })
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Writes the given replacements (path, original content, new content) to disk,
+/// files modified since the preview was computed are skipped
+pub async fn apply_vault_replace(
+    changes: Vec<(PathBuf, String, String)>,
+) -> Vec<Result<PathBuf, anywho::Error>> {
+    let mut results = Vec::with_capacity(changes.len());
+
+    for (path, original, replaced) in changes {
+        let result = async {
+            let current = tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|e| anywho!("{}: {}", path.display(), e))?;
+
+            if current != original {
+                return Err(anywho!(
+                    "{} was modified after the preview, skipped",
+                    path.display()
+                ));
+            }
+
+            tokio::fs::write(&path, replaced)
+                .await
+                .map_err(|e| anywho!("{}: {}", path.display(), e))?;
+
+            Ok(path.clone())
+        }
+        .await;
+
+        results.push(result);
+    }
+
+    results
+}
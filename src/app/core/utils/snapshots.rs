@@ -0,0 +1,272 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use anywho::anywho;
+use cosmic::Task;
+use serde::{Deserialize, Serialize};
+
+use crate::app::Message;
+use crate::app::core::history::{self, StoredChunk};
+use crate::app::core::utils::files::{self, VAULT_DATA_DIR};
+use crate::app::core::utils::search::{self, DiffChunk};
+use crate::fl;
+
+/// Max snapshots kept per file, the oldest ones get merged
+const MAX_SNAPSHOTS: usize = 100;
+
+/// Snapshots are taken one at a time, so two saves in a row can't overwrite each other's snapshot
+static SNAPSHOT_LOCK: LazyLock<futures_util::lock::Mutex<()>> =
+    LazyLock::new(|| futures_util::lock::Mutex::new(()));
+
+/// Actions related to the file history panel
+#[derive(Debug, Clone)]
+pub enum FileHistoryAction {
+    /// Load again the snapshots of the open file
+    Refresh,
+    /// The snapshots for the given load generation have been loaded
+    Loaded(u64, Result<Vec<Snapshot>, anywho::Error>),
+    /// A snapshot of the given file has been stored
+    SnapshotTaken(PathBuf),
+    /// Show the diff between the given snapshot and the open file
+    Select(usize),
+    /// Replace the content of the open file with the given snapshot
+    Restore(usize),
+}
+
+/// Content of a file at some point in time
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Unix timestamp (in seconds) of the save that created the snapshot
+    pub timestamp: u64,
+    pub content: String,
+}
+
+impl Snapshot {
+    /// Human readable time elapsed since the snapshot was taken (relative to `now`)
+    pub fn age(&self, now: u64) -> String {
+        time_ago(self.timestamp, now)
+    }
+}
+
+/// Human readable time elapsed between the given unix timestamps (in seconds)
+pub fn time_ago(timestamp: u64, now: u64) -> String {
+    let elapsed = now.saturating_sub(timestamp);
+    match elapsed {
+        0..60 => fl!("just-now"),
+        60..3600 => fl!("minutes-ago", minutes = elapsed / 60),
+        3600..86400 => fl!("hours-ago", hours = elapsed / 3600),
+        _ => fl!("days-ago", days = elapsed / 86400),
+    }
+}
+
+/// Holds the state of the file history panel
+#[derive(Default)]
+pub struct FileHistoryState {
+    /// File the snapshots have been loaded for
+    pub target: Option<PathBuf>,
+    /// Snapshots of the target, oldest first
+    pub snapshots: Vec<Snapshot>,
+    /// Index of the selected snapshot
+    pub selected: Option<usize>,
+    /// Diff between the selected snapshot and the open file (when it was selected)
+    pub diff: Vec<DiffChunk>,
+    /// True while the snapshots are being loaded
+    pub is_loading: bool,
+    /// Increased on every load so results of older loads can be discarded
+    pub generation: u64,
+}
+
+impl FileHistoryState {
+    /// Loads the snapshots of `target`, they are sent back with [`FileHistoryAction::Loaded`]
+    pub fn refresh(
+        &mut self,
+        vault_path: PathBuf,
+        target: Option<PathBuf>,
+    ) -> Task<cosmic::Action<Message>> {
+        self.generation += 1;
+
+        if self.target != target {
+            self.snapshots.clear();
+            self.selected = None;
+            self.diff.clear();
+        }
+        self.target = target.clone();
+
+        let Some(target) = target.filter(|t| t.starts_with(&vault_path)) else {
+            self.is_loading = false;
+            return Task::none();
+        };
+
+        self.is_loading = true;
+        let generation = self.generation;
+
+        Task::perform(load_snapshots(vault_path, target), move |res| {
+            cosmic::action::app(Message::FileHistory(FileHistoryAction::Loaded(
+                generation, res,
+            )))
+        })
+    }
+
+    /// Selects the given snapshot, computing its diff against the given (current) content
+    pub fn select(&mut self, index: usize, current: &str) {
+        let Some(snapshot) = self.snapshots.get(index) else {
+            return;
+        };
+
+        self.diff = search::collapsed_diff(&snapshot.content, current);
+        self.selected = Some(index);
+    }
+}
+
+/// Snapshots of a single file as they are stored on disk
+#[derive(Default, Serialize, Deserialize)]
+struct SnapshotStore {
+    /// Path of the file relative to the vault
+    path: PathBuf,
+    /// Every snapshot stored as the diff against the previous one (the first one against an empty file)
+    snapshots: Vec<StoredSnapshot>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredSnapshot {
+    timestamp: u64,
+    patch: Vec<StoredChunk>,
+}
+
+impl SnapshotStore {
+    /// Content of every snapshot, oldest first
+    fn contents(&self) -> Result<Vec<Snapshot>, anywho::Error> {
+        let mut content = String::new();
+        self.snapshots
+            .iter()
+            .map(|stored| {
+                content = history::apply_stored(&content, &stored.patch).ok_or_else(|| {
+                    anywho!("The history of {} is corrupted", self.path.display())
+                })?;
+
+                Ok(Snapshot {
+                    timestamp: stored.timestamp,
+                    content: content.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+fn history_dir(vault_path: &Path) -> PathBuf {
+    vault_path.join(VAULT_DATA_DIR).join("history")
+}
+
+/// File where the snapshots of the given vault file are stored
+///
+/// The name only depends on the relative path, so it's the same on every machine the vault is synced to
+fn store_file(vault_path: &Path, relative: &Path) -> PathBuf {
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    history_dir(vault_path).join(format!(
+        "{:016x}.json",
+        files::stable_hash(relative.as_bytes())
+    ))
+}
+
+async fn read_store(file: &Path, relative: &Path) -> Result<SnapshotStore, anywho::Error> {
+    match tokio::fs::read(file).await {
+        Ok(bytes) => {
+            let store: SnapshotStore = serde_json::from_slice(&bytes).map_err(|e| {
+                anywho!(
+                    "Failed to read the history of {}: {}",
+                    relative.display(),
+                    e
+                )
+            })?;
+
+            // hash collision, start a new store
+            if store.path != relative {
+                return Ok(SnapshotStore {
+                    path: relative.to_path_buf(),
+                    ..Default::default()
+                });
+            }
+            Ok(store)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(SnapshotStore {
+            path: relative.to_path_buf(),
+            ..Default::default()
+        }),
+        Err(e) => Err(anywho!(
+            "Failed to read the history of {}: {}",
+            relative.display(),
+            e
+        )),
+    }
+}
+
+/// Stores a snapshot of the given vault file, returns the path if a snapshot was taken (`None` if nothing changed)
+pub async fn take_snapshot(
+    vault_path: PathBuf,
+    path: PathBuf,
+    content: String,
+) -> Result<Option<PathBuf>, anywho::Error> {
+    let Ok(relative) = path.strip_prefix(&vault_path) else {
+        return Ok(None);
+    };
+
+    let _guard = SNAPSHOT_LOCK.lock().await;
+
+    let file = store_file(&vault_path, relative);
+    let mut store = read_store(&file, relative).await?;
+    let mut contents = store.contents()?;
+
+    let previous = contents.last().map(|s| s.content.as_str()).unwrap_or("");
+    if !store.snapshots.is_empty() && previous == content {
+        return Ok(None);
+    }
+
+    store.snapshots.push(StoredSnapshot {
+        timestamp: history::unix_now(),
+        patch: history::stored_patch(previous, &content),
+    });
+
+    // merge the oldest snapshots so the second one becomes the first
+    if store.snapshots.len() > MAX_SNAPSHOTS {
+        contents.remove(0);
+        store.snapshots.remove(0);
+        store.snapshots[0].patch = history::stored_patch("", &contents[0].content);
+    }
+
+    let bytes =
+        serde_json::to_vec(&store).map_err(|e| anywho!("Failed to encode the history: {}", e))?;
+
+    if let Some(dir) = file.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| anywho!("Failed to create the history directory: {}", e))?;
+    }
+
+    // write to a temporary file first so a crash while writing never leaves a half written history
+    let tmp_file = file.with_extension("json.tmp");
+    tokio::fs::write(&tmp_file, bytes)
+        .await
+        .map_err(|e| anywho!("Failed to write the history: {}", e))?;
+    tokio::fs::rename(&tmp_file, &file)
+        .await
+        .map_err(|e| anywho!("Failed to write the history: {}", e))?;
+
+    Ok(Some(path))
+}
+
+/// Loads every snapshot of the given vault file, oldest first
+pub async fn load_snapshots(
+    vault_path: PathBuf,
+    path: PathBuf,
+) -> Result<Vec<Snapshot>, anywho::Error> {
+    let Ok(relative) = path.strip_prefix(&vault_path) else {
+        return Ok(Vec::new());
+    };
+
+    let file = store_file(&vault_path, relative);
+    read_store(&file, relative).await?.contents()
+}
@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use chrono::{DateTime, Local};
+
+use crate::app::core::utils::files::{self, VaultFilter};
+
+/// Folder of the vault with the note templates, unless the vault config says otherwise
+pub const DEFAULT_TEMPLATES_DIR: &str = "Templates";
+
+// We do this so that we don't have to recompile the regex every time
+static PLACEHOLDER_RE: OnceLock<regex::Regex> = OnceLock::new();
+
+fn placeholder_re() -> &'static regex::Regex {
+    PLACEHOLDER_RE.get_or_init(|| regex::Regex::new(r"\{\{\s*(\w+)\s*\}\}").unwrap())
+}
+
+/// Every note inside of the given templates folder, sorted by path
+pub fn vault_templates(filter: &VaultFilter, templates_dir: &Path) -> Vec<PathBuf> {
+    let mut templates = files::vault_notes(filter, templates_dir);
+    templates.sort();
+    templates
+}
+
+/// Name of the given template as shown in the new file dialog (relative to the templates folder)
+pub fn template_name(templates_dir: &Path, template: &Path) -> String {
+    template
+        .strip_prefix(templates_dir)
+        .unwrap_or(template)
+        .with_extension("")
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// True if expanding the template needs the content of the clipboard
+pub fn uses_clipboard(template: &str) -> bool {
+    placeholder_re()
+        .captures_iter(template)
+        .any(|caps| &caps[1] == "clipboard")
+}
+
+/// Expands the placeholders of a template, returns the text and where the cursor goes (line and column)
+///
+/// Supports `{{title}}`, `{{date}}`, `{{time}}`, `{{clipboard}}` and `{{cursor}}` (only the first one
+/// places the cursor, the rest are removed), unknown placeholders are left as they are.
+/// Dates and times are in local time.
+pub fn expand(
+    template: &str,
+    title: &str,
+    clipboard: &str,
+    now: DateTime<Local>,
+) -> (String, Option<(usize, usize)>) {
+    let date = now.format("%Y-%m-%d").to_string();
+    let time = now.format("%H:%M").to_string();
+
+    let mut text = String::with_capacity(template.len());
+    let mut cursor = None;
+    let mut last = 0;
+    for caps in placeholder_re().captures_iter(template) {
+        let whole = caps.get(0).unwrap();
+        text.push_str(&template[last..whole.start()]);
+        last = whole.end();
+
+        match &caps[1] {
+            "title" => text.push_str(title),
+            "date" => text.push_str(&date),
+            "time" => text.push_str(&time),
+            "clipboard" => text.push_str(clipboard),
+            "cursor" => {
+                if cursor.is_none() {
+                    cursor = Some(text_position(&text));
+                }
+            }
+            _ => text.push_str(whole.as_str()),
+        }
+    }
+    text.push_str(&template[last..]);
+
+    (text, cursor)
+}
+
+/// Line and column (in chars) of the end of the given text
+fn text_position(text: &str) -> (usize, usize) {
+    let line = text.matches('\n').count();
+    let column = text.rsplit('\n').next().unwrap_or_default().chars().count();
+    (line, column)
+}
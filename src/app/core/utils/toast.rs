@@ -3,9 +3,13 @@
 use cosmic::widget::Toast;
 use std::time::Duration;
 
+use crate::app::Message;
+
 #[derive(Debug, Clone)]
 pub struct CedillaToast {
     pub message: String,
+    /// Label of a button shown in the toast and the message it sends
+    pub action: Option<(String, Message)>,
 }
 
 impl CedillaToast {
@@ -15,12 +19,23 @@ impl CedillaToast {
     {
         Self {
             message: message.to_string(),
+            action: None,
         }
     }
+
+    /// Shows a button in the toast sending the given message (undo a deletion...)
+    pub fn with_action(mut self, label: impl ToString, message: Message) -> Self {
+        self.action = Some((label.to_string(), message));
+        self
+    }
 }
 
-impl From<CedillaToast> for Toast<crate::app::Message> {
+impl From<CedillaToast> for Toast<Message> {
     fn from(toast: CedillaToast) -> Self {
-        Toast::new(toast.message).duration(Duration::from_secs(5))
+        let cosmic_toast = Toast::new(toast.message).duration(Duration::from_secs(5));
+        match toast.action {
+            Some((label, message)) => cosmic_toast.action(label, move |_| message.clone()),
+            None => cosmic_toast,
+        }
     }
 }
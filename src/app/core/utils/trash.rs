@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use std::path::{Path, PathBuf};
+
+use anywho::anywho;
+use cosmic::Task;
+use serde::{Deserialize, Serialize};
+
+use crate::app::Message;
+use crate::app::core::history;
+use crate::app::core::utils::files::VAULT_DATA_DIR;
+
+/// Actions related to the trash panel
+#[derive(Debug, Clone)]
+pub enum TrashAction {
+    /// Load again the items of the trash
+    Refresh,
+    /// The items of the trash have been loaded
+    Loaded(Result<Vec<TrashItem>, anywho::Error>),
+    /// Move the given items back to where they were deleted from
+    Restore(Vec<String>),
+    /// The items have been restored to the given paths
+    Restored(Vec<Result<PathBuf, anywho::Error>>),
+    /// Remove the given item for good
+    Delete(String),
+    /// Remove every item of the trash for good
+    Empty,
+    /// Items have been removed from the trash for good
+    Deleted(Result<(), anywho::Error>),
+}
+
+/// File or folder deleted from the vault
+#[derive(Debug, Clone)]
+pub struct TrashItem {
+    /// Name of the item inside of the trash
+    pub id: String,
+    /// Path the item was deleted from (relative to the vault)
+    pub path: PathBuf,
+    /// Unix timestamp (in seconds) of the deletion
+    pub deleted_at: u64,
+    pub is_dir: bool,
+}
+
+impl TrashItem {
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Holds the state of the trash panel
+#[derive(Default)]
+pub struct TrashState {
+    /// Items of the trash, newest first
+    pub items: Vec<TrashItem>,
+    /// True while the items are being loaded
+    pub is_loading: bool,
+}
+
+impl TrashState {
+    /// Loads the items of the trash, they are sent back with [`TrashAction::Loaded`]
+    pub fn refresh(&mut self, vault_path: PathBuf) -> Task<cosmic::Action<Message>> {
+        self.is_loading = true;
+        Task::perform(load_trash(vault_path), |res| {
+            cosmic::action::app(Message::Trash(TrashAction::Loaded(res)))
+        })
+    }
+}
+
+/// Where a trashed item was deleted from, stored next to it
+#[derive(Serialize, Deserialize)]
+struct TrashInfo {
+    /// Path of the item relative to the vault
+    path: PathBuf,
+    deleted_at: u64,
+}
+
+/// The trash lives inside of the vault data, so it's never shown nor watched
+fn trash_dir(vault_path: &Path) -> PathBuf {
+    vault_path.join(VAULT_DATA_DIR).join("trash")
+}
+
+fn item_file(vault_path: &Path, id: &str) -> PathBuf {
+    trash_dir(vault_path).join("files").join(id)
+}
+
+fn info_file(vault_path: &Path, id: &str) -> PathBuf {
+    trash_dir(vault_path)
+        .join("info")
+        .join(format!("{id}.json"))
+}
+
+/// Moves the given vault file or folder to the trash of the vault
+pub fn move_to_trash(vault_path: &Path, path: &Path) -> Result<TrashItem, anywho::Error> {
+    // the paths of the navbar are canonical
+    let vault_path =
+        &std::fs::canonicalize(vault_path).unwrap_or_else(|_| vault_path.to_path_buf());
+    let relative = path
+        .strip_prefix(vault_path)
+        .map_err(|_| anywho!("{} is not inside of the vault", path.display()))?;
+
+    let deleted_at = history::unix_now();
+    let mut id = deleted_at.to_string();
+    let mut counter = 1;
+    while item_file(vault_path, &id).exists() || info_file(vault_path, &id).exists() {
+        id = format!("{deleted_at}-{counter}");
+        counter += 1;
+    }
+
+    let info = TrashInfo {
+        path: relative.to_path_buf(),
+        deleted_at,
+    };
+    let bytes =
+        serde_json::to_vec(&info).map_err(|e| anywho!("Failed to encode the trash info: {}", e))?;
+
+    let info_path = info_file(vault_path, &id);
+    let item_path = item_file(vault_path, &id);
+    for dir in [info_path.parent(), item_path.parent()]
+        .into_iter()
+        .flatten()
+    {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| anywho!("Failed to create the trash directory: {}", e))?;
+    }
+
+    std::fs::write(&info_path, bytes)
+        .map_err(|e| anywho!("Failed to write the trash info: {}", e))?;
+
+    let is_dir = path.is_dir();
+    if let Err(e) = std::fs::rename(path, &item_path) {
+        let _ = std::fs::remove_file(&info_path);
+        return Err(anywho!(
+            "Failed to move {} to the trash: {}",
+            relative.display(),
+            e
+        ));
+    }
+
+    Ok(TrashItem {
+        id,
+        path: info.path,
+        deleted_at,
+        is_dir,
+    })
+}
+
+/// Loads every item of the trash of the given vault, newest first
+pub async fn load_trash(vault_path: PathBuf) -> Result<Vec<TrashItem>, anywho::Error> {
+    let info_dir = trash_dir(&vault_path).join("info");
+    let mut entries = match tokio::fs::read_dir(&info_dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(anywho!("Failed to read the trash: {}", e)),
+    };
+
+    let mut items = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| anywho!("Failed to read the trash: {}", e))?
+    {
+        let file = entry.path();
+        let Some(id) = file
+            .file_stem()
+            .filter(|_| file.extension().is_some_and(|ext| ext == "json"))
+            .map(|stem| stem.to_string_lossy().into_owned())
+        else {
+            continue;
+        };
+
+        // items whose info or content can't be found are skipped
+        let item_path = item_file(&vault_path, &id);
+        let Ok(metadata) = tokio::fs::symlink_metadata(&item_path).await else {
+            continue;
+        };
+        let Ok(bytes) = tokio::fs::read(&file).await else {
+            continue;
+        };
+        let Ok(info) = serde_json::from_slice::<TrashInfo>(&bytes) else {
+            continue;
+        };
+
+        items.push(TrashItem {
+            id,
+            path: info.path,
+            deleted_at: info.deleted_at,
+            is_dir: metadata.is_dir(),
+        });
+    }
+
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(items)
+}
+
+/// Moves the given item of the trash back to where it was deleted from, returns the restored path
+///
+/// If something else has been created there since, the item is restored next to it with a new name
+pub async fn restore(vault_path: PathBuf, id: String) -> Result<PathBuf, anywho::Error> {
+    let info_path = info_file(&vault_path, &id);
+    let bytes = tokio::fs::read(&info_path)
+        .await
+        .map_err(|e| anywho!("Failed to read the trash info: {}", e))?;
+    let info: TrashInfo = serde_json::from_slice(&bytes)
+        .map_err(|e| anywho!("Failed to read the trash info: {}", e))?;
+
+    let target = available_path(vault_path.join(&info.path));
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| anywho!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    tokio::fs::rename(item_file(&vault_path, &id), &target)
+        .await
+        .map_err(|e| anywho!("Failed to restore {}: {}", info.path.display(), e))?;
+    let _ = tokio::fs::remove_file(&info_path).await;
+
+    Ok(tokio::fs::canonicalize(&target).await.unwrap_or(target))
+}
+
+/// Restores every given item of the trash, see [`restore`]
+pub async fn restore_items(
+    vault_path: PathBuf,
+    ids: Vec<String>,
+) -> Vec<Result<PathBuf, anywho::Error>> {
+    let mut results = Vec::with_capacity(ids.len());
+    for id in ids {
+        results.push(restore(vault_path.clone(), id).await);
+    }
+    results
+}
+
+/// The given path, or the first `name (n).ext` next to it that doesn't exist yet
+fn available_path(path: PathBuf) -> PathBuf {
+    if !path.exists() {
+        return path;
+    }
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut counter = 1;
+    loop {
+        let candidate = path.with_file_name(format!("{stem} ({counter}){extension}"));
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Removes the given item of the trash for good
+pub async fn delete_permanently(vault_path: PathBuf, id: String) -> Result<(), anywho::Error> {
+    let item_path = item_file(&vault_path, &id);
+    let result = match tokio::fs::symlink_metadata(&item_path).await {
+        Ok(metadata) if metadata.is_dir() => tokio::fs::remove_dir_all(&item_path).await,
+        Ok(_) => tokio::fs::remove_file(&item_path).await,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    };
+    result.map_err(|e| anywho!("Failed to delete the item from the trash: {}", e))?;
+
+    let _ = tokio::fs::remove_file(info_file(&vault_path, &id)).await;
+    Ok(())
+}
+
+/// Removes every item of the trash for good
+pub async fn empty_trash(vault_path: PathBuf) -> Result<(), anywho::Error> {
+    match tokio::fs::remove_dir_all(trash_dir(&vault_path)).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(anywho!("Failed to empty the trash: {}", e)),
+    }
+}
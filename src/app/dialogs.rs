@@ -2,18 +2,31 @@
 
 use std::{collections::VecDeque, path::PathBuf};
 
-use cosmic::{Element, Task, iced::Alignment, theme, widget};
+use cosmic::{
+    Element, Task,
+    iced::{Alignment, Color, Font},
+    theme, widget,
+};
 
 use crate::{
-    app::{DiscardChangesAction, Message},
+    app::{
+        DiscardChangesAction, Message,
+        core::utils::{
+            links::LinkRewrite,
+            merge::{MergeState, Resolution},
+            recovery::RecoveredDocument,
+            search::DiffChunk,
+            trash::TrashAction,
+        },
+    },
     fl,
 };
 
 /// Represents a [`DialogPage`] of the application
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DialogPage {
-    /// Dialog for creating a new file on the vault
-    NewVaultFile(String),
+    /// Dialog for creating a new file on the vault, optionally from the given template
+    NewVaultFile(String, Option<PathBuf>),
     /// Dialog for creating a new folder on the vault
     NewVaultFolder(String),
     /// Delete the currently selected folder/file
@@ -22,43 +35,90 @@ pub enum DialogPage {
     RenameNode(cosmic::widget::segmented_button::Entity, String),
     /// Move the given entity to a selected folder first entity = what to move, second = selected target folder (None if none chosen yet)
     MoveNode(cosmic::widget::segmented_button::Entity, Option<PathBuf>),
+    /// Delete the given files/folders selected on the navbar
+    DeleteNodes(Vec<PathBuf>),
+    /// Move the given files/folders selected on the navbar to a selected folder (None if none chosen yet)
+    MoveNodes(Vec<PathBuf>, Option<PathBuf>),
+    /// Lists what went wrong in a bulk action, some of the files/folders were left as they were
+    BulkFailures(Vec<String>),
     /// Dialog for when closing a file with pending changes
     ConfirmCloseFile(DiscardChangesAction),
     /// Open file was modified externally
     ExternalFileModified(PathBuf),
+    /// Open file was deleted (or moved somewhere unknown) by another program
+    OpenFileRemoved(cosmic::widget::segmented_button::Entity, PathBuf),
+    /// Open file was modified externally and some of the changes conflict with the unsaved ones
+    MergeConflicts(MergeState),
+    /// Asks to update the links broken by a rename/move
+    RewriteLinks(Vec<LinkRewrite>),
+    /// Documents with unsaved changes found after a crash
+    RecoverDocuments(Vec<RecoveredDocument>),
+    /// Confirm deleting forever every item of the trash
+    EmptyTrash,
 }
 
 impl DialogPage {
     /// View of the [`DialogPage`]
-    pub fn display(&self, dialog_state: &DialogState) -> Option<Element<'_, Message>> {
+    pub fn display<'a>(&'a self, dialog_state: &'a DialogState) -> Option<Element<'a, Message>> {
         let spacing = theme::active().cosmic().spacing;
 
         let dialog = match &self {
-            DialogPage::NewVaultFile(file_name) => widget::dialog()
-                .title(fl!("new-vault-file"))
-                .primary_action(
-                    widget::button::suggested(fl!("create"))
-                        .on_press(Message::DialogAction(DialogAction::DialogComplete)),
-                )
-                .secondary_action(
-                    widget::button::standard(fl!("cancel"))
-                        .on_press(Message::DialogAction(DialogAction::DialogCancel)),
-                )
-                .control(
-                    widget::column::with_children(vec![
-                        widget::text::body(fl!("file-name")).into(),
-                        widget::text_input("", file_name.as_str())
-                            .id(dialog_state.dialog_text_input.clone())
-                            .on_input(move |name| {
+            DialogPage::NewVaultFile(file_name, template) => {
+                let mut controls: Vec<Element<'a, Message>> = vec![
+                    widget::text::body(fl!("file-name")).into(),
+                    widget::text_input("", file_name.as_str())
+                        .id(dialog_state.dialog_text_input.clone())
+                        .on_input(move |name| {
+                            Message::DialogAction(DialogAction::DialogUpdate(
+                                DialogPage::NewVaultFile(name, template.clone()),
+                            ))
+                        })
+                        .on_submit(|_x| Message::DialogAction(DialogAction::DialogComplete))
+                        .into(),
+                ];
+
+                // the templates picker is only shown when the vault has templates
+                if !dialog_state.templates.is_empty() {
+                    // offset by 1 because "Empty Note" is at index 0
+                    let selected = template
+                        .as_ref()
+                        .and_then(|t| dialog_state.templates.iter().position(|p| p == t))
+                        .map(|i| i + 1)
+                        .unwrap_or(0);
+
+                    controls.push(widget::text::body(fl!("template")).into());
+                    controls.push(
+                        widget::dropdown(
+                            &dialog_state.template_names,
+                            Some(selected),
+                            move |index| {
                                 Message::DialogAction(DialogAction::DialogUpdate(
-                                    DialogPage::NewVaultFile(name),
+                                    DialogPage::NewVaultFile(
+                                        file_name.clone(),
+                                        index
+                                            .checked_sub(1)
+                                            .and_then(|i| dialog_state.templates.get(i))
+                                            .cloned(),
+                                    ),
                                 ))
-                            })
-                            .on_submit(|_x| Message::DialogAction(DialogAction::DialogComplete))
-                            .into(),
-                    ])
-                    .spacing(spacing.space_xxs),
-                ),
+                            },
+                        )
+                        .into(),
+                    );
+                }
+
+                widget::dialog()
+                    .title(fl!("new-vault-file"))
+                    .primary_action(
+                        widget::button::suggested(fl!("create"))
+                            .on_press(Message::DialogAction(DialogAction::DialogComplete)),
+                    )
+                    .secondary_action(
+                        widget::button::standard(fl!("cancel"))
+                            .on_press(Message::DialogAction(DialogAction::DialogCancel)),
+                    )
+                    .control(widget::column::with_children(controls).spacing(spacing.space_xxs))
+            }
             DialogPage::NewVaultFolder(folder_name) => widget::dialog()
                 .title(fl!("new-folder"))
                 .primary_action(
@@ -126,37 +186,9 @@ impl DialogPage {
                     .spacing(spacing.space_xxs),
                 ),
             DialogPage::MoveNode(source_entity, selected_target) => {
-                let folder_list = widget::column::with_children(
-                    dialog_state
-                        .available_folders
-                        .iter()
-                        .map(|(path, name, indent)| {
-                            let is_selected = selected_target.as_ref() == Some(path);
-                            let indent_padding = (*indent as f32) * 32.0;
-
-                            widget::button::custom(
-                                widget::row::with_children(vec![
-                                    widget::space::horizontal().width(indent_padding).into(),
-                                    widget::icon::from_name("folder-symbolic").size(16).into(),
-                                    widget::text::body(name.clone()).into(),
-                                ])
-                                .align_y(Alignment::Center)
-                                .spacing(spacing.space_xxs),
-                            )
-                            .on_press(Message::DialogAction(DialogAction::DialogUpdate(
-                                DialogPage::MoveNode(*source_entity, Some(path.clone())),
-                            )))
-                            .class(if is_selected {
-                                theme::Button::Suggested
-                            } else {
-                                theme::Button::MenuItem
-                            })
-                            .width(cosmic::iced::Length::Fill)
-                            .into()
-                        })
-                        .collect::<Vec<Element<Message>>>(),
-                )
-                .spacing(spacing.space_xxxs);
+                let folder_list = folder_list(dialog_state, selected_target, |path| {
+                    DialogPage::MoveNode(*source_entity, Some(path))
+                });
 
                 widget::dialog()
                     .title(fl!("move-to"))
@@ -192,7 +224,12 @@ impl DialogPage {
                 )
                 .control(
                     widget::column::with_children(vec![
-                        widget::text::body(fl!("save-warning")).into(),
+                        widget::text::body(match action {
+                            // every tab is closed, not only the one in front
+                            DiscardChangesAction::SwitchVault(_) => fl!("switch-vault-warning"),
+                            _ => fl!("save-warning"),
+                        })
+                        .into(),
                     ])
                     .spacing(spacing.space_xxs),
                 ),
@@ -220,6 +257,336 @@ impl DialogPage {
                         .spacing(spacing.space_xxs),
                     )
             }
+            DialogPage::OpenFileRemoved(tab, path) => {
+                let file_name = path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("this file");
+
+                widget::dialog()
+                    .title(fl!("file-removed-externally"))
+                    .primary_action(
+                        widget::button::suggested(fl!("save-as"))
+                            .on_press(Message::DialogAction(DialogAction::DialogComplete)),
+                    )
+                    .secondary_action(widget::button::destructive(fl!("close-file")).on_press(
+                        Message::DialogAction(DialogAction::DiscardChanges(
+                            DiscardChangesAction::CloseTab(*tab),
+                        )),
+                    ))
+                    .tertiary_action(
+                        widget::button::text(fl!("keep-editing"))
+                            .on_press(Message::DialogAction(DialogAction::DialogCancel)),
+                    )
+                    .control(
+                        widget::column::with_children(vec![
+                            widget::text::body(format!("\"{file_name}\" {}", fl!("removed-text")))
+                                .into(),
+                        ])
+                        .spacing(spacing.space_xxs),
+                    )
+            }
+            DialogPage::MergeConflicts(merge) => {
+                let file_name = merge
+                    .path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+
+                let conflict_list = widget::column::with_children(
+                    merge
+                        .conflicts()
+                        .enumerate()
+                        .map(|(index, conflict)| {
+                            let choice = |label: String, resolution: Resolution| {
+                                widget::button::standard(label)
+                                    .on_press(Message::DialogAction(DialogAction::ResolveConflict(
+                                        index, resolution,
+                                    )))
+                                    .class(if conflict.resolution == Some(resolution) {
+                                        theme::Button::Suggested
+                                    } else {
+                                        theme::Button::Standard
+                                    })
+                                    .into()
+                            };
+
+                            widget::column::with_children(vec![
+                                widget::text::heading(fl!("conflict-number", number = index + 1))
+                                    .into(),
+                                widget::row::with_children(vec![
+                                    merge_side(fl!("your-version"), &conflict.ours),
+                                    merge_side(fl!("disk-version"), &conflict.theirs),
+                                ])
+                                .spacing(spacing.space_s)
+                                .into(),
+                                widget::row::with_children(vec![
+                                    choice(fl!("keep-mine"), Resolution::Ours),
+                                    choice(fl!("keep-disk"), Resolution::Theirs),
+                                    choice(fl!("keep-both"), Resolution::Both),
+                                    choice(fl!("edit-manually"), Resolution::Manual),
+                                ])
+                                .spacing(spacing.space_xxs)
+                                .into(),
+                            ])
+                            .spacing(spacing.space_xxs)
+                            .into()
+                        })
+                        .collect::<Vec<Element<Message>>>(),
+                )
+                .spacing(spacing.space_m);
+
+                widget::dialog()
+                    .title(fl!("merge-changes"))
+                    .primary_action(
+                        widget::button::suggested(fl!("apply-merge")).on_press_maybe(
+                            merge
+                                .conflicts()
+                                .all(|c| c.resolution.is_some())
+                                .then_some(Message::DialogAction(DialogAction::DialogComplete)),
+                        ),
+                    )
+                    .secondary_action(
+                        widget::button::standard(fl!("keep-my-version"))
+                            .on_press(Message::DialogAction(DialogAction::KeepMyFile)),
+                    )
+                    .tertiary_action(widget::button::text(fl!("reload")).on_press(
+                        Message::DialogAction(DialogAction::DiscardChanges(
+                            DiscardChangesAction::OpenFile(merge.path.clone()),
+                        )),
+                    ))
+                    .control(
+                        widget::column::with_children(vec![
+                            widget::text::body(fl!(
+                                "merge-changes-text",
+                                file = file_name,
+                                conflicts = merge.conflicts().count()
+                            ))
+                            .into(),
+                            widget::scrollable(conflict_list)
+                                .height(cosmic::iced::Length::Fixed(400.0))
+                                .into(),
+                        ])
+                        .spacing(spacing.space_s),
+                    )
+            }
+            DialogPage::RewriteLinks(rewrites) => {
+                let links: usize = rewrites.iter().map(|r| r.count).sum();
+
+                let file_list = widget::column::with_children(
+                    rewrites
+                        .iter()
+                        .map(|rewrite| {
+                            let file_name = rewrite
+                                .path
+                                .file_name()
+                                .map(|n| n.to_string_lossy().into_owned())
+                                .unwrap_or_default();
+
+                            widget::row::with_children(vec![
+                                widget::icon::from_name("text-x-generic-symbolic")
+                                    .size(16)
+                                    .into(),
+                                widget::text::body(file_name).into(),
+                                widget::space::horizontal().into(),
+                                widget::text::caption(rewrite.count.to_string()).into(),
+                            ])
+                            .align_y(Alignment::Center)
+                            .spacing(spacing.space_xxs)
+                            .into()
+                        })
+                        .collect::<Vec<Element<Message>>>(),
+                )
+                .spacing(spacing.space_xxxs);
+
+                widget::dialog()
+                    .title(fl!("update-links"))
+                    .primary_action(
+                        widget::button::suggested(fl!("update-links"))
+                            .on_press(Message::DialogAction(DialogAction::DialogComplete)),
+                    )
+                    .secondary_action(
+                        widget::button::standard(fl!("skip"))
+                            .on_press(Message::DialogAction(DialogAction::DialogCancel)),
+                    )
+                    .control(
+                        widget::column::with_children(vec![
+                            widget::text::body(fl!(
+                                "update-links-text",
+                                links = links,
+                                files = rewrites.len()
+                            ))
+                            .into(),
+                            widget::scrollable(file_list)
+                                .height(cosmic::iced::Length::Shrink)
+                                .into(),
+                        ])
+                        .spacing(spacing.space_s),
+                    )
+            }
+            DialogPage::RecoverDocuments(documents) => {
+                let cosmic_theme = theme::active();
+                let insert_color: Color = cosmic_theme.cosmic().success_color().into();
+                let delete_color: Color = cosmic_theme.cosmic().destructive_color().into();
+
+                let document_list = widget::column::with_children(
+                    documents
+                        .iter()
+                        .map(|document| {
+                            let name = document
+                                .path
+                                .as_ref()
+                                .and_then(|p| p.file_name())
+                                .map(|n| n.to_string_lossy().into_owned())
+                                .unwrap_or_else(|| fl!("new-file"));
+
+                            let header = widget::row::with_children(vec![
+                                widget::icon::from_name("text-x-generic-symbolic")
+                                    .size(16)
+                                    .into(),
+                                widget::text::heading(name).into(),
+                                widget::space::horizontal().into(),
+                                widget::button::text(fl!("discard"))
+                                    .on_press(Message::DialogAction(
+                                        DialogAction::DiscardRecoveredDocument(
+                                            document.key.clone(),
+                                        ),
+                                    ))
+                                    .into(),
+                                widget::button::standard(fl!("restore"))
+                                    .on_press(Message::DialogAction(
+                                        DialogAction::RestoreRecoveredDocument(
+                                            document.key.clone(),
+                                        ),
+                                    ))
+                                    .into(),
+                            ])
+                            .align_y(Alignment::Center)
+                            .spacing(spacing.space_xxs);
+
+                            let spans: Vec<cosmic::iced::core::text::Span<'_, (), Font>> = document
+                                .diff
+                                .iter()
+                                .map(|chunk| match chunk {
+                                    DiffChunk::Equal(s) => cosmic::iced::widget::span(s.as_str()),
+                                    DiffChunk::Insert(s) => {
+                                        cosmic::iced::widget::span(s.as_str()).color(insert_color)
+                                    }
+                                    DiffChunk::Delete(s) => cosmic::iced::widget::span(s.as_str())
+                                        .color(delete_color)
+                                        .strikethrough(true),
+                                })
+                                .collect();
+
+                            widget::column::with_children(vec![
+                                header.into(),
+                                cosmic::iced::widget::rich_text(spans)
+                                    .font(Font::MONOSPACE)
+                                    .size(12)
+                                    .into(),
+                            ])
+                            .spacing(spacing.space_xxs)
+                            .into()
+                        })
+                        .collect::<Vec<Element<Message>>>(),
+                )
+                .spacing(spacing.space_m);
+
+                widget::dialog()
+                    .title(fl!("recover-documents"))
+                    .primary_action(
+                        widget::button::suggested(fl!("restore-all"))
+                            .on_press(Message::DialogAction(DialogAction::DialogComplete)),
+                    )
+                    .secondary_action(
+                        widget::button::standard(fl!("later"))
+                            .on_press(Message::DialogAction(DialogAction::DialogCancel)),
+                    )
+                    .control(
+                        widget::column::with_children(vec![
+                            widget::text::body(fl!("recover-documents-text")).into(),
+                            widget::scrollable(document_list)
+                                .height(cosmic::iced::Length::Fixed(400.0))
+                                .into(),
+                        ])
+                        .spacing(spacing.space_s),
+                    )
+            }
+            DialogPage::DeleteNodes(paths) => widget::dialog()
+                .title(fl!("delete-nodes", count = paths.len()))
+                .primary_action(
+                    widget::button::suggested(fl!("delete"))
+                        .on_press(Message::DialogAction(DialogAction::DialogComplete)),
+                )
+                .secondary_action(
+                    widget::button::standard(fl!("cancel"))
+                        .on_press(Message::DialogAction(DialogAction::DialogCancel)),
+                )
+                .control(
+                    widget::column::with_children(vec![
+                        widget::text::body(fl!("delete-nodes-confirmation")).into(),
+                        widget::scrollable(node_list(paths))
+                            .height(cosmic::iced::Length::Fixed(200.0))
+                            .into(),
+                    ])
+                    .spacing(spacing.space_s),
+                ),
+            DialogPage::MoveNodes(paths, selected_target) => {
+                let folder_list = folder_list(dialog_state, selected_target, |path| {
+                    DialogPage::MoveNodes(paths.clone(), Some(path))
+                });
+
+                widget::dialog()
+                    .title(fl!("move-nodes", count = paths.len()))
+                    .primary_action(
+                        widget::button::suggested(fl!("move")).on_press_maybe(
+                            selected_target
+                                .as_ref()
+                                .map(|_| Message::DialogAction(DialogAction::DialogComplete)),
+                        ),
+                    )
+                    .secondary_action(
+                        widget::button::standard(fl!("cancel"))
+                            .on_press(Message::DialogAction(DialogAction::DialogCancel)),
+                    )
+                    .control(
+                        widget::scrollable(folder_list).height(cosmic::iced::Length::Fixed(300.0)),
+                    )
+            }
+            DialogPage::BulkFailures(failures) => widget::dialog()
+                .title(fl!("bulk-failures", count = failures.len()))
+                .primary_action(
+                    widget::button::suggested(fl!("ok"))
+                        .on_press(Message::DialogAction(DialogAction::DialogComplete)),
+                )
+                .control(
+                    widget::scrollable(
+                        widget::column::with_children(
+                            failures
+                                .iter()
+                                .map(|failure| widget::text::body(failure.as_str()).into())
+                                .collect::<Vec<Element<Message>>>(),
+                        )
+                        .spacing(spacing.space_xxs),
+                    )
+                    .height(cosmic::iced::Length::Fixed(200.0)),
+                ),
+            DialogPage::EmptyTrash => widget::dialog()
+                .title(fl!("empty-trash"))
+                .primary_action(
+                    widget::button::destructive(fl!("empty-trash"))
+                        .on_press(Message::DialogAction(DialogAction::DialogComplete)),
+                )
+                .secondary_action(
+                    widget::button::standard(fl!("cancel"))
+                        .on_press(Message::DialogAction(DialogAction::DialogCancel)),
+                )
+                .control(
+                    widget::column::with_children(vec![
+                        widget::text::body(fl!("empty-trash-confirmation")).into(),
+                    ])
+                    .spacing(spacing.space_xxs),
+                ),
         };
 
         Some(dialog.into())
@@ -241,8 +608,20 @@ pub enum DialogAction {
     OpenRenameNodeDialog(cosmic::widget::segmented_button::Entity),
     /// Asks to open the [`DialogPage`] for moving a node in the vault
     OpenMoveNodeDialog(cosmic::widget::segmented_button::Entity),
+    /// Asks to open the [`DialogPage`] for deleting the files/folders selected on the navbar
+    OpenDeleteNodesDialog(Vec<PathBuf>),
+    /// Asks to open the [`DialogPage`] for moving the files/folders selected on the navbar
+    OpenMoveNodesDialog(Vec<PathBuf>),
     /// Asks to open the [`DialogPage`] for asking confirmation before closing a file
     OpenConfirmCloseFileDialog(DiscardChangesAction),
+    /// Asks to open the [`DialogPage`] for emptying the trash of the vault
+    OpenEmptyTrashDialog,
+    /// Restores the recovered document with the given key
+    RestoreRecoveredDocument(String),
+    /// Discards the recovered document with the given key
+    DiscardRecoveredDocument(String),
+    /// Picks how to resolve the conflict at the given index of the shown merge
+    ResolveConflict(usize, Resolution),
     /// Action after user confirms wants to discard changes on Save dialog
     DiscardChanges(DiscardChangesAction),
     /// Action after user confirms/ok's/accepts the action of a Dialog
@@ -266,7 +645,10 @@ impl DialogAction {
                 Task::done(cosmic::action::app(Message::SaveFile))
             }
             DialogAction::OpenNewVaultFileDialog => {
-                dialog_pages.push_back(DialogPage::NewVaultFile(String::new()));
+                dialog_pages.push_back(DialogPage::NewVaultFile(
+                    String::new(),
+                    dialog_state.default_template.clone(),
+                ));
                 widget::text_input::focus(dialog_state.dialog_text_input.clone())
             }
             DialogAction::OpenNewVaultFolderDialog => {
@@ -276,10 +658,10 @@ impl DialogAction {
             DialogAction::DialogComplete => {
                 if let Some(dialog_page) = dialog_pages.pop_front() {
                     match dialog_page {
-                        DialogPage::NewVaultFile(file_name) => {
+                        DialogPage::NewVaultFile(file_name, template) => {
                             if !file_name.is_empty() {
                                 return Task::done(cosmic::action::app(Message::NewVaultFile(
-                                    file_name,
+                                    file_name, template,
                                 )));
                             }
                         }
@@ -307,6 +689,17 @@ impl DialogAction {
                             )));
                         }
                         DialogPage::MoveNode(_, None) => {}
+                        DialogPage::DeleteNodes(paths) => {
+                            return Task::done(cosmic::action::app(Message::DeleteNodes(paths)));
+                        }
+                        DialogPage::MoveNodes(paths, Some(target_path)) => {
+                            return Task::done(cosmic::action::app(Message::MoveNodes(
+                                paths,
+                                target_path,
+                            )));
+                        }
+                        DialogPage::MoveNodes(_, None) => {}
+                        DialogPage::BulkFailures(_) => {}
                         DialogPage::ConfirmCloseFile(action) => match action {
                             DiscardChangesAction::CloseApp => {
                                 return Task::done(cosmic::action::app(Message::SaveFile));
@@ -314,6 +707,12 @@ impl DialogAction {
                             DiscardChangesAction::OpenFile(_file_path) => {
                                 return Task::done(cosmic::action::app(Message::SaveFile));
                             }
+                            DiscardChangesAction::CloseTab(_entity) => {
+                                return Task::done(cosmic::action::app(Message::SaveFile));
+                            }
+                            DiscardChangesAction::SwitchVault(_index) => {
+                                return Task::done(cosmic::action::app(Message::SaveFile));
+                            }
                         },
 
                         DialogPage::ExternalFileModified(path) => {
@@ -321,6 +720,27 @@ impl DialogAction {
                                 DiscardChangesAction::OpenFile(path.clone()),
                             )));
                         }
+                        DialogPage::OpenFileRemoved(_tab, _path) => {
+                            return Task::done(cosmic::action::app(Message::SaveFile));
+                        }
+                        DialogPage::MergeConflicts(merge) => {
+                            return Task::done(cosmic::action::app(Message::ApplyMerge(merge)));
+                        }
+                        DialogPage::RewriteLinks(rewrites) => {
+                            return Task::done(cosmic::action::app(Message::ApplyLinkRewrites(
+                                rewrites,
+                            )));
+                        }
+                        DialogPage::RecoverDocuments(documents) => {
+                            return Task::done(cosmic::action::app(Message::RestoreDocuments(
+                                documents,
+                            )));
+                        }
+                        DialogPage::EmptyTrash => {
+                            return Task::done(cosmic::action::app(Message::Trash(
+                                TrashAction::Empty,
+                            )));
+                        }
                     }
                 }
                 Task::none()
@@ -349,20 +769,173 @@ impl DialogAction {
                 dialog_pages.push_back(DialogPage::MoveNode(entity, None));
                 Task::none()
             }
+            DialogAction::OpenDeleteNodesDialog(paths) => {
+                dialog_pages.push_back(DialogPage::DeleteNodes(paths));
+                Task::none()
+            }
+            DialogAction::OpenMoveNodesDialog(paths) => {
+                dialog_pages.push_back(DialogPage::MoveNodes(paths, None));
+                Task::none()
+            }
             DialogAction::OpenConfirmCloseFileDialog(action) => {
                 dialog_pages.push_back(DialogPage::ConfirmCloseFile(action));
                 Task::none()
             }
+            DialogAction::OpenEmptyTrashDialog => {
+                dialog_pages.push_back(DialogPage::EmptyTrash);
+                Task::none()
+            }
+            DialogAction::RestoreRecoveredDocument(key) => {
+                match take_recovered_document(dialog_pages, &key) {
+                    Some(document) => {
+                        Task::done(cosmic::action::app(Message::RestoreDocuments(vec![
+                            document,
+                        ])))
+                    }
+                    None => Task::none(),
+                }
+            }
+            DialogAction::ResolveConflict(index, resolution) => {
+                if let Some(DialogPage::MergeConflicts(merge)) = dialog_pages.front_mut() {
+                    merge.resolve(index, resolution);
+                }
+                Task::none()
+            }
+            DialogAction::DiscardRecoveredDocument(key) => {
+                match take_recovered_document(dialog_pages, &key) {
+                    Some(document) => Task::done(cosmic::action::app(
+                        Message::DiscardRecoveredDocument(document.key),
+                    )),
+                    None => Task::none(),
+                }
+            }
         }
     }
 }
 
+/// Removes the given document from the shown recovery [`DialogPage`], closing it once it's empty
+fn take_recovered_document(
+    dialog_pages: &mut VecDeque<DialogPage>,
+    key: &str,
+) -> Option<RecoveredDocument> {
+    let Some(DialogPage::RecoverDocuments(documents)) = dialog_pages.front_mut() else {
+        return None;
+    };
+
+    let position = documents.iter().position(|d| d.key == key)?;
+    let document = documents.remove(position);
+
+    if documents.is_empty() {
+        dialog_pages.pop_front();
+    }
+
+    Some(document)
+}
+
+/// Folders a node can be moved to, pressing one updates the dialog with the [`DialogPage`] returned by `on_select`
+fn folder_list<'a>(
+    dialog_state: &DialogState,
+    selected_target: &Option<PathBuf>,
+    on_select: impl Fn(PathBuf) -> DialogPage,
+) -> Element<'a, Message> {
+    let spacing = theme::active().cosmic().spacing;
+
+    widget::column::with_children(
+        dialog_state
+            .available_folders
+            .iter()
+            .map(|(path, name, indent)| {
+                let is_selected = selected_target.as_ref() == Some(path);
+                let indent_padding = (*indent as f32) * 32.0;
+
+                widget::button::custom(
+                    widget::row::with_children(vec![
+                        widget::space::horizontal().width(indent_padding).into(),
+                        widget::icon::from_name("folder-symbolic").size(16).into(),
+                        widget::text::body(name.clone()).into(),
+                    ])
+                    .align_y(Alignment::Center)
+                    .spacing(spacing.space_xxs),
+                )
+                .on_press(Message::DialogAction(DialogAction::DialogUpdate(
+                    on_select(path.clone()),
+                )))
+                .class(if is_selected {
+                    theme::Button::Suggested
+                } else {
+                    theme::Button::MenuItem
+                })
+                .width(cosmic::iced::Length::Fill)
+                .into()
+            })
+            .collect::<Vec<Element<Message>>>(),
+    )
+    .spacing(spacing.space_xxxs)
+    .into()
+}
+
+/// Names of the given files/folders, one per line
+fn node_list<'a>(paths: &[PathBuf]) -> Element<'a, Message> {
+    let spacing = theme::active().cosmic().spacing;
+
+    widget::column::with_children(
+        paths
+            .iter()
+            .map(|path| {
+                let icon = if path.is_dir() {
+                    "folder-symbolic"
+                } else {
+                    "text-x-generic-symbolic"
+                };
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+
+                widget::row::with_children(vec![
+                    widget::icon::from_name(icon).size(16).into(),
+                    widget::text::body(name).into(),
+                ])
+                .align_y(Alignment::Center)
+                .spacing(spacing.space_xxs)
+                .into()
+            })
+            .collect::<Vec<Element<Message>>>(),
+    )
+    .spacing(spacing.space_xxxs)
+    .into()
+}
+
+/// One side (version) of a merge conflict
+fn merge_side<'a>(label: String, text: &str) -> Element<'a, Message> {
+    let spacing = theme::active().cosmic().spacing;
+
+    let lines: Element<Message> = if text.is_empty() {
+        widget::text::caption(fl!("merge-removed")).into()
+    } else {
+        widget::text::body(text.trim_end_matches('\n').to_string())
+            .font(Font::MONOSPACE)
+            .into()
+    };
+
+    widget::column::with_children(vec![widget::text::caption(label).into(), lines])
+        .spacing(spacing.space_xxxs)
+        .width(cosmic::iced::Length::Fill)
+        .into()
+}
+
 /// State of all the dialog widgets of the app
 pub struct DialogState {
     /// Input inside of the Dialog Pages of the Application
     pub dialog_text_input: widget::Id,
     /// Available folders to move a node in
     pub available_folders: Vec<(PathBuf, String, u16)>,
+    /// Note templates of the vault a new file can be created from
+    pub templates: Vec<PathBuf>,
+    /// Names of the templates for the dropdown, "Empty Note" first
+    pub template_names: Vec<String>,
+    /// Template picked when the new file dialog opens, the default template of the vault
+    pub default_template: Option<PathBuf>,
 }
 
 impl Default for DialogState {
@@ -370,6 +943,9 @@ impl Default for DialogState {
         Self {
             dialog_text_input: widget::Id::unique(),
             available_folders: Vec::new(),
+            templates: Vec::new(),
+            template_names: Vec::new(),
+            default_template: None,
         }
     }
 }
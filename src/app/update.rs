@@ -4,8 +4,11 @@ mod config;
 mod dialog;
 mod editor;
 mod file;
+mod git;
 mod menu;
 mod others;
 mod preview;
+mod recovery;
+mod tabs;
 mod ui;
 mod vault;
@@ -1,9 +1,14 @@
 // SPDX-License-Identifier: GPL-3.0
 
+use crate::app::core::project::ProjectNode;
+use crate::app::core::utils::{self, CedillaToast};
 use crate::app::{AppModel, Message};
 use crate::config::{AppTheme, CedillaConfig, CedillaHighlighterTheme, ConfigInput};
+use crate::fl;
 use cosmic::iced::Font;
 use cosmic::prelude::*;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
 
 impl AppModel {
     /// Applies a config change via the handler, falling back to an in-memory update if the handler fails or is missing.
@@ -22,6 +27,87 @@ impl AppModel {
     }
 
     pub fn handle_config_input(&mut self, input: ConfigInput) -> Task<cosmic::Action<Message>> {
+        let changes_filter = matches!(
+            input,
+            ConfigInput::VaultFilterSave
+                | ConfigInput::RespectGitignore(_)
+                | ConfigInput::ShowAttachments(_)
+        );
+
+        // the settings page changes the settings of the app, the ones of the vault still win over them
+        self.revert_vault_config();
+        let task = self.update_config(input);
+        self.apply_vault_config();
+
+        if changes_filter {
+            return task.chain(self.reload_vault_filter());
+        }
+        task
+    }
+
+    /// Loads the settings stored in the current vault and applies them over the ones of the app
+    pub fn load_vault_config(&mut self) -> Task<cosmic::Action<Message>> {
+        self.revert_vault_config();
+        let (vault_config, errors) = utils::files::load_vault_config(&self.config.vault_path());
+        self.vault_config = vault_config;
+        self.apply_vault_config();
+
+        let mut tasks: Vec<_> = errors
+            .into_iter()
+            .map(|error| {
+                self.handle_add_toast(CedillaToast::new(fl!("vault-config-error", error = error)))
+            })
+            .collect();
+        tasks.push(self.reload_vault_filter());
+        Task::batch(tasks)
+    }
+
+    /// Builds again which files of the vault are shown (and which are notes) and updates the navbar
+    pub fn reload_vault_filter(&mut self) -> Task<cosmic::Action<Message>> {
+        let (vault_filter, errors) = utils::files::VaultFilter::new(&self.config);
+        self.vault_filter = vault_filter;
+        self.note_extensions_input = self.config.note_extensions.join(", ");
+        self.exclude_patterns_input = self.config.exclude_patterns.join(", ");
+
+        // every open folder shows (or hides) what the new filter says
+        let open_folders: BTreeSet<PathBuf> = self
+            .nav_model
+            .iter()
+            .filter_map(|id| match self.nav_model.data::<ProjectNode>(id) {
+                Some(ProjectNode::Folder {
+                    path, open: true, ..
+                }) => Some(path.clone()),
+                _ => None,
+            })
+            .collect();
+        for folder in open_folders {
+            self.sync_nav_folder(&folder);
+        }
+        self.refresh_vault_notes();
+
+        let tasks: Vec<_> = errors
+            .into_iter()
+            .map(|error| {
+                self.handle_add_toast(CedillaToast::new(fl!(
+                    "invalid-exclude-pattern",
+                    error = error
+                )))
+            })
+            .collect();
+        Task::batch(tasks)
+    }
+
+    /// Restores the app settings overridden by the vault settings
+    fn revert_vault_config(&mut self) {
+        let replaced = std::mem::take(&mut self.replaced_config);
+        replaced.apply(&mut self.config);
+    }
+
+    fn apply_vault_config(&mut self) {
+        self.replaced_config = self.vault_config.apply(&mut self.config);
+    }
+
+    fn update_config(&mut self, input: ConfigInput) -> Task<cosmic::Action<Message>> {
         match input {
             ConfigInput::SystemThemeModeChange => {
                 cosmic::command::set_theme(self.config.app_theme.theme())
@@ -86,6 +172,61 @@ impl AppModel {
                 }
                 Ok(())
             }),
+            ConfigInput::Autosave(mode) => self.apply_config(|config, handler| {
+                if let Some(h) = handler {
+                    config.set_autosave(h, mode).map_err(|e| e.to_string())?;
+                } else {
+                    config.autosave = mode;
+                }
+                Ok(())
+            }),
+            ConfigInput::UpdateAutosaveDelay(seconds) => {
+                let seconds = seconds as u64;
+                self.apply_config(|config, handler| {
+                    if let Some(h) = handler {
+                        config
+                            .set_autosave_delay(h, seconds)
+                            .map_err(|e| e.to_string())?;
+                    } else {
+                        config.autosave_delay = seconds;
+                    }
+                    Ok(())
+                })
+            }
+            ConfigInput::PersistentUndo(state) => self.apply_config(|config, handler| {
+                if let Some(h) = handler {
+                    config
+                        .set_persistent_undo(h, state)
+                        .map_err(|e| e.to_string())?;
+                } else {
+                    config.persistent_undo = state;
+                }
+                Ok(())
+            }),
+            ConfigInput::UpdateUndoMaxDays(days) => {
+                let days = days as u64;
+                self.apply_config(|config, handler| {
+                    if let Some(h) = handler {
+                        config
+                            .set_undo_max_days(h, days)
+                            .map_err(|e| e.to_string())?;
+                    } else {
+                        config.undo_max_days = days;
+                    }
+                    Ok(())
+                })
+            }
+            ConfigInput::UpdateUndoMaxKb(kb) => {
+                let kb = kb as u64;
+                self.apply_config(|config, handler| {
+                    if let Some(h) = handler {
+                        config.set_undo_max_kb(h, kb).map_err(|e| e.to_string())?;
+                    } else {
+                        config.undo_max_kb = kb;
+                    }
+                    Ok(())
+                })
+            }
             ConfigInput::GotenbergUrlInput(state) => self.apply_config(|config, handler| {
                 if let Some(h) = handler {
                     config
@@ -94,6 +235,53 @@ impl AppModel {
                 }
                 Ok(())
             }),
+            ConfigInput::NoteExtensionsInput(input) => {
+                self.note_extensions_input = input;
+                Task::none()
+            }
+            ConfigInput::ExcludePatternsInput(input) => {
+                self.exclude_patterns_input = input;
+                Task::none()
+            }
+            ConfigInput::VaultFilterSave => {
+                let extensions = split_list(&self.note_extensions_input);
+                let patterns = split_list(&self.exclude_patterns_input);
+
+                self.apply_config(|config, handler| {
+                    if let Some(h) = handler {
+                        config
+                            .set_note_extensions(h, extensions)
+                            .map_err(|e| e.to_string())?;
+                        config
+                            .set_exclude_patterns(h, patterns)
+                            .map_err(|e| e.to_string())?;
+                    } else {
+                        config.note_extensions = extensions;
+                        config.exclude_patterns = patterns;
+                    }
+                    Ok(())
+                })
+            }
+            ConfigInput::RespectGitignore(state) => self.apply_config(|config, handler| {
+                if let Some(h) = handler {
+                    config
+                        .set_respect_gitignore(h, state)
+                        .map_err(|e| e.to_string())?;
+                } else {
+                    config.respect_gitignore = state;
+                }
+                Ok(())
+            }),
+            ConfigInput::ShowAttachments(state) => self.apply_config(|config, handler| {
+                if let Some(h) = handler {
+                    config
+                        .set_show_attachments(h, state)
+                        .map_err(|e| e.to_string())?;
+                } else {
+                    config.show_attachments = state;
+                }
+                Ok(())
+            }),
             ConfigInput::GotenbergUrlSave => {
                 self.gotenberg_client = gotenberg_pdf::Client::new(&self.config.gotenberg_url);
                 Task::none()
@@ -183,6 +371,64 @@ impl AppModel {
                     Ok(())
                 })
             }
+            ConfigInput::RenameVault(index, name) => {
+                let mut vaults = self.config.vaults.clone();
+                let Some(vault) = vaults.get_mut(index) else {
+                    return Task::none();
+                };
+                vault.name = name;
+
+                let task = self.apply_config(|config, handler| {
+                    if let Some(h) = handler {
+                        config.set_vaults(h, vaults).map_err(|e| e.to_string())?;
+                    } else {
+                        config.vaults = vaults;
+                    }
+                    Ok(())
+                });
+
+                // the root of the navbar shows the name of the current vault
+                if self.config.current_vault() == Some(index) {
+                    let root = self.nav_model.iter().find(|&id| {
+                        matches!(
+                            self.nav_model.data::<ProjectNode>(id),
+                            Some(ProjectNode::Folder { root: true, .. })
+                        )
+                    });
+                    if let Some(root) = root {
+                        self.nav_model.text_set(root, self.config.vault_name());
+                    }
+                }
+
+                task
+            }
+            ConfigInput::RemoveVault(index) => {
+                // the current vault can't be removed
+                if index >= self.config.vaults.len() || self.config.current_vault() == Some(index) {
+                    return Task::none();
+                }
+
+                let mut vaults = self.config.vaults.clone();
+                vaults.remove(index);
+
+                self.apply_config(|config, handler| {
+                    if let Some(h) = handler {
+                        config.set_vaults(h, vaults).map_err(|e| e.to_string())?;
+                    } else {
+                        config.vaults = vaults;
+                    }
+                    Ok(())
+                })
+            }
         }
     }
 }
+
+/// Items of a comma separated list, without the empty ones
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
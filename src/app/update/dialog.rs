@@ -5,6 +5,7 @@ use crate::app::{AppModel, Message, NavMenuAction, State};
 use cosmic::prelude::*;
 use cosmic::widget::segmented_button;
 use slotmap::Key as SlotmapKey;
+use std::path::PathBuf;
 
 impl AppModel {
     pub fn handle_dialog_action(
@@ -34,17 +35,46 @@ impl AppModel {
 
         match action {
             NavMenuAction::DeleteNode(entity) => {
-                self.handle_dialog_action(dialogs::DialogAction::OpenDeleteNodeDialog(entity))
+                match self.nav_selection_for(entity) {
+                    Some(paths) => self
+                        .handle_dialog_action(dialogs::DialogAction::OpenDeleteNodesDialog(paths)),
+                    None => self
+                        .handle_dialog_action(dialogs::DialogAction::OpenDeleteNodeDialog(entity)),
+                }
             }
             NavMenuAction::RenameNode(entity) => {
                 self.handle_dialog_action(dialogs::DialogAction::OpenRenameNodeDialog(entity))
             }
             NavMenuAction::MoveNode(entity) => {
                 let vault_path = self.config.vault_path();
-                self.dialog_state.available_folders = self.collect_all_folders(&vault_path, entity);
+
+                if let Some(paths) = self.nav_selection_for(entity) {
+                    self.dialog_state.available_folders =
+                        self.collect_all_folders(&vault_path, &paths);
+                    return self
+                        .handle_dialog_action(dialogs::DialogAction::OpenMoveNodesDialog(paths));
+                }
+
+                let exclude: Vec<PathBuf> = self.nav_node_path(entity).into_iter().collect();
+                self.dialog_state.available_folders =
+                    self.collect_all_folders(&vault_path, &exclude);
 
                 self.handle_dialog_action(dialogs::DialogAction::OpenMoveNodeDialog(entity))
             }
+            NavMenuAction::ExportNodes(entity) => {
+                let paths = self
+                    .nav_selection_for(entity)
+                    .or_else(|| self.nav_node_path(entity).map(|path| vec![path]))
+                    .unwrap_or_default();
+
+                self.handle_export_nodes(paths)
+            }
+            NavMenuAction::DuplicateNode(entity) => self.handle_duplicate_node(entity),
+            NavMenuAction::CopyNodePath(entity) => match self.nav_node_path(entity) {
+                Some(path) => self.handle_copy_to_clipboard(path.to_string_lossy().into_owned()),
+                None => Task::none(),
+            },
+            NavMenuAction::CopyNodeLink(entity) => self.handle_copy_node_link(entity),
             NavMenuAction::OpenNodeFileManager(entity) => {
                 if let Some(entity) = self
                     .nav_model
@@ -100,6 +130,7 @@ impl AppModel {
                     // we update the selected nav path becasue the context menu click does not update it so
                     // if the user hasn't left clicked first it has not updated
                     self.selected_nav_path = Some(path.to_path_buf());
+                    self.load_templates();
                     self.handle_dialog_action(dialogs::DialogAction::OpenNewVaultFileDialog)
                 } else {
                     Task::none()
@@ -1,13 +1,14 @@
 // SPDX-License-Identifier: GPL-3.0
 
-use crate::app::core::editor::{EditorSearchState, EditorState};
-use crate::app::core::utils::search::SearchAction;
-use crate::app::core::utils::{self};
+use crate::app::core::editor::EditorState;
+use crate::app::core::utils::search::{SearchAction, SearchRange};
+use crate::app::core::utils::{self, CedillaToast};
 use crate::app::{
-    AppModel, Message, State, editor_scrollable_id, preview_scrollable_id, search_input_id,
-    text_editor_id,
+    AppModel, Message, State, editor_scrollable_id, preview_scrollable_id, replace_input_id,
+    search_input_id, text_editor_id,
 };
 use crate::config::BoolState;
+use crate::fl;
 use cosmic::iced::widget::scrollable::scroll_to;
 use cosmic::prelude::*;
 use widgets::text_editor;
@@ -96,7 +97,10 @@ impl AppModel {
     }
 
     pub fn handle_search(&mut self, action: SearchAction) -> Task<cosmic::Action<Message>> {
-        let State::Ready { editor, .. } = &mut self.state else {
+        let State::Ready {
+            editor, preview, ..
+        } = &mut self.state
+        else {
             return Task::none();
         };
 
@@ -105,38 +109,65 @@ impl AppModel {
         match action {
             SearchAction::ToggleSearch => {
                 editor.search.show_search_box = !editor.search.show_search_box;
-                // clear state when closing
+                // clear state when closing (the search options are kept for the whole session)
                 if !editor.search.show_search_box {
-                    editor.search = EditorSearchState::default();
+                    editor.search = editor.search.persisted();
                     widgets::text_editor::focus(text_editor_id())
                         .chain(ensure_cursor_visible(editor, sync_preview))
                 } else {
+                    // remember the user selection before searching starts moving it around
+                    editor.search.selection_range = editor.selection_range();
                     cosmic::widget::text_input::focus(search_input_id())
                 }
             }
 
             SearchAction::UpdateSearchValue(new_value) => {
                 editor.search.search_value = new_value;
-                editor.search.compute_matches(&editor.content.text());
-
-                if let Some(idx) = editor.search.current_match_index {
-                    editor.navigate_to_match(&editor.search.matches[idx].clone());
-                    ensure_cursor_visible(editor, sync_preview)
-                } else {
-                    Task::none()
-                }
+                refresh_matches(editor, sync_preview)
             }
 
             SearchAction::ToggleRegex => {
                 editor.search.use_regex = !editor.search.use_regex;
-                editor.search.compute_matches(&editor.content.text());
+                refresh_matches(editor, sync_preview)
+            }
 
-                if let Some(idx) = editor.search.current_match_index {
-                    editor.navigate_to_match(&editor.search.matches[idx].clone());
-                    ensure_cursor_visible(editor, sync_preview)
+            SearchAction::ToggleCaseInsensitive => {
+                editor.search.case_insensitive = !editor.search.case_insensitive;
+                refresh_matches(editor, sync_preview)
+            }
+
+            SearchAction::ToggleWholeWord => {
+                editor.search.whole_word = !editor.search.whole_word;
+                refresh_matches(editor, sync_preview)
+            }
+
+            SearchAction::ToggleInSelection => {
+                if editor.search.in_selection {
+                    editor.search.in_selection = false;
                 } else {
-                    Task::none()
+                    // the current selection may just be the current match, in that case use the one we remembered
+                    let current_match = editor
+                        .search
+                        .current_match_index
+                        .and_then(|i| editor.search.matches.get(i))
+                        .map(|m| SearchRange {
+                            start: (m.line, m.col_start),
+                            end: (m.line, m.col_end),
+                        });
+
+                    if let Some(range) = editor.selection_range()
+                        && Some(range) != current_match
+                    {
+                        editor.search.selection_range = Some(range);
+                    }
+
+                    if editor.search.selection_range.is_none() {
+                        return self.handle_add_toast(CedillaToast::new(fl!("no-selection")));
+                    }
+                    editor.search.in_selection = true;
                 }
+                editor.search.current_match_index = None;
+                refresh_matches(editor, sync_preview)
             }
 
             SearchAction::NextResult => {
@@ -157,13 +188,76 @@ impl AppModel {
                 }
             }
 
+            SearchAction::ToggleReplace => {
+                editor.search.show_replace = !editor.search.show_replace;
+                if editor.search.show_replace {
+                    cosmic::widget::text_input::focus(replace_input_id())
+                } else {
+                    cosmic::widget::text_input::focus(search_input_id())
+                }
+            }
+
+            SearchAction::UpdateReplaceValue(new_value) => {
+                editor.search.replace_value = new_value;
+                Task::none()
+            }
+
+            SearchAction::Replace => {
+                if !editor.replace_current_match(preview) {
+                    return Task::none();
+                }
+
+                if let Some(idx) = editor.search.current_match_index {
+                    editor.navigate_to_match(&editor.search.matches[idx].clone());
+                }
+
+                utils::images::download_images(
+                    &mut preview.markstate,
+                    &mut preview.images_in_progress,
+                    &editor.path,
+                )
+                .chain(ensure_cursor_visible(editor, sync_preview))
+            }
+
+            SearchAction::ReplaceAll => {
+                let count = editor.replace_all_matches(preview);
+                if count == 0 {
+                    return Task::none();
+                }
+
+                let images_task = utils::images::download_images(
+                    &mut preview.markstate,
+                    &mut preview.images_in_progress,
+                    &editor.path,
+                );
+
+                images_task.chain(
+                    self.handle_add_toast(CedillaToast::new(fl!(
+                        "replaced-matches",
+                        count = count
+                    ))),
+                )
+            }
+
             SearchAction::FocusSearchField => cosmic::widget::text_input::focus(search_input_id()),
         }
     }
 }
 
+/// Recomputes the search matches and goes to the current one (if any)
+fn refresh_matches(editor: &mut EditorState, sync_preview: bool) -> Task<cosmic::Action<Message>> {
+    editor.search.compute_matches(&editor.content.text());
+
+    if let Some(idx) = editor.search.current_match_index {
+        editor.navigate_to_match(&editor.search.matches[idx].clone());
+        ensure_cursor_visible(editor, sync_preview)
+    } else {
+        Task::none()
+    }
+}
+
 /// Scrolls the editor to keep the cursor visible.
-fn ensure_cursor_visible(
+pub fn ensure_cursor_visible(
     editor: &mut EditorState,
     sync_preview: bool,
 ) -> Task<cosmic::Action<Message>> {
@@ -1,19 +1,25 @@
 // SPDX-License-Identifier: GPL-3.0
 
 use crate::app::core::editor::{EditorScrollState, EditorSearchState, EditorState};
-use crate::app::core::history::HistoryState;
+use crate::app::core::history::{self, HistoryLimits, HistoryState, StoredHistory};
 use crate::app::core::preview::MarkdownPreview;
-use crate::app::core::utils::{self, CedillaToast};
+use crate::app::core::tabs::{Document, restore_scroll, tab_title};
+use crate::app::core::utils::merge::MergeState;
+use crate::app::core::utils::snapshots::{self, FileHistoryAction};
+use crate::app::core::utils::{self, CedillaToast, templates};
+use crate::app::dialogs::DialogPage;
 use crate::app::{
-    AppModel, Message, PreviewState, State, editor_scrollable_id, preview_scrollable_id,
+    AppModel, DiscardChangesAction, Message, PreviewState, State, editor_scrollable_id,
+    preview_scrollable_id, text_editor_id,
 };
-use crate::app::{DiscardChangesAction, create_default_panes};
-use crate::config::{BoolState, ShowState};
+use crate::config::{AutosaveMode, BoolState, CedillaConfig, Session, ShowState};
+use crate::fl;
 use cosmic::iced::widget::scrollable::scroll_to;
 use cosmic::prelude::*;
+use cosmic::widget::{pane_grid, segmented_button};
 use frostmark::MarkState;
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use widgets::text_editor;
 
@@ -22,13 +28,39 @@ impl AppModel {
         &mut self,
         open_with_file: Option<PathBuf>,
     ) -> Task<cosmic::Action<Message>> {
-        let panes = create_default_panes();
-
         let preview_state = match self.config.last_preview_showstate {
             ShowState::Show => PreviewState::Shown,
             ShowState::Hide => PreviewState::Hidden,
         };
 
+        let session = match self.config.open_last_file {
+            BoolState::Yes => self.config.last_session.clone(),
+            BoolState::No => None,
+        };
+
+        // restore the tabs of the last session, the file we were opened with (if any) goes on top of them
+        if let Some(session) = session
+            && !session.files.is_empty()
+        {
+            let open_with_task = match open_with_file {
+                Some(p) if p.exists() => Task::perform(utils::files::load_file(p), |res| {
+                    cosmic::action::app(Message::OpenFile(res))
+                }),
+                _ => Task::none(),
+            };
+
+            return Task::perform(
+                utils::files::load_session(session),
+                |(session, contents)| {
+                    cosmic::action::app(Message::SessionLoaded(session, contents))
+                },
+            )
+            .chain(open_with_task)
+            .chain(Task::done(cosmic::action::app(Message::SetPreviewState(
+                preview_state,
+            ))));
+        }
+
         let path = open_with_file.or_else(|| match self.config.open_last_file {
             BoolState::Yes => self.config.last_open_file.clone(),
             BoolState::No => None,
@@ -48,15 +80,18 @@ impl AppModel {
             ))));
         }
 
-        self.state = State::Ready {
+        self.open_document(Document {
             editor: EditorState {
+                id: 0,
                 path: None,
                 content: text_editor::Content::new(),
                 is_dirty: true,
+                saved_content: String::new(),
                 history: HistoryState::default(),
                 scroll: EditorScrollState::default(),
                 search: EditorSearchState::default(),
                 ignore_next_external_change: false,
+                is_orphaned: false,
             },
             preview: MarkdownPreview {
                 markstate: MarkState::with_html_and_markdown(""),
@@ -64,21 +99,20 @@ impl AppModel {
                 svgs: HashMap::new(),
                 images_in_progress: HashSet::new(),
             },
-            panes,
-            preview_state,
-        };
+        });
         Task::none()
     }
 
     pub fn handle_new_file(&mut self) -> Task<cosmic::Action<Message>> {
-        let panes = get_previous_pane_state(self);
-        let preview_state = get_previous_preview_state(self);
+        let search = get_previous_search_state(self);
 
-        self.state = State::Ready {
+        self.open_document(Document {
             editor: EditorState {
+                id: 0,
                 path: None,
                 content: text_editor::Content::new(),
                 is_dirty: true,
+                saved_content: String::new(),
                 history: HistoryState::default(),
                 scroll: EditorScrollState {
                     // pre-absorb the programmatic resets we're about to fire
@@ -86,8 +120,9 @@ impl AppModel {
                     pending_preview_scrolls: 1,
                     ..EditorScrollState::default()
                 },
-                search: EditorSearchState::default(),
+                search,
                 ignore_next_external_change: false,
+                is_orphaned: false,
             },
             preview: MarkdownPreview {
                 markstate: MarkState::with_html_and_markdown(""),
@@ -95,9 +130,7 @@ impl AppModel {
                 svgs: HashMap::new(),
                 images_in_progress: HashSet::new(),
             },
-            panes,
-            preview_state,
-        };
+        });
 
         Task::batch([
             scroll_to(editor_scrollable_id(), crate::app::utils::scroll::abs(0.0))
@@ -107,7 +140,88 @@ impl AppModel {
         ])
     }
 
-    pub fn handle_new_vault_file(&mut self, file_name: String) -> Task<cosmic::Action<Message>> {
+    pub fn handle_new_vault_file(
+        &mut self,
+        file_name: String,
+        template: Option<PathBuf>,
+    ) -> Task<cosmic::Action<Message>> {
+        let Some(template) = template else {
+            return self.create_vault_file(file_name, String::new(), None);
+        };
+
+        let template = match std::fs::read_to_string(&template) {
+            Ok(template) => template,
+            Err(e) => return self.handle_add_toast(CedillaToast::new(e)),
+        };
+
+        // the clipboard is only read if the template needs it
+        if templates::uses_clipboard(&template) {
+            return cosmic::iced::clipboard::read().map(move |clipboard| {
+                cosmic::action::app(Message::NewVaultFileFromTemplate(
+                    file_name.clone(),
+                    template.clone(),
+                    clipboard.unwrap_or_default(),
+                ))
+            });
+        }
+
+        self.handle_new_vault_file_from_template(file_name, template, String::new())
+    }
+
+    pub fn handle_new_vault_file_from_template(
+        &mut self,
+        file_name: String,
+        template: String,
+        clipboard: String,
+    ) -> Task<cosmic::Action<Message>> {
+        let (content, cursor) =
+            templates::expand(&template, &file_name, &clipboard, chrono::Local::now());
+        self.create_vault_file(file_name, content, cursor)
+    }
+
+    /// Fills the templates of the new file dialog with the ones of the vault
+    pub fn load_templates(&mut self) {
+        let vault_path = self.config.vault_path();
+        let templates_dir = vault_path.join(
+            self.vault_config
+                .templates_folder
+                .as_deref()
+                .unwrap_or(Path::new(templates::DEFAULT_TEMPLATES_DIR)),
+        );
+
+        let found = templates::vault_templates(&self.vault_filter, &templates_dir);
+
+        // the default template can be written with or without its extension
+        self.dialog_state.default_template =
+            self.vault_config
+                .default_template
+                .as_ref()
+                .and_then(|default| {
+                    let default = templates_dir.join(default);
+                    found
+                        .iter()
+                        .find(|t| **t == default || t.with_extension("") == default)
+                        .cloned()
+                });
+
+        self.dialog_state.template_names = std::iter::once(fl!("empty-note"))
+            .chain(
+                found
+                    .iter()
+                    .map(|template| templates::template_name(&templates_dir, template)),
+            )
+            .collect();
+        self.dialog_state.templates = found;
+    }
+
+    /// Creates a file with the given content in the selected folder of the vault and opens it,
+    /// with the cursor at the given line and column (if any)
+    fn create_vault_file(
+        &mut self,
+        file_name: String,
+        content: String,
+        cursor: Option<(usize, usize)>,
+    ) -> Task<cosmic::Action<Message>> {
         let dir = self.selected_directory();
 
         // find a name that doesn't already exist
@@ -128,46 +242,57 @@ impl AppModel {
         };
 
         // create the file on disk
-        if let Err(e) = std::fs::write(&file_path, "") {
+        if let Err(e) = std::fs::write(&file_path, &content) {
             return self.handle_add_toast(CedillaToast::new(e));
         }
 
         self.insert_file_node(&file_path, &dir);
+        self.refresh_vault_notes();
 
-        let panes = get_previous_pane_state(self);
-        let preview_state = get_previous_preview_state(self);
+        let search = get_previous_search_state(self);
 
-        self.state = State::Ready {
-            editor: EditorState {
-                path: Some(file_path),
-                content: text_editor::Content::new(),
-                is_dirty: true,
-                history: HistoryState::default(),
-                scroll: EditorScrollState {
-                    // pre-absorb the programmatic resets we're about to fire
-                    pending_editor_scrolls: 1,
-                    pending_preview_scrolls: 1,
-                    ..EditorScrollState::default()
-                },
-                search: EditorSearchState::default(),
-                ignore_next_external_change: false,
+        let mut editor = EditorState {
+            id: 0,
+            path: Some(file_path),
+            content: text_editor::Content::with_text(&content),
+            is_dirty: true,
+            saved_content: content.clone(),
+            history: HistoryState::default(),
+            scroll: EditorScrollState {
+                // pre-absorb the programmatic resets we're about to fire
+                pending_editor_scrolls: 1,
+                pending_preview_scrolls: 1,
+                ..EditorScrollState::default()
             },
+            search,
+            ignore_next_external_change: false,
+            is_orphaned: false,
+        };
+        if let Some((line, column)) = cursor {
+            editor.restore_cursor(line, column);
+        }
+
+        self.open_document(Document {
+            editor,
             preview: MarkdownPreview {
-                markstate: MarkState::with_html_and_markdown(""),
+                markstate: MarkState::with_html_and_markdown(&content),
                 images: HashMap::new(),
                 svgs: HashMap::new(),
                 images_in_progress: HashSet::new(),
             },
-            panes,
-            preview_state,
-        };
+        });
 
-        Task::batch([
+        let mut tasks = vec![
             scroll_to(editor_scrollable_id(), crate::app::utils::scroll::abs(0.0))
                 .map(cosmic::action::app),
             scroll_to(preview_scrollable_id(), crate::app::utils::scroll::abs(0.0))
                 .map(cosmic::action::app),
-        ])
+        ];
+        // the template says where to start typing
+        if cursor.is_some() {
+            tasks.push(text_editor::focus(text_editor_id()));
+        }
+        Task::batch(tasks)
     }
 
     pub fn handle_new_vault_folder(
@@ -204,7 +329,7 @@ impl AppModel {
     }
 
     pub fn handle_save_file(&mut self) -> Task<cosmic::Action<Message>> {
-        let State::Ready { editor, .. } = &mut self.state else {
+        let State::Ready { editor, tabs, .. } = &mut self.state else {
             return Task::none();
         };
 
@@ -213,10 +338,15 @@ impl AppModel {
         }
 
         let content = editor.content.text();
-        let path = editor.path.clone();
+        // a file deleted by another program isn't silently created again, we ask where to save it
+        let path = editor.path.clone().filter(|_| !editor.is_orphaned);
         let vault_path = self.config.vault_path.clone();
+        // the user may switch tabs before saving finishes
+        let tab = tabs.active();
         editor.ignore_next_external_change = true;
 
+        let saved = content.clone();
+
         Task::perform(
             async move {
                 match path {
@@ -230,13 +360,35 @@ impl AppModel {
                     },
                 }
             },
-            |res| cosmic::action::app(Message::FileSaved(res)),
+            move |res| cosmic::action::app(Message::FileSaved(tab, saved.clone(), res)),
         )
     }
 
     pub fn handle_open_file(
         &mut self,
         result: Result<(PathBuf, Arc<String>), anywho::Error>,
+    ) -> Task<cosmic::Action<Message>> {
+        // a file that is already open only gets its tab activated, so its unsaved changes are kept
+        if let Ok((path, _content)) = &result
+            && let Some(entity) = self.find_tab(path)
+        {
+            return self.activate_tab(entity);
+        }
+
+        self.show_loaded_file(result)
+    }
+
+    /// Replaces the document of the given file with its content on disk, discarding its changes
+    pub fn handle_reload_file(
+        &mut self,
+        result: Result<(PathBuf, Arc<String>), anywho::Error>,
+    ) -> Task<cosmic::Action<Message>> {
+        self.show_loaded_file(result)
+    }
+
+    fn show_loaded_file(
+        &mut self,
+        result: Result<(PathBuf, Arc<String>), anywho::Error>,
     ) -> Task<cosmic::Action<Message>> {
         match result {
             Ok((path, content)) => {
@@ -246,36 +398,14 @@ impl AppModel {
                     self.selected_nav_path = path.parent().map(|p| p.to_path_buf());
                 }
 
-                let panes = get_previous_pane_state(self);
-                let preview_state = get_previous_preview_state(self);
-
-                let markstate = MarkState::with_html_and_markdown(content.as_ref());
-                let images_in_progress = HashSet::new();
-
-                self.state = State::Ready {
-                    editor: EditorState {
-                        path: Some(path),
-                        content: text_editor::Content::with_text(content.as_ref()),
-                        is_dirty: false,
-                        history: HistoryState::new_with_content(content.to_string()),
-                        scroll: EditorScrollState {
-                            // pre-absorb the programmatic resets we're about to fire
-                            pending_editor_scrolls: 1,
-                            pending_preview_scrolls: 1,
-                            ..EditorScrollState::default()
-                        },
-                        search: EditorSearchState::default(),
-                        ignore_next_external_change: false,
-                    },
-                    preview: MarkdownPreview {
-                        markstate,
-                        images: HashMap::new(),
-                        svgs: HashMap::new(),
-                        images_in_progress,
-                    },
-                    panes,
-                    preview_state,
-                };
+                let search = get_previous_search_state(self);
+                let history_task = load_history_task(&self.config, path.clone(), &content);
+
+                let mut document = file_document(path, &content, search);
+                // pre-absorb the programmatic resets we're about to fire
+                document.editor.scroll.pending_editor_scrolls = 1;
+                document.editor.scroll.pending_preview_scrolls = 1;
+                self.open_document(document);
 
                 let reset_editor =
                     scroll_to(editor_scrollable_id(), crate::app::utils::scroll::abs(0.0))
@@ -289,13 +419,17 @@ impl AppModel {
                     editor, preview, ..
                 } = &mut self.state
                 {
-                    return utils::images::download_images(
+                    let images_task = utils::images::download_images(
                         &mut preview.markstate,
                         &mut preview.images_in_progress,
                         &editor.path,
-                    )
-                    .chain(reset_editor)
-                    .chain(reset_preview);
+                    );
+
+                    return images_task
+                        .chain(reset_editor)
+                        .chain(reset_preview)
+                        .chain(history_task)
+                        .chain(self.refresh_backlinks());
                 }
 
                 Task::none()
@@ -304,92 +438,601 @@ impl AppModel {
         }
     }
 
+    pub fn handle_session_loaded(
+        &mut self,
+        session: Session,
+        contents: Vec<Arc<String>>,
+    ) -> Task<cosmic::Action<Message>> {
+        // every file of the session is gone
+        if session.files.is_empty() {
+            return self.handle_new_file();
+        }
+
+        let mut tasks = Vec::with_capacity(session.files.len() * 2 + 1);
+
+        for (file, content) in session.files.into_iter().zip(contents) {
+            tasks.push(load_history_task(&self.config, file.path.clone(), &content));
+
+            let mut document = file_document(file.path, &content, EditorSearchState::default());
+            document.editor.restore_cursor(file.cursor.0, file.cursor.1);
+            document.editor.scroll.restore_offsets =
+                Some((file.editor_scroll, file.preview_scroll));
+
+            tasks.push(utils::images::download_images(
+                &mut document.preview.markstate,
+                &mut document.preview.images_in_progress,
+                &document.editor.path,
+            ));
+            self.open_document(document);
+        }
+
+        let active_tab = session
+            .active
+            .as_deref()
+            .and_then(|path| self.find_tab(path));
+
+        let State::Ready {
+            editor,
+            panes,
+            tabs,
+            ..
+        } = &mut self.state
+        else {
+            return Task::batch(tasks);
+        };
+
+        if let Some(ratio) = session.split_ratio
+            && let pane_grid::Node::Split { id, .. } = panes.layout()
+        {
+            let split = *id;
+            panes.resize(split, ratio);
+        }
+
+        let scroll_task = match active_tab {
+            Some(tab) if tab != tabs.active() => self.activate_tab(tab),
+            _ => restore_scroll(editor),
+        };
+        tasks.push(scroll_task);
+
+        Task::batch(tasks).chain(self.refresh_backlinks())
+    }
+
     pub fn handle_file_saved(
         &mut self,
+        tab: segmented_button::Entity,
+        content: String,
         result: Option<Result<PathBuf, anywho::Error>>,
     ) -> Task<cosmic::Action<Message>> {
+        let State::Ready { editor, tabs, .. } = &mut self.state else {
+            return Task::none();
+        };
+
+        let editor = if tabs.active() == tab {
+            editor
+        } else {
+            match tabs.data_mut::<Document>(tab) {
+                Some(document) => &mut document.editor,
+                // the tab has been closed meanwhile
+                None => return Task::none(),
+            }
+        };
+
         if let Some(res) = result {
             match res {
                 Ok(new_path) => {
-                    let State::Ready { editor, .. } = &mut self.state else {
-                        return Task::none();
-                    };
-
                     editor.path = Some(new_path);
-                    editor.is_dirty = false;
+                    // keep it dirty if it has been edited while saving
+                    editor.is_dirty = editor.content.text() != content;
+                    editor.is_orphaned = false;
+                    let history_task = store_history_task(&self.config, editor, &content);
+                    let snapshot_task = snapshot_task(&self.config, editor, content.clone());
+                    // what's on disk from now on, the base to merge external changes with
+                    editor.saved_content = content;
 
                     self.handle_add_toast(CedillaToast::new("File Saved!"))
+                        .chain(history_task)
+                        .chain(snapshot_task)
+                        .chain(self.refresh_backlinks())
+                        .chain(self.refresh_git())
                 }
                 Err(e) => self.handle_add_toast(CedillaToast::new(e)),
             }
         } else {
-            let State::Ready { editor, .. } = &mut self.state else {
-                return Task::none();
-            };
             editor.ignore_next_external_change = false;
 
             Task::none()
         }
     }
 
+    /// Saves every open vault file with unsaved changes in the background
+    pub fn autosave_documents(&mut self) -> Task<cosmic::Action<Message>> {
+        let vault_path = self.config.vault_path();
+        let State::Ready { editor, tabs, .. } = &mut self.state else {
+            return Task::none();
+        };
+
+        let active = tabs.active();
+        let entities: Vec<segmented_button::Entity> = tabs.iter().collect();
+        let mut tasks = Vec::new();
+
+        for tab in entities {
+            // an autosave of this document is already running
+            if self.autosaving.contains(&tab) {
+                continue;
+            }
+
+            let editor = if tab == active {
+                &mut *editor
+            } else {
+                match tabs.data_mut::<Document>(tab) {
+                    Some(document) => &mut document.editor,
+                    None => continue,
+                }
+            };
+
+            let Some(path) = editor.path.clone() else {
+                continue;
+            };
+
+            if !editor.is_dirty || editor.is_orphaned || !path.starts_with(&vault_path) {
+                continue;
+            }
+
+            let content = editor.content.text();
+            let saved = content.clone();
+            // don't warn about our own write
+            editor.ignore_next_external_change = true;
+            self.autosaving.insert(tab);
+
+            tasks.push(Task::perform(
+                utils::files::save_file(path, content),
+                move |res| cosmic::action::app(Message::Autosaved(tab, saved.clone(), res)),
+            ));
+        }
+
+        Task::batch(tasks)
+    }
+
+    pub fn handle_autosave_tick(&mut self) -> Task<cosmic::Action<Message>> {
+        let delay = std::time::Duration::from_secs(self.config.autosave_delay);
+
+        match self.last_edit {
+            Some(last_edit) if last_edit.elapsed() >= delay => {
+                self.last_edit = None;
+                self.autosave_documents()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn handle_window_unfocused(&mut self) -> Task<cosmic::Action<Message>> {
+        match self.config.autosave {
+            AutosaveMode::FocusLost => self.autosave_documents(),
+            _ => Task::none(),
+        }
+    }
+
+    pub fn handle_autosaved(
+        &mut self,
+        tab: segmented_button::Entity,
+        content: String,
+        result: Result<PathBuf, anywho::Error>,
+    ) -> Task<cosmic::Action<Message>> {
+        self.autosaving.remove(&tab);
+
+        let State::Ready { editor, tabs, .. } = &mut self.state else {
+            return Task::none();
+        };
+
+        let editor = if tabs.active() == tab {
+            editor
+        } else {
+            match tabs.data_mut::<Document>(tab) {
+                Some(document) => &mut document.editor,
+                // the tab has been closed meanwhile
+                None => return Task::none(),
+            }
+        };
+
+        match result {
+            Ok(_) => {
+                let snapshot_task = snapshot_task(&self.config, editor, content.clone());
+                let edited = editor.content.text() != content;
+                // what's on disk from now on, the base to merge external changes with
+                editor.saved_content = content;
+
+                // keep it dirty if it has been edited while saving
+                if edited {
+                    return snapshot_task
+                        .chain(self.refresh_backlinks())
+                        .chain(self.refresh_git());
+                }
+
+                editor.is_dirty = false;
+                store_history_task(&self.config, editor, &editor.saved_content)
+                    .chain(snapshot_task)
+                    .chain(self.refresh_backlinks())
+                    .chain(self.refresh_git())
+            }
+            Err(e) => {
+                editor.ignore_next_external_change = false;
+                self.handle_add_toast(CedillaToast::new(e))
+            }
+        }
+    }
+
+    /// Callback after loading the stored undo history of a file
+    pub fn handle_history_loaded(
+        &mut self,
+        stored: StoredHistory,
+    ) -> Task<cosmic::Action<Message>> {
+        let Some(tab) = self.find_tab(&stored.path) else {
+            return Task::none();
+        };
+
+        let State::Ready { editor, tabs, .. } = &mut self.state else {
+            return Task::none();
+        };
+
+        let editor = if tabs.active() == tab {
+            editor
+        } else {
+            match tabs.data_mut::<Document>(tab) {
+                Some(document) => &mut document.editor,
+                None => return Task::none(),
+            }
+        };
+
+        // only if it hasn't been edited yet and it's still the content the history leads to
+        if editor.history.history_patches.is_empty()
+            && utils::recovery::content_hash(&editor.content.text()) == stored.content_hash
+            && let Some(history) = stored.into_history()
+        {
+            editor.history = history;
+        }
+
+        Task::none()
+    }
+
     pub fn handle_discard_changes(
         &mut self,
         action: DiscardChangesAction,
     ) -> Task<cosmic::Action<Message>> {
         match action {
             DiscardChangesAction::CloseApp => {
+                utils::recovery::discard_recovery_blocking(self.recovery_written.keys());
                 std::process::exit(0);
             }
             DiscardChangesAction::OpenFile(path) => {
                 Task::perform(utils::files::load_file(path), |res| {
-                    cosmic::action::app(Message::OpenFile(res))
+                    cosmic::action::app(Message::ReloadFile(res))
                 })
             }
+            // the tab may no longer be the active one, the next autosave drops its recovery file
+            DiscardChangesAction::CloseTab(entity) => self
+                .close_tab(entity)
+                .chain(Task::done(cosmic::action::app(Message::AutosaveRecovery))),
+            DiscardChangesAction::SwitchVault(index) => self.switch_vault(index),
         }
     }
 
     pub fn handle_external_file_changed(&mut self, path: PathBuf) -> Task<cosmic::Action<Message>> {
-        let State::Ready { editor, .. } = &mut self.state else {
+        let Some(tab) = self.find_tab(&path) else {
+            return self.refresh_backlinks();
+        };
+
+        let State::Ready { editor, tabs, .. } = &mut self.state else {
             return Task::none();
         };
 
-        if editor.ignore_next_external_change {
-            editor.ignore_next_external_change = false;
+        let tab_editor = if tabs.active() == tab {
+            editor
+        } else {
+            match tabs.data_mut::<Document>(tab) {
+                Some(document) => &mut document.editor,
+                None => return Task::none(),
+            }
+        };
+
+        if tab_editor.ignore_next_external_change {
+            tab_editor.ignore_next_external_change = false;
             return Task::none();
         }
 
-        let currently_open = editor.path.as_ref() == Some(&path);
-        let already_shown = self
-            .dialog_pages
-            .iter()
-            .any(|d| matches!(d, crate::app::dialogs::DialogPage::ExternalFileModified(_)));
+        let already_shown = self.dialog_pages.iter().any(|d| {
+            matches!(
+                d,
+                DialogPage::ExternalFileModified(_) | DialogPage::MergeConflicts(_)
+            )
+        });
 
-        if currently_open && !already_shown {
-            editor.is_dirty = true; // we mark it as dirty so if the file has been removed and we keep the current version it creates it again
+        if already_shown {
+            return self.refresh_backlinks();
+        }
+
+        Task::perform(utils::files::load_file(path.clone()), move |res| {
+            cosmic::action::app(Message::ExternalFileLoaded(
+                path,
+                res.map(|(_path, content)| content),
+            ))
+        })
+        .chain(self.refresh_backlinks())
+    }
+
+    /// Marks the document of a file deleted by another program as orphaned and asks what to do with it
+    pub fn handle_external_file_removed(&mut self, path: PathBuf) -> Task<cosmic::Action<Message>> {
+        // the rename was followed (or the tab closed) meanwhile
+        let Some(tab) = self.find_tab(&path) else {
+            return Task::none();
+        };
+
+        let State::Ready { editor, tabs, .. } = &mut self.state else {
+            return Task::none();
+        };
+
+        let tab_editor = if tabs.active() == tab {
+            editor
+        } else {
+            match tabs.data_mut::<Document>(tab) {
+                Some(document) => &mut document.editor,
+                None => return Task::none(),
+            }
+        };
+
+        if tab_editor.is_orphaned {
+            return Task::none();
+        }
+
+        tab_editor.is_orphaned = true;
+        // the content only lives in the buffer now
+        tab_editor.is_dirty = true;
+        let title = tab_title(tab_editor);
+        tabs.text_set(tab, title);
+
+        self.dialog_pages
+            .push_back(DialogPage::OpenFileRemoved(tab, path));
+
+        // the dialog actions (save as/close) act on the active document, so we bring its tab to the front
+        self.activate_tab(tab).chain(self.refresh_backlinks())
+    }
+
+    /// Merges the new content on disk of an open file with its buffer, asking the user only about the conflicting changes
+    pub fn handle_external_file_loaded(
+        &mut self,
+        path: PathBuf,
+        result: Result<Arc<String>, anywho::Error>,
+    ) -> Task<cosmic::Action<Message>> {
+        let Some(tab) = self.find_tab(&path) else {
+            return Task::none();
+        };
+
+        let State::Ready {
+            editor,
+            preview,
+            tabs,
+            ..
+        } = &mut self.state
+        else {
+            return Task::none();
+        };
+
+        let (tab_editor, tab_preview) = if tabs.active() == tab {
+            (editor, preview)
+        } else {
+            match tabs.data_mut::<Document>(tab) {
+                Some(document) => (&mut document.editor, &mut document.preview),
+                None => return Task::none(),
+            }
+        };
+
+        let merge = match result {
+            Ok(theirs) => {
+                let ours = tab_editor.content.text();
+                MergeState::new(
+                    path,
+                    &tab_editor.saved_content,
+                    &ours,
+                    theirs.as_ref().clone(),
+                )
+            }
+            // the file is gone (or can't be read), there's nothing to merge with
+            Err(e) => {
+                eprintln!("{e}");
+                tab_editor.is_dirty = true; // we mark it as dirty so if the file has been removed and we keep the current version it creates it again
+                self.dialog_pages
+                    .push_back(DialogPage::ExternalFileModified(path));
+                // the dialog actions (reload/keep) act on the active document, so we bring its tab to the front
+                return self.activate_tab(tab);
+            }
+        };
+
+        if merge.conflicts().next().is_some() {
+            // another change of the same file is already being merged
+            if self
+                .dialog_pages
+                .iter()
+                .any(|d| matches!(d, DialogPage::MergeConflicts(m) if m.path == merge.path))
+            {
+                return Task::none();
+            }
+
+            tab_editor.is_dirty = true; // keeping our version saves it over the one on disk
             self.dialog_pages
-                .push_back(crate::app::dialogs::DialogPage::ExternalFileModified(path));
+                .push_back(DialogPage::MergeConflicts(merge));
+            return self.activate_tab(tab);
         }
 
+        let toast = if tab_editor.content.text() == tab_editor.saved_content {
+            fl!("file-reloaded")
+        } else {
+            fl!("external-changes-merged")
+        };
+        apply_merge(tab_editor, tab_preview, &merge);
+
+        self.handle_add_toast(CedillaToast::new(toast))
+    }
+
+    /// Applies a merge (with every conflict resolved) to the open document
+    pub fn handle_apply_merge(&mut self, merge: MergeState) -> Task<cosmic::Action<Message>> {
+        // the user may have switched tabs while resolving the conflicts
+        let Some(tab) = self.find_tab(&merge.path) else {
+            return Task::none();
+        };
+
+        let State::Ready {
+            editor,
+            preview,
+            tabs,
+            ..
+        } = &mut self.state
+        else {
+            return Task::none();
+        };
+
+        let (tab_editor, tab_preview) = if tabs.active() == tab {
+            (editor, preview)
+        } else {
+            match tabs.data_mut::<Document>(tab) {
+                Some(document) => (&mut document.editor, &mut document.preview),
+                None => return Task::none(),
+            }
+        };
+
+        apply_merge(tab_editor, tab_preview, &merge);
         Task::none()
     }
 }
 
-/// Returns the previous state of the panes if available, default if not
-fn get_previous_pane_state(
-    app_model: &AppModel,
-) -> cosmic::widget::pane_grid::State<crate::app::PaneContent> {
-    let State::Ready { panes, .. } = &app_model.state else {
-        return create_default_panes();
+/// Replaces the content of the editor with the merged text (as a change that can be undone)
+fn apply_merge(editor: &mut EditorState, preview: &mut MarkdownPreview, merge: &MergeState) {
+    let Some(merged) = merge.merged() else {
+        return;
     };
 
-    panes.clone()
+    // the content on disk is the base of the next merge
+    editor.saved_content = merge.theirs.clone();
+    editor.is_dirty = merged != merge.theirs;
+
+    if merged == editor.content.text() {
+        return;
+    }
+
+    let cursor_before = editor.content.cursor().position;
+    editor.content.replace_text(&merged);
+    editor.restore_cursor(cursor_before.line, cursor_before.column);
+    preview.update_content(&merged);
+    editor.push_history((cursor_before.line, cursor_before.column));
+}
+
+/// Creates the [`Document`] of a file with the given content
+fn file_document(path: PathBuf, content: &str, search: EditorSearchState) -> Document {
+    Document {
+        editor: EditorState {
+            id: 0,
+            path: Some(path),
+            content: text_editor::Content::with_text(content),
+            is_dirty: false,
+            saved_content: content.to_string(),
+            history: HistoryState::new_with_content(content.to_string()),
+            scroll: EditorScrollState::default(),
+            search,
+            ignore_next_external_change: false,
+            is_orphaned: false,
+        },
+        preview: MarkdownPreview {
+            markstate: MarkState::with_html_and_markdown(content),
+            images: HashMap::new(),
+            svgs: HashMap::new(),
+            images_in_progress: HashSet::new(),
+        },
+    }
+}
+
+/// Limits of the persisted undo history set on the config
+fn undo_limits(config: &CedillaConfig) -> HistoryLimits {
+    HistoryLimits {
+        max_age_days: config.undo_max_days,
+        max_size_kb: config.undo_max_kb,
+    }
+}
+
+/// Loads the stored undo history of the given file (if persisting it is enabled)
+fn load_history_task(
+    config: &CedillaConfig,
+    path: PathBuf,
+    content: &str,
+) -> Task<cosmic::Action<Message>> {
+    if config.persistent_undo != BoolState::Yes {
+        return Task::none();
+    }
+
+    let content_hash = utils::recovery::content_hash(content);
+    Task::perform(
+        history::load_history(path, content_hash, undo_limits(config)),
+        |stored| match stored {
+            Some(stored) => cosmic::action::app(Message::HistoryLoaded(stored)),
+            None => cosmic::action::none(),
+        },
+    )
+}
+
+/// Stores the undo history of the given (just saved) file (if persisting it is enabled)
+fn store_history_task(
+    config: &CedillaConfig,
+    editor: &EditorState,
+    saved_content: &str,
+) -> Task<cosmic::Action<Message>> {
+    let Some(path) = editor.path.clone() else {
+        return Task::none();
+    };
+
+    if config.persistent_undo != BoolState::Yes || editor.history.history_patches.is_empty() {
+        return Task::none();
+    }
+
+    // the history is loaded again only if the file still has the content that was saved
+    let content_hash = utils::recovery::content_hash(saved_content);
+    let stored = StoredHistory::new(path, &editor.history, content_hash);
+
+    Task::perform(history::store_history(stored, undo_limits(config)), |res| {
+        if let Err(e) = res {
+            eprintln!("{e}");
+        }
+        cosmic::action::none()
+    })
+}
+
+/// Stores a snapshot of the given (just saved) file in the history of the vault (only vault files)
+fn snapshot_task(
+    config: &CedillaConfig,
+    editor: &EditorState,
+    content: String,
+) -> Task<cosmic::Action<Message>> {
+    let vault_path = config.vault_path();
+    let Some(path) = editor.path.clone().filter(|p| p.starts_with(&vault_path)) else {
+        return Task::none();
+    };
+
+    Task::perform(
+        snapshots::take_snapshot(vault_path, path, content),
+        |res| match res {
+            Ok(Some(path)) => {
+                cosmic::action::app(Message::FileHistory(FileHistoryAction::SnapshotTaken(path)))
+            }
+            Ok(None) => cosmic::action::none(),
+            Err(e) => {
+                eprintln!("{e}");
+                cosmic::action::none()
+            }
+        },
+    )
 }
 
-/// Returns the previous state of the preview if available, default if not
-fn get_previous_preview_state(app_model: &AppModel) -> PreviewState {
-    let State::Ready { preview_state, .. } = &app_model.state else {
-        return PreviewState::Shown;
+/// Returns a closed search state keeping the previous search options if available, default if not
+fn get_previous_search_state(app_model: &AppModel) -> EditorSearchState {
+    let State::Ready { editor, .. } = &app_model.state else {
+        return EditorSearchState::default();
     };
 
-    preview_state.clone()
+    editor.search.persisted()
 }
@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use crate::app::context_page::ContextPage;
+use crate::app::core::utils::CedillaToast;
+use crate::app::core::utils::git::{self, GitAction};
+use crate::app::{AppModel, Message, State};
+use crate::fl;
+use cosmic::prelude::*;
+use std::path::PathBuf;
+
+impl AppModel {
+    pub fn handle_git(&mut self, action: GitAction) -> Task<cosmic::Action<Message>> {
+        match action {
+            GitAction::Refresh => self.refresh_git(),
+            GitAction::StatusLoaded(result) => match result {
+                Ok(repo) => {
+                    self.git.repo = repo;
+                    self.update_git_decorations();
+                    self.refresh_git_file()
+                }
+                Err(e) => {
+                    self.git.repo = None;
+                    self.update_git_decorations();
+                    self.handle_add_toast(CedillaToast::new(e))
+                }
+            },
+            GitAction::HeadLoaded(path, content) => {
+                // discard the content of a file that is no longer open
+                if self.git.head_target.as_ref() == Some(&path) {
+                    self.git.head = content;
+                    self.update_git_line_changes();
+                }
+                Task::none()
+            }
+            GitAction::LogLoaded(path, result) => {
+                if self.git.log_target.as_ref() != Some(&path) {
+                    return Task::none();
+                }
+
+                match result {
+                    Ok(log) => self.git.log = log,
+                    // a repository without commits has no log
+                    Err(e) => {
+                        eprintln!("{e}");
+                        self.git.log.clear();
+                    }
+                }
+                Task::none()
+            }
+            GitAction::Stage(path) => self.run_git(|root| git::stage(root, path)),
+            GitAction::Unstage(path) => self.run_git(|root| git::unstage(root, path)),
+            GitAction::StageAll => self.run_git(git::stage_all),
+            GitAction::UpdateCommitMessage(message) => {
+                self.git.commit_message = message;
+                Task::none()
+            }
+            GitAction::Commit => {
+                let Some(root) = self.git.repo.as_ref().map(|repo| repo.root.clone()) else {
+                    return Task::none();
+                };
+
+                let message = self.git.commit_message.trim().to_string();
+                if message.is_empty() || self.git.is_busy {
+                    return Task::none();
+                }
+
+                self.git.is_busy = true;
+                Task::perform(git::commit(root, message), |res| {
+                    cosmic::action::app(Message::Git(GitAction::Committed(res)))
+                })
+            }
+            GitAction::Staged(result) => {
+                self.git.is_busy = false;
+
+                match result {
+                    Ok(()) => self.refresh_git(),
+                    Err(e) => self
+                        .handle_add_toast(CedillaToast::new(e))
+                        .chain(self.refresh_git()),
+                }
+            }
+            GitAction::Committed(result) => {
+                self.git.is_busy = false;
+
+                match result {
+                    Ok(summary) => {
+                        self.git.commit_message.clear();
+                        self.handle_add_toast(CedillaToast::new(fl!(
+                            "committed",
+                            summary = summary
+                        )))
+                        .chain(self.refresh_git())
+                    }
+                    Err(e) => self.handle_add_toast(CedillaToast::new(e)),
+                }
+            }
+        }
+    }
+
+    /// Runs a git command changing the repository (staging...), the status is loaded again afterwards
+    fn run_git<F, Fut>(&mut self, command: F) -> Task<cosmic::Action<Message>>
+    where
+        F: FnOnce(PathBuf) -> Fut,
+        Fut: Future<Output = Result<(), anywho::Error>> + Send + 'static,
+    {
+        let Some(root) = self.git.repo.as_ref().map(|repo| repo.root.clone()) else {
+            return Task::none();
+        };
+
+        if self.git.is_busy {
+            return Task::none();
+        }
+
+        self.git.is_busy = true;
+        Task::perform(command(root), |res| {
+            cosmic::action::app(Message::Git(GitAction::Staged(res)))
+        })
+    }
+
+    /// Loads again the git status of the vault (and the state of the open file)
+    pub fn refresh_git(&mut self) -> Task<cosmic::Action<Message>> {
+        self.git.refresh(self.config.vault_path())
+    }
+
+    /// Loads the content in `HEAD` of the open file, and its log if the git panel is shown
+    pub fn refresh_git_file(&mut self) -> Task<cosmic::Action<Message>> {
+        let target = match &self.state {
+            State::Ready { editor, .. } => editor.path.clone(),
+            State::Loading => None,
+        };
+
+        let head_task = self.git.load_head(target.clone());
+
+        if !self.core.window.show_context || self.context_page != ContextPage::Git {
+            return head_task;
+        }
+
+        Task::batch([head_task, self.git.load_log(target)])
+    }
+
+    /// Computes again the changed lines of the open file against `HEAD`
+    pub fn update_git_line_changes(&mut self) {
+        if self.git.head.is_none() {
+            self.git.line_changes.clear();
+            return;
+        }
+
+        if let State::Ready { editor, .. } = &self.state {
+            let content = editor.content.text();
+            self.git
+                .update_line_changes(editor.path.as_deref(), &content);
+        }
+    }
+}
@@ -3,7 +3,7 @@
 use crate::app::app_menu::MenuAction;
 use crate::app::context_page::ContextPage;
 use crate::app::core::utils;
-use crate::app::{AppModel, Message, PreviewState};
+use crate::app::{AppModel, Message, PreviewState, vault_search_input_id};
 use crate::app::{State, dialogs};
 use cosmic::prelude::*;
 
@@ -30,12 +30,17 @@ impl AppModel {
             ),
             MenuAction::NewFile => self.handle_new_file(),
             MenuAction::NewVaultFile => {
+                self.load_templates();
                 self.handle_dialog_action(dialogs::DialogAction::OpenNewVaultFileDialog)
             }
             MenuAction::NewVaultFolder => {
                 self.handle_dialog_action(dialogs::DialogAction::OpenNewVaultFolderDialog)
             }
             MenuAction::SaveFile => self.handle_save_file(),
+            MenuAction::AddVault => self.handle_add_vault(),
+            MenuAction::CloseTab => self.handle_close_active_tab(),
+            MenuAction::NextTab => self.cycle_tab(1),
+            MenuAction::PreviousTab => self.cycle_tab(-1),
             MenuAction::TogglePreview => {
                 match preview_state {
                     PreviewState::Hidden => *preview_state = PreviewState::Shown,
@@ -46,6 +51,13 @@ impl AppModel {
             MenuAction::Undo => self.handle_undo(),
             MenuAction::Redo => self.handle_redo(),
             MenuAction::Search => self.handle_search(utils::search::SearchAction::ToggleSearch),
+            MenuAction::FindInVault => self
+                .handle_toggle_context_page(ContextPage::VaultSearch)
+                .chain(cosmic::widget::text_input::focus(vault_search_input_id())),
+            MenuAction::Backlinks => self.handle_toggle_context_page(ContextPage::Backlinks),
+            MenuAction::FileHistory => self.handle_toggle_context_page(ContextPage::FileHistory),
+            MenuAction::Trash => self.handle_toggle_context_page(ContextPage::Trash),
+            MenuAction::Git => self.handle_toggle_context_page(ContextPage::Git),
             MenuAction::CloseCurrentDialog => self.handle_dialog_action(dialogs::DialogAction::DialogCancel),
         }
     }
@@ -3,8 +3,10 @@
 use crate::app::core::utils::{self, CedillaToast};
 use crate::app::{AppModel, DiscardChangesAction, Message, PreviewState, State, dialogs};
 use crate::config::ShowState;
+use crate::fl;
 use cosmic::prelude::*;
-use std::path::Path;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::process;
 
 impl AppModel {
@@ -60,6 +62,103 @@ impl AppModel {
         }
     }
 
+    /// Exports every note of the given files/folders as PDF to a folder picked by the user, the saved content is exported
+    pub fn handle_export_nodes(&mut self, paths: Vec<PathBuf>) -> Task<cosmic::Action<Message>> {
+        if !self.config.is_gotenberg_configured() {
+            return self.handle_add_toast(CedillaToast::new(fl!("gotenberg-not-configured")));
+        }
+
+        let mut notes = BTreeSet::new();
+        for path in paths {
+            if path.is_dir() {
+                notes.extend(
+                    self.vault_notes
+                        .iter()
+                        .filter(|n| n.starts_with(&path))
+                        .cloned(),
+                );
+            } else if self.vault_filter.is_note(&path) {
+                notes.insert(path);
+            }
+        }
+
+        if notes.is_empty() {
+            return self.handle_add_toast(CedillaToast::new(fl!("no-notes-to-export")));
+        }
+
+        let client = self.gotenberg_client.clone();
+        let vault_path = self.config.vault_path.clone();
+
+        Task::perform(
+            async move {
+                let destination =
+                    PathBuf::from(utils::files::open_folder_picker(vault_path).await?);
+
+                let mut exported = 0;
+                let mut failures = Vec::new();
+                for note in notes {
+                    let name = note
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    let stem = note
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+
+                    // notes with the same name in different folders don't overwrite each other
+                    let mut pdf_path = destination.join(format!("{stem}.pdf"));
+                    let mut counter = 1;
+                    while pdf_path.exists() {
+                        pdf_path = destination.join(format!("{stem} ({counter}).pdf"));
+                        counter += 1;
+                    }
+
+                    let result = match tokio::fs::read_to_string(&note).await {
+                        Ok(content) => {
+                            utils::pdf::export_pdf(
+                                client.clone(),
+                                Some(note.clone()),
+                                content,
+                                pdf_path.to_string_lossy().into_owned(),
+                                HashMap::new(),
+                            )
+                            .await
+                        }
+                        Err(e) => Err(anywho::anywho!("{e}")),
+                    };
+
+                    match result {
+                        Ok(()) => exported += 1,
+                        Err(e) => failures.push(format!("{name}: {e}")),
+                    }
+                }
+
+                Some((exported, failures))
+            },
+            |res| match res {
+                Some((exported, failures)) => {
+                    cosmic::action::app(Message::NodesExported(exported, failures))
+                }
+                // no folder was picked
+                None => cosmic::action::none(),
+            },
+        )
+    }
+
+    pub fn handle_nodes_exported(
+        &mut self,
+        exported: usize,
+        failures: Vec<String>,
+    ) -> Task<cosmic::Action<Message>> {
+        self.show_bulk_failures(failures);
+
+        if exported == 0 {
+            return Task::none();
+        }
+        self.handle_add_toast(CedillaToast::new(fl!("exported-notes", count = exported)))
+    }
+
     pub fn handle_app_close_requested(&mut self) -> Task<cosmic::Action<Message>> {
         let State::Ready {
             editor,
@@ -70,6 +169,8 @@ impl AppModel {
             return Task::none();
         };
 
+        let session = self.current_session();
+
         if let Some(handler) = &self.config_handler {
             let current_preview_state = match preview_state {
                 PreviewState::Hidden => ShowState::Hide,
@@ -98,19 +199,29 @@ impl AppModel {
             if let Err(err) = self.config.set_last_open_file(handler, editor.path.clone()) {
                 eprintln!("{err}");
             }
+
+            if let Err(err) = self.config.set_last_session(handler, session.clone()) {
+                eprintln!("{err}");
+            }
         }
 
-        if editor.is_dirty {
-            // if it's a vault path with any modification or if it's a new file with any content
-            if editor.needs_confirmation() {
-                self.handle_dialog_action(dialogs::DialogAction::OpenConfirmCloseFileDialog(
-                    DiscardChangesAction::CloseApp,
-                ))
-            } else {
-                process::exit(0);
+        // the current vault remembers where it was left for when we switch back to it
+        if let Some(current) = self.config.current_vault() {
+            let mut vaults = self.config.vaults.clone();
+            vaults[current].last_open_file = editor.path.clone();
+            vaults[current].last_session = session;
+            self.set_vaults(vaults);
+        }
+
+        // if any tab has a vault path with any modification or a new file with any content
+        match self.first_unsaved_tab() {
+            Some(tab) => self.activate_tab(tab).chain(self.handle_dialog_action(
+                dialogs::DialogAction::OpenConfirmCloseFileDialog(DiscardChangesAction::CloseApp),
+            )),
+            None => {
+                utils::recovery::discard_recovery_blocking(self.recovery_written.keys());
+                process::exit(0)
             }
-        } else {
-            process::exit(0);
         }
     }
 }
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0
 
+use crate::app::core::tabs::Document;
 use crate::app::core::utils::Image;
 use crate::app::{
     AppModel, Message, PreviewState, State, editor_scrollable_id, preview_scrollable_id,
@@ -26,12 +27,30 @@ impl AppModel {
         &mut self,
         res: Result<Image, anywho::Error>,
     ) -> Task<cosmic::Action<Message>> {
-        let State::Ready { preview, .. } = &mut self.state else {
+        let State::Ready { preview, tabs, .. } = &mut self.state else {
             return Task::none();
         };
 
         match res {
             Ok(image) => {
+                // the image may have been requested by the preview of other tabs too
+                let entities: Vec<_> = tabs.iter().collect();
+                for entity in entities {
+                    if let Some(document) = tabs.data_mut::<Document>(entity)
+                        && document.preview.images_in_progress.contains(&image.url)
+                    {
+                        if image.is_svg {
+                            document
+                                .preview
+                                .insert_svg(image.url.clone(), image.bytes.clone());
+                        } else {
+                            document
+                                .preview
+                                .insert_image(image.url.clone(), image.bytes.clone());
+                        }
+                    }
+                }
+
                 if image.is_svg {
                     preview.insert_svg(image.url, image.bytes);
                 } else {
@@ -101,6 +120,15 @@ impl AppModel {
         if is_editor {
             editor.scroll.last_editor_viewport = Some(viewport);
 
+            // we were waiting for the viewport to scroll to the cursor
+            if editor.scroll.reveal_cursor {
+                editor.scroll.reveal_cursor = false;
+                editor.scroll.pending_editor_scrolls =
+                    editor.scroll.pending_editor_scrolls.saturating_sub(1);
+                let sync_preview = self.config.scrollbar_sync == BoolState::Yes;
+                return super::editor::ensure_cursor_visible(editor, sync_preview);
+            }
+
             // programmatic scroll we fired, consume it and skip sync
             if editor.scroll.pending_editor_scrolls > 0 {
                 editor.scroll.pending_editor_scrolls -= 1;
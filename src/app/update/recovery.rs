@@ -0,0 +1,163 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use crate::app::core::editor::{EditorScrollState, EditorSearchState, EditorState};
+use crate::app::core::history::{self, HistoryState};
+use crate::app::core::preview::MarkdownPreview;
+use crate::app::core::tabs::Document;
+use crate::app::core::utils::{
+    self,
+    recovery::{self, RecoveredDocument, RecoverySnapshot},
+};
+use crate::app::{AppModel, Message, State, dialogs::DialogPage};
+use cosmic::prelude::*;
+use frostmark::MarkState;
+use std::collections::{HashMap, HashSet};
+use widgets::text_editor;
+
+impl AppModel {
+    /// Writes the documents with unsaved changes to the recovery directory
+    pub fn handle_autosave_recovery(&mut self) -> Task<cosmic::Action<Message>> {
+        let State::Ready { editor, tabs, .. } = &self.state else {
+            return Task::none();
+        };
+
+        let active = tabs.active();
+        let mut snapshots = Vec::new();
+        let mut written = HashMap::new();
+
+        for entity in tabs.iter() {
+            let editor = if entity == active {
+                editor
+            } else {
+                match tabs.data::<Document>(entity) {
+                    Some(document) => &document.editor,
+                    None => continue,
+                }
+            };
+
+            if !(editor.is_dirty && editor.needs_confirmation()) {
+                continue;
+            }
+
+            let content = editor.content.text();
+            let key = recovery::recovery_key(editor.path.as_deref(), editor.id);
+            let hash = recovery::content_hash(&content);
+
+            // unchanged since the last autosave
+            if self.recovery_written.get(&key) != Some(&hash) {
+                snapshots.push(RecoverySnapshot {
+                    key: key.clone(),
+                    path: editor.path.clone(),
+                    content,
+                });
+            }
+            written.insert(key, hash);
+        }
+
+        // documents that have been saved or closed since the last autosave
+        let stale: Vec<String> = self
+            .recovery_written
+            .keys()
+            .filter(|key| !written.contains_key(*key))
+            .cloned()
+            .collect();
+
+        if snapshots.is_empty() && stale.is_empty() {
+            return Task::none();
+        }
+
+        self.recovery_written = written;
+
+        Task::perform(recovery::write_recovery(snapshots, stale), |res| {
+            if let Err(e) = res {
+                eprintln!("{e}");
+            }
+            cosmic::action::none()
+        })
+    }
+
+    /// Callback after looking for documents to recover on startup
+    pub fn handle_recovery_loaded(
+        &mut self,
+        documents: Vec<RecoveredDocument>,
+    ) -> Task<cosmic::Action<Message>> {
+        if !documents.is_empty() {
+            self.dialog_pages
+                .push_back(DialogPage::RecoverDocuments(documents));
+        }
+
+        Task::none()
+    }
+
+    /// Opens the given recovered documents as documents with unsaved changes
+    pub fn handle_restore_documents(
+        &mut self,
+        documents: Vec<RecoveredDocument>,
+    ) -> Task<cosmic::Action<Message>> {
+        let mut image_tasks = Vec::with_capacity(documents.len());
+        let mut discard_tasks = Vec::with_capacity(documents.len());
+
+        for recovered in documents {
+            // undoing the restored changes brings back the version on disk
+            let mut history = HistoryState::new_with_content(recovered.original.clone());
+            history
+                .history_patches
+                .push(history::make_patch(&recovered.original, &recovered.content));
+            history.history_index = 1;
+            history.cursor_before.push((0, 0));
+            history.cursor_after.push((0, 0));
+
+            let mut document = Document {
+                editor: EditorState {
+                    id: 0,
+                    path: recovered.path,
+                    content: text_editor::Content::with_text(&recovered.content),
+                    is_dirty: true,
+                    saved_content: recovered.original.clone(),
+                    history,
+                    scroll: EditorScrollState::default(),
+                    search: EditorSearchState::default(),
+                    ignore_next_external_change: false,
+                    is_orphaned: false,
+                },
+                preview: MarkdownPreview {
+                    markstate: MarkState::with_html_and_markdown(&recovered.content),
+                    images: HashMap::new(),
+                    svgs: HashMap::new(),
+                    images_in_progress: HashSet::new(),
+                },
+            };
+
+            image_tasks.push(utils::images::download_images(
+                &mut document.preview.markstate,
+                &mut document.preview.images_in_progress,
+                &document.editor.path,
+            ));
+            self.open_document(document);
+
+            // the next autosave writes it again under the key of this instance
+            self.recovery_written.remove(&recovered.key);
+            discard_tasks.push(Task::perform(
+                recovery::discard_recovery(recovered.key),
+                |_| cosmic::action::none(),
+            ));
+        }
+
+        Task::batch([
+            Task::batch(discard_tasks)
+                .chain(Task::done(cosmic::action::app(Message::AutosaveRecovery))),
+            Task::batch(image_tasks),
+            self.refresh_backlinks(),
+        ])
+    }
+
+    /// Removes the recovery files of the given recovered document
+    pub fn handle_discard_recovered_document(
+        &mut self,
+        key: String,
+    ) -> Task<cosmic::Action<Message>> {
+        self.recovery_written.remove(&key);
+
+        Task::perform(recovery::discard_recovery(key), |_| cosmic::action::none())
+    }
+}
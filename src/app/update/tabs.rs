@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use crate::app::core::tabs::Document;
+use crate::app::{AppModel, DiscardChangesAction, Message, State, dialogs};
+use cosmic::prelude::*;
+use cosmic::widget::segmented_button;
+
+impl AppModel {
+    pub fn handle_activate_tab(
+        &mut self,
+        entity: segmented_button::Entity,
+    ) -> Task<cosmic::Action<Message>> {
+        self.activate_tab(entity)
+    }
+
+    pub fn handle_close_tab(
+        &mut self,
+        entity: segmented_button::Entity,
+    ) -> Task<cosmic::Action<Message>> {
+        let State::Ready { editor, tabs, .. } = &self.state else {
+            return Task::none();
+        };
+
+        let is_active = tabs.active() == entity;
+        let needs_confirmation = if is_active {
+            editor.is_dirty && editor.needs_confirmation()
+        } else {
+            match tabs.data::<Document>(entity) {
+                Some(document) => document.editor.is_dirty && document.editor.needs_confirmation(),
+                None => return Task::none(),
+            }
+        };
+
+        if !needs_confirmation {
+            return self.close_tab(entity);
+        }
+
+        // show the document that is going to be closed before asking
+        self.activate_tab(entity).chain(self.handle_dialog_action(
+            dialogs::DialogAction::OpenConfirmCloseFileDialog(DiscardChangesAction::CloseTab(
+                entity,
+            )),
+        ))
+    }
+
+    pub fn handle_close_active_tab(&mut self) -> Task<cosmic::Action<Message>> {
+        let State::Ready { tabs, .. } = &self.state else {
+            return Task::none();
+        };
+
+        self.handle_close_tab(tabs.active())
+    }
+}
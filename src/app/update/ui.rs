@@ -36,7 +36,17 @@ impl AppModel {
         &mut self,
         page: ContextPage,
     ) -> Task<cosmic::Action<Message>> {
-        page.toggle_context_page(self)
+        let task = page.toggle_context_page(self);
+        // the open file may have changed while the backlinks or file history panels were hidden
+        let task = task
+            .chain(self.refresh_backlinks())
+            .chain(self.refresh_file_history())
+            .chain(self.refresh_trash());
+
+        if self.core.window.show_context && self.context_page == ContextPage::Git {
+            return task.chain(self.refresh_git());
+        }
+        task
     }
 
     pub fn handle_surface(&mut self, a: surface::Action) -> Task<cosmic::Action<Message>> {
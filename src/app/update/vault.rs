@@ -1,11 +1,20 @@
 // SPDX-License-Identifier: GPL-3.0
 
+use crate::app::context_page::ContextPage;
+use crate::app::core::history;
 use crate::app::core::project::ProjectNode;
+use crate::app::core::utils::links::{self, BacklinksAction, LinkRewrite};
+use crate::app::core::utils::search::{self, VaultReplacePreview, VaultSearchAction};
+use crate::app::core::utils::snapshots::FileHistoryAction;
+use crate::app::core::utils::trash::{self, TrashAction, TrashState};
 use crate::app::core::utils::{self, CedillaToast};
-use crate::app::{AppModel, Message, State};
+use crate::app::{AppModel, DiscardChangesAction, Message, State, VaultChange, dialogs};
+use crate::config::{BoolState, CedillaConfig, LinkStyle, Vault};
+use crate::fl;
 use cosmic::prelude::*;
 use cosmic::widget::segmented_button;
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 
 impl AppModel {
     pub fn handle_delete_node(
@@ -25,27 +34,28 @@ impl AppModel {
             ProjectNode::Folder { path, .. } => path.clone(),
         };
 
+        // deleted nodes go to the trash of the vault, so they can be restored
         editor.ignore_next_external_change = true;
-        let delete_result = match &node {
-            ProjectNode::File { .. } => std::fs::remove_file(&path),
-            ProjectNode::Folder { .. } => std::fs::remove_dir_all(&path),
+        let item = match trash::move_to_trash(&self.config.vault_path(), &path) {
+            Ok(item) => item,
+            Err(e) => {
+                editor.ignore_next_external_change = false;
+                return self.handle_add_toast(CedillaToast::new(e));
+            }
         };
 
-        if let Err(e) = delete_result {
-            editor.ignore_next_external_change = false;
-            return self.handle_add_toast(CedillaToast::new(e));
-        }
-
         self.remove_nav_node(&path);
+        self.refresh_vault_notes();
 
-        // if the deleted file was currently open, create a new empty file
-        if let State::Ready { editor, .. } = &self.state
-            && editor.path.as_deref() == Some(&path)
-        {
-            return self.handle_new_file();
-        }
+        let toast = CedillaToast::new(fl!("moved-to-trash", name = item.name())).with_action(
+            fl!("undo"),
+            Message::Trash(TrashAction::Restore(vec![item.id.clone()])),
+        );
 
-        Task::none()
+        // close the tabs of the deleted files (a new empty file is created if no tabs are left)
+        self.close_tabs_under(&path)
+            .chain(self.handle_add_toast(toast))
+            .chain(self.refresh_trash())
     }
 
     pub fn handle_rename_node(
@@ -67,7 +77,10 @@ impl AppModel {
 
         let new_name = match &node {
             ProjectNode::File { .. } => {
-                if new_name.ends_with(".md") {
+                // attachments are renamed as they are written
+                if self.vault_filter.is_note(Path::new(&new_name))
+                    || !self.vault_filter.is_note(&old_path)
+                {
                     new_name
                 } else {
                     format!("{}.md", new_name)
@@ -92,6 +105,7 @@ impl AppModel {
             )));
         }
 
+        let old_notes = self.vault_notes.clone();
         editor.ignore_next_external_change = true;
         if let Err(e) = std::fs::rename(&old_path, &new_path) {
             editor.ignore_next_external_change = false;
@@ -99,23 +113,78 @@ impl AppModel {
         }
 
         self.rename_nav_node(&old_path, &new_path, &new_name);
+        self.refresh_vault_notes();
 
-        // update the open editor state if the open file was inside the renamed path
-        #[allow(clippy::collapsible_if)]
-        if let State::Ready { editor, .. } = &mut self.state {
-            if let Some(current) = editor.path.as_deref() {
-                if current.starts_with(&old_path) {
-                    let suffix = current.strip_prefix(&old_path).unwrap().to_path_buf();
-                    editor.path = Some(if suffix == std::path::Path::new("") {
-                        new_path.clone()
-                    } else {
-                        new_path.join(suffix)
-                    });
+        // update the open documents that were inside the renamed path
+        self.rename_open_paths(&old_path, &new_path);
+
+        self.plan_link_rewrites(old_notes, vec![(old_path, new_path)])
+    }
+
+    pub fn handle_duplicate_node(
+        &mut self,
+        entity: segmented_button::Entity,
+    ) -> Task<cosmic::Action<Message>> {
+        let Some(path) = self.nav_node_path(entity) else {
+            return Task::none();
+        };
+
+        Task::perform(utils::files::duplicate_node(path), |res| {
+            cosmic::action::app(Message::NodeDuplicated(res))
+        })
+    }
+
+    pub fn handle_node_duplicated(
+        &mut self,
+        result: Result<PathBuf, anywho::Error>,
+    ) -> Task<cosmic::Action<Message>> {
+        match result {
+            Ok(path) => {
+                if let Some(parent) = path.parent() {
+                    self.sync_nav_folder(&parent.to_path_buf());
                 }
+                self.refresh_vault_notes();
+                Task::none()
             }
+            Err(e) => self.handle_add_toast(CedillaToast::new(e)),
         }
+    }
 
-        Task::none()
+    /// Copies a markdown link to the given node, relative to the open note so it can be pasted in it
+    /// (or a wiki link if the vault says so and the node is a note)
+    pub fn handle_copy_node_link(
+        &mut self,
+        entity: segmented_button::Entity,
+    ) -> Task<cosmic::Action<Message>> {
+        let Some(path) = self.nav_node_path(entity) else {
+            return Task::none();
+        };
+
+        if self.copies_wikilink(&path) {
+            let link = links::wikilink(&self.config.vault_path(), &self.vault_notes, &path);
+            return self.handle_copy_to_clipboard(link);
+        }
+
+        let open_dir = match &self.state {
+            State::Ready { editor, .. } => editor
+                .path
+                .as_deref()
+                .and_then(Path::parent)
+                .map(Path::to_path_buf),
+            State::Loading => None,
+        };
+        // without an open note the link is relative to the root of the vault
+        let from_dir = open_dir.unwrap_or_else(|| {
+            let vault_path = self.config.vault_path();
+            std::fs::canonicalize(&vault_path).unwrap_or(vault_path)
+        });
+
+        self.handle_copy_to_clipboard(links::markdown_link(&from_dir, &path))
+    }
+
+    /// True if the link copied for the given node is a wiki link (only notes, folders have no wiki links)
+    pub fn copies_wikilink(&self, path: &Path) -> bool {
+        self.vault_config.link_style == Some(LinkStyle::Wikilink) && self.vault_filter.is_note(path)
     }
 
     pub fn handle_move_node(
@@ -142,6 +211,16 @@ impl AppModel {
             return Task::none();
         }
 
+        if target_path.starts_with(&source_path) {
+            return self.handle_add_toast(CedillaToast::new(fl!("move-into-itself")));
+        }
+
+        // renaming would silently replace what is already there
+        if dest.exists() {
+            return self.handle_add_toast(CedillaToast::new(fl!("already-exists")));
+        }
+
+        let old_notes = self.vault_notes.clone();
         editor.ignore_next_external_change = true;
         if let Err(e) = std::fs::rename(&source_path, &dest) {
             editor.ignore_next_external_change = false;
@@ -157,6 +236,7 @@ impl AppModel {
 
         if let Some(target_entity) = target_entity {
             self.move_nav_node(source_entity, target_entity, &dest);
+            self.refresh_vault_notes();
         } else {
             // target folder was never opened, reload from disk
             let vault_path = self.config.vault_path();
@@ -164,15 +244,212 @@ impl AppModel {
             self.open_vault_folder(&vault_path);
         }
 
-        if let State::Ready { editor, .. } = &mut self.state
-            && editor.path.as_deref() == Some(&source_path)
-        {
-            editor.path = Some(dest);
+        self.rename_open_paths(&source_path, &dest);
+
+        self.plan_link_rewrites(old_notes, vec![(source_path, dest)])
+    }
+
+    /// Moves every given file/folder to the trash, the ones that fail stay selected and are listed afterwards
+    pub fn handle_delete_nodes(&mut self, paths: Vec<PathBuf>) -> Task<cosmic::Action<Message>> {
+        let State::Ready { editor, .. } = &mut self.state else {
+            return Task::none();
+        };
+
+        let vault_path = self.config.vault_path();
+        let mut deleted = Vec::new();
+        let mut ids = Vec::new();
+        let mut failures = Vec::new();
+
+        editor.ignore_next_external_change = true;
+        for path in paths {
+            match trash::move_to_trash(&vault_path, &path) {
+                Ok(item) => {
+                    ids.push(item.id);
+                    deleted.push(path);
+                }
+                Err(e) => failures.push(format!("{}: {}", node_name(&path), e)),
+            }
+        }
+
+        if deleted.is_empty() {
+            editor.ignore_next_external_change = false;
+            self.show_bulk_failures(failures);
+            return Task::none();
+        }
+
+        let mut tasks = Vec::with_capacity(deleted.len() + 2);
+        for path in &deleted {
+            self.remove_nav_node(path);
+            // close the tabs of the deleted files (a new empty file is created if no tabs are left)
+            tasks.push(self.close_tabs_under(path));
         }
+        self.refresh_vault_notes();
+        self.show_bulk_failures(failures);
+
+        let toast = CedillaToast::new(fl!("moved-items-to-trash", count = deleted.len()))
+            .with_action(fl!("undo"), Message::Trash(TrashAction::Restore(ids)));
+        tasks.push(self.handle_add_toast(toast));
+        tasks.push(self.refresh_trash());
+
+        Task::batch(tasks)
+    }
+
+    /// Moves every given file/folder into `target_path`, the ones that fail stay selected and are listed afterwards
+    pub fn handle_move_nodes(
+        &mut self,
+        paths: Vec<PathBuf>,
+        target_path: PathBuf,
+    ) -> Task<cosmic::Action<Message>> {
+        let State::Ready { editor, .. } = &mut self.state else {
+            return Task::none();
+        };
+
+        let old_notes = self.vault_notes.clone();
+        let mut moves = Vec::new();
+        let mut failures = Vec::new();
+
+        editor.ignore_next_external_change = true;
+        for source_path in paths {
+            let Some(file_name) = source_path.file_name() else {
+                continue;
+            };
+            let dest = target_path.join(file_name);
+
+            // already there
+            if dest == source_path {
+                continue;
+            }
 
+            let result = if target_path.starts_with(&source_path) {
+                Err(fl!("move-into-itself"))
+            } else if dest.exists() {
+                Err(fl!("already-exists"))
+            } else {
+                std::fs::rename(&source_path, &dest).map_err(|e| e.to_string())
+            };
+
+            match result {
+                Ok(()) => moves.push((source_path, dest)),
+                Err(e) => failures.push(format!("{}: {}", node_name(&source_path), e)),
+            }
+        }
+
+        if moves.is_empty() {
+            editor.ignore_next_external_change = false;
+            self.show_bulk_failures(failures);
+            return Task::none();
+        }
+
+        // moved folders show up collapsed in the target folder (if it's open)
+        for (source_path, dest) in &moves {
+            self.remove_nav_node(source_path);
+            self.rename_open_paths(source_path, dest);
+        }
+        self.sync_nav_folder(&target_path);
+        self.refresh_vault_notes();
+        self.show_bulk_failures(failures);
+
+        let folder = node_name(&target_path);
+        let toast = self.handle_add_toast(CedillaToast::new(fl!(
+            "moved-items",
+            count = moves.len(),
+            folder = folder
+        )));
+
+        toast.chain(self.plan_link_rewrites(old_notes, moves))
+    }
+
+    /// Shows what went wrong in a bulk action (nothing if it all went fine)
+    pub fn show_bulk_failures(&mut self, failures: Vec<String>) {
+        if !failures.is_empty() {
+            self.dialog_pages
+                .push_back(dialogs::DialogPage::BulkFailures(failures));
+        }
+    }
+
+    /// Callback after finding the links broken by a rename/move, asks the user if they should be updated
+    pub fn handle_link_rewrites_planned(
+        &mut self,
+        rewrites: Vec<LinkRewrite>,
+    ) -> Task<cosmic::Action<Message>> {
+        if !rewrites.is_empty() {
+            self.dialog_pages
+                .push_back(dialogs::DialogPage::RewriteLinks(rewrites));
+        }
         Task::none()
     }
 
+    pub fn handle_apply_link_rewrites(
+        &mut self,
+        rewrites: Vec<LinkRewrite>,
+    ) -> Task<cosmic::Action<Message>> {
+        // open files are rewritten in their tab buffers (after the rest succeeds) so it can be undone
+        let (in_buffer, on_disk): (Vec<_>, Vec<_>) = rewrites
+            .into_iter()
+            .partition(|rewrite| self.find_tab(&rewrite.path).is_some());
+
+        let files = on_disk.len() + in_buffer.len();
+        Task::perform(
+            async move {
+                let count = links::apply_link_rewrites(on_disk).await?;
+                Ok::<_, anywho::Error>((in_buffer, count, files))
+            },
+            |res| cosmic::action::app(Message::LinkRewritesApplied(res)),
+        )
+    }
+
+    pub fn handle_link_rewrites_applied(
+        &mut self,
+        result: Result<(Vec<LinkRewrite>, usize, usize), anywho::Error>,
+    ) -> Task<cosmic::Action<Message>> {
+        let (in_buffer, mut links, mut files) = match result {
+            Ok(res) => res,
+            Err(e) => return self.handle_add_toast(CedillaToast::new(e)),
+        };
+
+        let mut tasks = Vec::new();
+        for rewrite in in_buffer {
+            let rewritten = self.find_tab(&rewrite.path).is_some_and(|tab| {
+                self.replace_tab_text(tab, &rewrite.original, &rewrite.rewritten)
+            });
+
+            if rewritten {
+                links += rewrite.count;
+            } else {
+                files -= 1;
+                tasks.push(self.handle_add_toast(CedillaToast::new(anywho::anywho!(
+                    "{} was modified before the links could be updated",
+                    rewrite.path.display()
+                ))));
+            }
+        }
+
+        tasks.push(self.handle_add_toast(CedillaToast::new(fl!(
+            "links-updated",
+            links = links,
+            files = files
+        ))));
+        Task::batch(tasks).chain(self.refresh_backlinks())
+    }
+
+    /// Looks (in the background) for the links broken after the given renames/moves (`(old_path, new_path)` pairs)
+    fn plan_link_rewrites(
+        &self,
+        old_notes: Vec<PathBuf>,
+        moves: Vec<(PathBuf, PathBuf)>,
+    ) -> Task<cosmic::Action<Message>> {
+        Task::perform(
+            links::plan_link_rewrites(
+                self.config.vault_path(),
+                old_notes,
+                self.vault_notes.clone(),
+                moves,
+                self.open_buffers(),
+            ),
+            |rewrites| cosmic::action::app(Message::LinkRewritesPlanned(rewrites)),
+        )
+    }
+
     pub fn handle_move_vault(&mut self) -> Task<cosmic::Action<Message>> {
         let old_vault_path = self.config.vault_path.clone();
 
@@ -199,6 +476,15 @@ impl AppModel {
     ) -> Task<cosmic::Action<Message>> {
         match result {
             Ok(new_path) => {
+                let old_vault_path = self.config.vault_path();
+
+                // the vault keeps its name and session in the list of vaults
+                if let Some(current) = self.config.current_vault() {
+                    let mut vaults = self.config.vaults.clone();
+                    vaults[current].path = new_path.clone();
+                    self.set_vaults(vaults);
+                }
+
                 // update the vault path in the config
                 #[allow(clippy::collapsible_if)]
                 if let Some(handler) = &self.config_handler {
@@ -218,12 +504,644 @@ impl AppModel {
                 self.core.nav_bar_set_toggled(false);
                 self.nav_model.clear();
 
-                // load vault
+                // load vault (the exclude patterns are relative to it)
+                let filter_task = self.reload_vault_filter();
                 self.open_vault_folder(self.config.vault_path());
 
-                Task::done(cosmic::action::app(Message::NewFile))
+                // the open documents of the vault moved with it
+                self.rename_open_paths(&old_vault_path, &new_path);
+
+                filter_task
             }
             Err(e) => self.handle_add_toast(CedillaToast::new(e)),
         }
     }
+
+    /// Adds the current vault to the list of vaults if it's not there yet
+    pub fn register_current_vault(&mut self) {
+        if self.config.current_vault().is_some() {
+            return;
+        }
+
+        let mut vault = Vault::new(self.config.vault_path());
+        // the default vault (and the one used before there could be more than one) keeps its name
+        if self.config.vaults.is_empty()
+            || self.config.vault_path() == CedillaConfig::default().vault_path()
+        {
+            vault.name = fl!("default-vault-name");
+        }
+        vault.last_open_file = self.config.last_open_file.clone();
+        vault.last_session = self.config.last_session.clone();
+
+        let mut vaults = self.config.vaults.clone();
+        vaults.push(vault);
+        self.set_vaults(vaults);
+    }
+
+    /// Updates (and saves) the list of vaults
+    pub fn set_vaults(&mut self, vaults: Vec<Vault>) {
+        #[allow(clippy::collapsible_if)]
+        if let Some(handler) = &self.config_handler {
+            if let Err(err) = self.config.set_vaults(handler, vaults.clone()) {
+                eprintln!("{err}");
+            }
+        }
+
+        // even if it fails we update the config (it won't get saved after restart)
+        self.config.vaults = vaults;
+    }
+
+    pub fn handle_add_vault(&mut self) -> Task<cosmic::Action<Message>> {
+        let vault_path = self.config.vault_path.clone();
+
+        Task::perform(
+            utils::files::open_folder_picker(vault_path),
+            |res| match res {
+                Some(path) => cosmic::action::app(Message::VaultFolderPicked(PathBuf::from(path))),
+                None => cosmic::action::none(),
+            },
+        )
+    }
+
+    /// Registers the picked folder as a vault (it's used where it is, nothing gets copied) and switches to it
+    pub fn handle_vault_folder_picked(&mut self, path: PathBuf) -> Task<cosmic::Action<Message>> {
+        if !path.is_dir() {
+            return self.handle_add_toast(CedillaToast::new(fl!(
+                "vault-not-found",
+                path = path.to_string_lossy()
+            )));
+        }
+
+        let index = match self.config.vaults.iter().position(|v| v.path == path) {
+            Some(index) => index,
+            None => {
+                let mut vaults = self.config.vaults.clone();
+                vaults.push(Vault::new(path));
+                self.set_vaults(vaults);
+                self.config.vaults.len() - 1
+            }
+        };
+
+        self.handle_switch_vault(index)
+    }
+
+    pub fn handle_switch_vault(&mut self, index: usize) -> Task<cosmic::Action<Message>> {
+        if self.config.current_vault() == Some(index) || index >= self.config.vaults.len() {
+            return Task::none();
+        }
+
+        // the documents of the current vault are closed, ask before losing any change
+        match self.first_unsaved_tab() {
+            Some(tab) => self.activate_tab(tab).chain(self.handle_dialog_action(
+                dialogs::DialogAction::OpenConfirmCloseFileDialog(
+                    DiscardChangesAction::SwitchVault(index),
+                ),
+            )),
+            None => self.switch_vault(index),
+        }
+    }
+
+    /// Closes every open document and opens the vault at the given index where it was left
+    pub fn switch_vault(&mut self, index: usize) -> Task<cosmic::Action<Message>> {
+        let Some(target) = self.config.vaults.get(index).cloned() else {
+            return Task::none();
+        };
+
+        if !target.path.is_dir() {
+            return self.handle_add_toast(CedillaToast::new(fl!(
+                "vault-not-found",
+                path = target.path.to_string_lossy()
+            )));
+        }
+
+        // remember the session of the vault we are leaving
+        let mut vaults = self.config.vaults.clone();
+        if let Some(current) = self.config.current_vault() {
+            vaults[current].last_open_file = self.active_path();
+            vaults[current].last_session = self.current_session();
+        }
+        self.set_vaults(vaults);
+
+        let vault_path = target.path.to_string_lossy().to_string();
+        #[allow(clippy::collapsible_if)]
+        if let Some(handler) = &self.config_handler {
+            if let Err(err) = self.config.set_vault_path(handler, vault_path.clone()) {
+                eprintln!("{err}");
+            }
+            if let Err(err) = self
+                .config
+                .set_last_open_file(handler, target.last_open_file.clone())
+            {
+                eprintln!("{err}");
+            }
+            if let Err(err) = self
+                .config
+                .set_last_session(handler, target.last_session.clone())
+            {
+                eprintln!("{err}");
+            }
+        }
+
+        // even if it fails we update the config (it won't get saved after restart)
+        self.config.vault_path = vault_path;
+        self.config.last_open_file = target.last_open_file;
+        self.config.last_session = target.last_session;
+
+        // the documents (and dialogs) of the previous vault are discarded, with their recovery files
+        utils::recovery::discard_recovery_blocking(self.recovery_written.keys());
+        self.recovery_written.clear();
+        self.state = State::Loading;
+        self.dialog_pages.clear();
+        self.selected_nav_path = None;
+        self.nav_selection.clear();
+        self.nav_selection_anchor = None;
+        self.nav_drag = None;
+
+        // load vault
+        self.trash = TrashState::default();
+        self.nav_model.clear();
+        let vault_config_task = self.load_vault_config();
+        self.open_vault_folder(self.config.vault_path());
+
+        // results of a vault search belong to the previous vault, search again in this one
+        let search_task = self.vault_search.start(self.vault_notes.clone());
+
+        self.handle_startup(None)
+            .chain(search_task)
+            .chain(vault_config_task)
+            .chain(self.refresh_git())
+            .chain(self.refresh_backlinks())
+            .chain(self.refresh_file_history())
+            .chain(self.refresh_trash())
+    }
+
+    /// Applies the files/folders added, removed or renamed by other programs to the navbar
+    pub fn handle_vault_changed(
+        &mut self,
+        changes: Vec<VaultChange>,
+    ) -> Task<cosmic::Action<Message>> {
+        // sorted so parents are synced before their children
+        let mut folders = BTreeSet::new();
+        let mut tasks = Vec::new();
+        // the navbar only follows open folders, the notes have to follow every folder
+        let mut notes_changed = false;
+
+        for change in changes {
+            match change {
+                VaultChange::Renamed(from, to) => {
+                    notes_changed |= self.vault_filter.is_note(&from)
+                        || self.vault_filter.is_note(&to)
+                        || to.is_dir();
+
+                    // renamed in place, the node keeps its position (and its children if it's an open folder)
+                    if from.parent() == to.parent()
+                        && let Some(name) = to.file_name().and_then(|n| n.to_str())
+                        && self.nav_model.iter().any(|id| {
+                            matches!(
+                                self.nav_model.data::<ProjectNode>(id),
+                                Some(ProjectNode::File { path, .. } | ProjectNode::Folder { path, .. })
+                                    if *path == from
+                            )
+                        })
+                    {
+                        self.rename_nav_node(&from, &to, name);
+                    }
+
+                    // follow the open documents renamed/moved by another program
+                    if let Some(open_path) =
+                        self.open_paths().into_iter().find(|p| p.starts_with(&from))
+                    {
+                        self.rename_open_paths(&from, &to);
+                        self.dialog_pages.retain(|d| {
+                            !matches!(d, dialogs::DialogPage::OpenFileRemoved(_, p) if p.starts_with(&from))
+                        });
+
+                        let name = open_path
+                            .file_name()
+                            .map(|n| n.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        let vault_path = self.config.vault_path();
+                        let destination = to.strip_prefix(&vault_path).unwrap_or(&to);
+                        tasks.push(self.handle_add_toast(CedillaToast::new(fl!(
+                            "open-file-moved",
+                            name = name,
+                            path = destination.to_string_lossy()
+                        ))));
+                    }
+
+                    folders.extend(from.parent().map(Path::to_path_buf));
+                    folders.extend(to.parent().map(Path::to_path_buf));
+                }
+                VaultChange::Changed(path) => {
+                    notes_changed |= self.changes_vault_notes(&path);
+                    folders.extend(path.parent().map(Path::to_path_buf));
+                }
+                VaultChange::ConfigChanged => tasks.push(self.load_vault_config()),
+            }
+        }
+
+        for folder in folders {
+            self.sync_nav_folder(&folder);
+        }
+        if notes_changed {
+            self.refresh_vault_notes();
+        }
+
+        tasks.push(self.refresh_backlinks());
+        Task::batch(tasks)
+    }
+
+    /// True if something created, removed or modified at the given path can add or remove notes of the vault
+    fn changes_vault_notes(&self, path: &Path) -> bool {
+        let known = self.vault_notes.iter().any(|note| note.starts_with(path));
+        if !path.exists() {
+            return known || self.vault_filter.is_note(path);
+        }
+
+        // editing a note that is already known changes nothing
+        path.is_dir() || (self.vault_filter.is_note(path) && !known)
+    }
+
+    pub fn handle_open_wikilink(&mut self, target: String) -> Task<cosmic::Action<Message>> {
+        let Some(path) =
+            links::resolve_wikilink(&self.config.vault_path(), &self.vault_notes, &target)
+        else {
+            return self.handle_add_toast(CedillaToast::new(fl!(
+                "note-not-found",
+                name = links::wikilink_target(&target)
+            )));
+        };
+
+        self.open_path(path)
+    }
+
+    pub fn handle_backlinks(&mut self, action: BacklinksAction) -> Task<cosmic::Action<Message>> {
+        match action {
+            BacklinksAction::Refresh => self.refresh_backlinks(),
+            BacklinksAction::Found(generation, results) => {
+                // discard results of a search that has been replaced by a newer one
+                if generation == self.backlinks.generation {
+                    self.backlinks.results = results;
+                    self.backlinks.is_searching = false;
+                }
+                Task::none()
+            }
+            BacklinksAction::Open(path, search_match) => {
+                self.handle_vault_search(VaultSearchAction::OpenResult(path, search_match))
+            }
+        }
+    }
+
+    /// Searches again the notes linking to the open file, only if the backlinks panel is shown
+    pub fn refresh_backlinks(&mut self) -> Task<cosmic::Action<Message>> {
+        if !self.core.window.show_context || self.context_page != ContextPage::Backlinks {
+            return Task::none();
+        }
+
+        let target = match &self.state {
+            State::Ready { editor, .. } => editor.path.clone(),
+            State::Loading => None,
+        };
+
+        self.backlinks
+            .refresh(self.config.vault_path(), self.vault_notes.clone(), target)
+    }
+
+    pub fn handle_file_history(
+        &mut self,
+        action: FileHistoryAction,
+    ) -> Task<cosmic::Action<Message>> {
+        match action {
+            FileHistoryAction::Refresh => self.refresh_file_history(),
+            FileHistoryAction::Loaded(generation, result) => {
+                // discard snapshots of a load that has been replaced by a newer one
+                if generation != self.file_history.generation {
+                    return Task::none();
+                }
+                self.file_history.is_loading = false;
+
+                match result {
+                    Ok(snapshots) => {
+                        self.file_history.snapshots = snapshots;
+                        self.file_history.selected = None;
+                        self.file_history.diff.clear();
+                        Task::none()
+                    }
+                    Err(e) => self.handle_add_toast(CedillaToast::new(e)),
+                }
+            }
+            FileHistoryAction::SnapshotTaken(path) => {
+                if self.file_history.target.as_ref() == Some(&path) {
+                    return self.refresh_file_history();
+                }
+                Task::none()
+            }
+            FileHistoryAction::Select(index) => {
+                if let State::Ready { editor, .. } = &self.state {
+                    self.file_history.select(index, &editor.content.text());
+                }
+                Task::none()
+            }
+            FileHistoryAction::Restore(index) => {
+                let State::Ready {
+                    editor, preview, ..
+                } = &mut self.state
+                else {
+                    return Task::none();
+                };
+
+                let Some(snapshot) = self.file_history.snapshots.get(index) else {
+                    return Task::none();
+                };
+
+                if editor.path != self.file_history.target {
+                    return Task::none();
+                }
+
+                // restoring is a regular edit, it can be undone and has to be saved
+                let cursor_before = editor.content.cursor().position;
+                editor.content.replace_text(&snapshot.content);
+                editor.restore_cursor(cursor_before.line, cursor_before.column);
+                preview.update_content(&snapshot.content);
+                editor.is_dirty = true;
+                editor.push_history((cursor_before.line, cursor_before.column));
+
+                let age = snapshot.age(history::unix_now());
+                let current = editor.content.text();
+                self.file_history.select(index, &current);
+
+                self.handle_add_toast(CedillaToast::new(fl!("snapshot-restored", age = age)))
+            }
+        }
+    }
+
+    /// Loads again the snapshots of the open file, only if the file history panel is shown
+    pub fn refresh_file_history(&mut self) -> Task<cosmic::Action<Message>> {
+        if !self.core.window.show_context || self.context_page != ContextPage::FileHistory {
+            return Task::none();
+        }
+
+        let target = match &self.state {
+            State::Ready { editor, .. } => editor.path.clone(),
+            State::Loading => None,
+        };
+
+        self.file_history.refresh(self.config.vault_path(), target)
+    }
+
+    pub fn handle_trash(&mut self, action: TrashAction) -> Task<cosmic::Action<Message>> {
+        let vault_path = self.config.vault_path();
+
+        match action {
+            TrashAction::Refresh => self.trash.refresh(vault_path),
+            TrashAction::Loaded(result) => {
+                self.trash.is_loading = false;
+
+                match result {
+                    Ok(items) => {
+                        self.trash.items = items;
+                        Task::none()
+                    }
+                    Err(e) => self.handle_add_toast(CedillaToast::new(e)),
+                }
+            }
+            TrashAction::Restore(ids) => {
+                Task::perform(trash::restore_items(vault_path, ids), |res| {
+                    cosmic::action::app(Message::Trash(TrashAction::Restored(res)))
+                })
+            }
+            TrashAction::Restored(results) => {
+                let count = results.len();
+                let (restored, failures): (Vec<_>, Vec<_>) =
+                    results.into_iter().partition(Result::is_ok);
+                let restored: Vec<PathBuf> = restored.into_iter().filter_map(Result::ok).collect();
+                let failures: Vec<String> = failures
+                    .into_iter()
+                    .filter_map(Result::err)
+                    .map(|e| e.to_string())
+                    .collect();
+
+                // the folders they were deleted from may have been restored with them, sync them from the root
+                let vault_root = std::fs::canonicalize(&vault_path).unwrap_or(vault_path);
+                let folders: BTreeSet<PathBuf> = restored
+                    .iter()
+                    .flat_map(|path| path.ancestors().skip(1))
+                    .filter(|folder| folder.starts_with(&vault_root))
+                    .map(Path::to_path_buf)
+                    .collect();
+                for folder in &folders {
+                    self.sync_nav_folder(folder);
+                }
+                self.refresh_vault_notes();
+
+                let toast = match restored.as_slice() {
+                    [] => Task::none(),
+                    [path] => self.handle_add_toast(CedillaToast::new(fl!(
+                        "restored-from-trash",
+                        name = node_name(path)
+                    ))),
+                    _ => self.handle_add_toast(CedillaToast::new(fl!(
+                        "restored-items-from-trash",
+                        count = restored.len()
+                    ))),
+                };
+
+                // a single failure is shown as any other error
+                let failures_task = if count == 1 && failures.len() == 1 {
+                    self.handle_add_toast(CedillaToast::new(&failures[0]))
+                } else {
+                    self.show_bulk_failures(failures);
+                    Task::none()
+                };
+
+                toast
+                    .chain(failures_task)
+                    .chain(self.refresh_trash())
+                    .chain(self.refresh_backlinks())
+            }
+            TrashAction::Delete(id) => {
+                Task::perform(trash::delete_permanently(vault_path, id), |res| {
+                    cosmic::action::app(Message::Trash(TrashAction::Deleted(res)))
+                })
+            }
+            TrashAction::Empty => Task::perform(trash::empty_trash(vault_path), |res| {
+                cosmic::action::app(Message::Trash(TrashAction::Deleted(res)))
+            }),
+            TrashAction::Deleted(result) => match result {
+                Ok(()) => self.refresh_trash(),
+                Err(e) => self
+                    .handle_add_toast(CedillaToast::new(e))
+                    .chain(self.refresh_trash()),
+            },
+        }
+    }
+
+    /// Loads again the items of the trash, only if the trash panel is shown
+    pub fn refresh_trash(&mut self) -> Task<cosmic::Action<Message>> {
+        if !self.core.window.show_context || self.context_page != ContextPage::Trash {
+            return Task::none();
+        }
+
+        self.trash.refresh(self.config.vault_path())
+    }
+
+    pub fn handle_vault_search(
+        &mut self,
+        action: VaultSearchAction,
+    ) -> Task<cosmic::Action<Message>> {
+        match action {
+            VaultSearchAction::UpdateSearchValue(new_value) => {
+                self.vault_search.search_value = new_value;
+                Task::none()
+            }
+            VaultSearchAction::ToggleRegex => {
+                self.vault_search.use_regex = !self.vault_search.use_regex;
+                self.refresh_vault_search()
+            }
+            VaultSearchAction::StartSearch => self.refresh_vault_search(),
+            VaultSearchAction::ResultFound(generation, result) => {
+                // discard results of a search that has been replaced by a newer one
+                if generation == self.vault_search.generation {
+                    self.vault_search.results.push(result);
+                }
+                Task::none()
+            }
+            VaultSearchAction::SearchFinished(generation) => {
+                if generation == self.vault_search.generation {
+                    self.vault_search.is_searching = false;
+                    self.vault_search.handle = None;
+                }
+                Task::none()
+            }
+            VaultSearchAction::OpenResult(path, search_match) => {
+                // switches to the tab of the file if it's already open
+                self.open_path(path.clone())
+                    .chain(Task::done(cosmic::action::app(Message::VaultSearch(
+                        VaultSearchAction::RevealMatch(path, search_match),
+                    ))))
+            }
+            VaultSearchAction::RevealMatch(path, search_match) => {
+                let State::Ready { editor, .. } = &mut self.state else {
+                    return Task::none();
+                };
+
+                if editor.path.as_ref() != Some(&path) {
+                    return Task::none();
+                }
+
+                editor.navigate_to_match(&search_match);
+
+                // freshly opened file, we will scroll once the editor viewport is known
+                if editor.scroll.last_editor_viewport.is_none() {
+                    editor.scroll.reveal_cursor = true;
+                    return Task::none();
+                }
+
+                let sync_preview = self.config.scrollbar_sync == BoolState::Yes;
+                super::editor::ensure_cursor_visible(editor, sync_preview)
+            }
+            VaultSearchAction::ToggleReplaceMode => {
+                self.vault_search.replace_mode = !self.vault_search.replace_mode;
+                self.refresh_vault_search()
+            }
+            VaultSearchAction::UpdateReplaceValue(new_value) => {
+                self.vault_search.replace_value = new_value;
+                Task::none()
+            }
+            VaultSearchAction::PreviewReplace => self.refresh_vault_search(),
+            VaultSearchAction::ReplacePreviewReady(generation, previews) => {
+                if generation == self.vault_search.generation {
+                    self.vault_search.replace_previews = previews;
+                    self.vault_search.is_searching = false;
+                    self.vault_search.handle = None;
+                }
+                Task::none()
+            }
+            VaultSearchAction::ToggleReplaceFile(index) => {
+                if let Some(preview) = self.vault_search.replace_previews.get_mut(index) {
+                    preview.checked = !preview.checked;
+                }
+                Task::none()
+            }
+            VaultSearchAction::ApplyReplace => {
+                let checked: Vec<VaultReplacePreview> = self
+                    .vault_search
+                    .replace_previews
+                    .drain(..)
+                    .filter(|p| p.checked)
+                    .collect();
+
+                if checked.is_empty() {
+                    return Task::none();
+                }
+
+                let mut results = Vec::new();
+                let mut changes = Vec::new();
+
+                for p in checked {
+                    // open files are replaced in their tab buffers so the change can be undone
+                    let Some(tab) = self.find_tab(&p.path) else {
+                        changes.push((p.path, p.original, p.replaced));
+                        continue;
+                    };
+
+                    if self.replace_tab_text(tab, &p.original, &p.replaced) {
+                        results.push(Ok(p.path));
+                    } else {
+                        results.push(Err(anywho::anywho!(
+                            "{} was modified after the preview, skipped",
+                            p.path.display()
+                        )));
+                    }
+                }
+
+                Task::perform(
+                    async move {
+                        results.extend(search::apply_vault_replace(changes).await);
+                        results
+                    },
+                    |results| {
+                        cosmic::action::app(Message::VaultSearch(
+                            VaultSearchAction::ReplaceApplied(results),
+                        ))
+                    },
+                )
+            }
+            VaultSearchAction::ReplaceApplied(results) => {
+                let (replaced, failed): (Vec<_>, Vec<_>) =
+                    results.into_iter().partition(Result::is_ok);
+
+                let mut tasks = vec![self.handle_add_toast(CedillaToast::new(fl!(
+                    "replaced-in-files",
+                    files = replaced.len()
+                )))];
+                for err in failed.into_iter().filter_map(Result::err) {
+                    tasks.push(self.handle_add_toast(CedillaToast::new(err)));
+                }
+
+                // unchecked files (and anything modified meanwhile) show up again
+                tasks.push(self.refresh_vault_search());
+                Task::batch(tasks)
+            }
+        }
+    }
+
+    /// Restarts the vault search (or the replacement preview when in replace mode)
+    fn refresh_vault_search(&mut self) -> Task<cosmic::Action<Message>> {
+        let notes = self.vault_notes.clone();
+
+        if self.vault_search.replace_mode {
+            let open_buffers = self.open_buffers();
+            self.vault_search.preview_replace(notes, open_buffers)
+        } else {
+            self.vault_search.start(notes)
+        }
+    }
+}
+
+/// Name of the given file/folder as shown in the navbar
+fn node_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
 }
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0
 
-use std::{fmt::Display, path::PathBuf, sync::LazyLock};
+use std::{fmt::Display, ops::RangeInclusive, path::PathBuf, sync::LazyLock};
 
 use cosmic::{
     Application,
@@ -13,18 +13,39 @@ use crate::fl;
 
 pub const CONFIG_VERSION: u64 = 1;
 
-#[derive(Debug, Clone, CosmicConfigEntry, Eq, PartialEq)]
+#[derive(Debug, Clone, CosmicConfigEntry, PartialEq)]
 #[version = 1]
 pub struct CedillaConfig {
     pub app_theme: AppTheme,
+    /// Path of the current vault
     pub vault_path: String,
+    /// Every vault the user can switch to (the current one included)
+    pub vaults: Vec<Vault>,
     pub show_helper_header_bar: ShowState,
     pub show_status_bar: ShowState,
     pub last_navbar_showstate: ShowState,
     pub last_preview_showstate: ShowState,
     pub open_last_file: BoolState,
     pub last_open_file: Option<PathBuf>,
+    pub last_session: Option<Session>,
     pub scrollbar_sync: BoolState,
+    pub autosave: AutosaveMode,
+    /// Seconds without editing before saving when using [`AutosaveMode::Idle`]
+    pub autosave_delay: u64,
+    /// Keep the undo history of the files between runs
+    pub persistent_undo: BoolState,
+    /// Days a persisted undo history is kept
+    pub undo_max_days: u64,
+    /// Max size (in KB) of the persisted undo history of a single file
+    pub undo_max_kb: u64,
+    /// Extensions of the files treated as notes
+    pub note_extensions: Vec<String>,
+    /// Glob patterns (gitignore syntax) of the files and folders left out of the vault
+    pub exclude_patterns: Vec<String>,
+    /// Leave out of the vault what its `.gitignore` files ignore
+    pub respect_gitignore: BoolState,
+    /// Show the files that are not notes in the navbar (they are opened with the default app)
+    pub show_attachments: BoolState,
     pub gotenberg_url: String,
     pub text_size: i32,
     pub light_highlighter_theme: CedillaHighlighterTheme,
@@ -46,13 +67,24 @@ impl Default for CedillaConfig {
         Self {
             app_theme: AppTheme::default(),
             vault_path: vault_path.to_string_lossy().to_string(),
+            vaults: Vec::new(),
             show_helper_header_bar: ShowState::default(),
             show_status_bar: ShowState::default(),
             last_navbar_showstate: ShowState::default(),
             last_preview_showstate: ShowState::default(),
             open_last_file: BoolState::default(),
             last_open_file: None,
+            last_session: None,
             scrollbar_sync: BoolState::default(),
+            autosave: AutosaveMode::default(),
+            autosave_delay: 5,
+            persistent_undo: BoolState::No,
+            undo_max_days: 30,
+            undo_max_kb: 1024,
+            note_extensions: ["md", "markdown", "mdx", "txt"].map(String::from).to_vec(),
+            exclude_patterns: [".git", ".obsidian"].map(String::from).to_vec(),
+            respect_gitignore: BoolState::Yes,
+            show_attachments: BoolState::No,
             gotenberg_url: String::new(),
             text_size: 16,
             light_highlighter_theme: CedillaHighlighterTheme::from(
@@ -88,6 +120,19 @@ impl CedillaConfig {
         PathBuf::from(&self.vault_path)
     }
 
+    /// Returns the index of the current vault in the list of vaults
+    pub fn current_vault(&self) -> Option<usize> {
+        let vault_path = self.vault_path();
+        self.vaults.iter().position(|v| v.path == vault_path)
+    }
+
+    /// Returns the display name of the current vault
+    pub fn vault_name(&self) -> String {
+        self.current_vault()
+            .map(|index| self.vaults[index].name.clone())
+            .unwrap_or_else(|| fl!("default-vault-name"))
+    }
+
     /// Returns true if the Gotenberg URL is not empty
     pub fn is_gotenberg_configured(&self) -> bool {
         !self.gotenberg_url.trim().is_empty()
@@ -150,6 +195,185 @@ impl ShowState {
     }
 }
 
+/// Working session of the app (open tabs, cursors, scroll...), saved on exit and restored on startup
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// Files open in tabs, in tab order
+    pub files: Vec<SessionFile>,
+    /// File of the active tab
+    pub active: Option<PathBuf>,
+    /// Ratio of the split between the editor and preview panes
+    pub split_ratio: Option<f32>,
+}
+
+/// A folder registered as a vault, it remembers the files that were open the last time it was used
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Vault {
+    /// Name shown in the vault switcher
+    pub name: String,
+    pub path: PathBuf,
+    pub last_open_file: Option<PathBuf>,
+    pub last_session: Option<Session>,
+}
+
+impl Vault {
+    /// New vault for the given folder, named after it
+    pub fn new(path: PathBuf) -> Self {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| fl!("default-vault-name"));
+
+        Self {
+            name,
+            path,
+            last_open_file: None,
+            last_session: None,
+        }
+    }
+}
+
+// the vault switcher shows the vault names
+impl AsRef<str> for Vault {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Settings stored inside of a vault (in `.cedilla/config`, as TOML or JSON) so they travel with it,
+/// while the vault is open they override the ones of the app
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VaultConfig {
+    pub autosave: Option<AutosaveMode>,
+    pub autosave_delay: Option<u64>,
+    pub persistent_undo: Option<bool>,
+    pub undo_max_days: Option<u64>,
+    pub undo_max_kb: Option<u64>,
+    pub note_extensions: Option<Vec<String>>,
+    pub exclude_patterns: Option<Vec<String>>,
+    pub respect_gitignore: Option<bool>,
+    pub show_attachments: Option<bool>,
+    /// Folder with the note templates (relative to the vault), only set per vault
+    pub templates_folder: Option<PathBuf>,
+    /// Template new notes are created from unless another one is picked (relative to the templates folder),
+    /// only set per vault
+    pub default_template: Option<PathBuf>,
+    /// How the links to notes copied from the navbar are written, only set per vault
+    pub link_style: Option<LinkStyle>,
+}
+
+impl VaultConfig {
+    /// Parses the content of a vault config file, settings with invalid values are left out (with an error for each one)
+    pub fn parse(content: &str, is_json: bool) -> Result<(Self, Vec<String>), String> {
+        let mut config: Self = if is_json {
+            serde_json::from_str(content).map_err(|e| e.to_string())?
+        } else {
+            toml::from_str(content).map_err(|e| e.to_string())?
+        };
+
+        // same limits as the settings page
+        let mut errors = Vec::new();
+        validate_range(
+            &mut config.autosave_delay,
+            "autosave_delay",
+            1..=60,
+            &mut errors,
+        );
+        validate_range(
+            &mut config.undo_max_days,
+            "undo_max_days",
+            1..=365,
+            &mut errors,
+        );
+        validate_range(
+            &mut config.undo_max_kb,
+            "undo_max_kb",
+            64..=8192,
+            &mut errors,
+        );
+
+        Ok((config, errors))
+    }
+
+    /// Sets the settings of the vault on the given config, returns the values they replaced
+    pub fn apply(&self, config: &mut CedillaConfig) -> VaultConfig {
+        let mut replaced = VaultConfig::default();
+
+        if let Some(autosave) = self.autosave {
+            replaced.autosave = Some(config.autosave);
+            config.autosave = autosave;
+        }
+        if let Some(delay) = self.autosave_delay {
+            replaced.autosave_delay = Some(config.autosave_delay);
+            config.autosave_delay = delay;
+        }
+        if let Some(persistent_undo) = self.persistent_undo {
+            replaced.persistent_undo = Some(config.persistent_undo == BoolState::Yes);
+            config.persistent_undo = BoolState::from(persistent_undo);
+        }
+        if let Some(days) = self.undo_max_days {
+            replaced.undo_max_days = Some(config.undo_max_days);
+            config.undo_max_days = days;
+        }
+        if let Some(kb) = self.undo_max_kb {
+            replaced.undo_max_kb = Some(config.undo_max_kb);
+            config.undo_max_kb = kb;
+        }
+        if let Some(extensions) = &self.note_extensions {
+            replaced.note_extensions = Some(config.note_extensions.clone());
+            config.note_extensions = extensions.clone();
+        }
+        if let Some(patterns) = &self.exclude_patterns {
+            replaced.exclude_patterns = Some(config.exclude_patterns.clone());
+            config.exclude_patterns = patterns.clone();
+        }
+        if let Some(respect_gitignore) = self.respect_gitignore {
+            replaced.respect_gitignore = Some(config.respect_gitignore == BoolState::Yes);
+            config.respect_gitignore = BoolState::from(respect_gitignore);
+        }
+        if let Some(show_attachments) = self.show_attachments {
+            replaced.show_attachments = Some(config.show_attachments == BoolState::Yes);
+            config.show_attachments = BoolState::from(show_attachments);
+        }
+
+        replaced
+    }
+}
+
+/// Leaves out the given setting if it's out of range
+fn validate_range(
+    value: &mut Option<u64>,
+    key: &str,
+    range: RangeInclusive<u64>,
+    errors: &mut Vec<String>,
+) {
+    if let Some(v) = *value
+        && !range.contains(&v)
+    {
+        errors.push(fl!(
+            "vault-config-out-of-range",
+            key = key,
+            value = v,
+            min = range.start(),
+            max = range.end()
+        ));
+        *value = None;
+    }
+}
+
+/// State of a file open in a tab of a [`Session`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SessionFile {
+    pub path: PathBuf,
+    /// Cursor position (line, column)
+    pub cursor: (usize, usize),
+    /// Scroll offset of the editor
+    pub editor_scroll: f32,
+    /// Scroll offset of the preview
+    pub preview_scroll: f32,
+}
+
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub enum BoolState {
     #[default]
@@ -166,6 +390,15 @@ impl Display for BoolState {
     }
 }
 
+impl From<bool> for BoolState {
+    fn from(value: bool) -> Self {
+        match value {
+            true => BoolState::Yes,
+            false => BoolState::No,
+        }
+    }
+}
+
 impl BoolState {
     pub fn all_labels() -> &'static [String] {
         static LABELS: LazyLock<Vec<String>> = LazyLock::new(|| vec![fl!("yes"), fl!("no")]);
@@ -188,6 +421,73 @@ impl BoolState {
     }
 }
 
+/// How links to notes are written
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LinkStyle {
+    /// `[Note](relative/path/Note.md)`
+    #[default]
+    Markdown,
+    /// `[[Note]]`
+    Wikilink,
+}
+
+/// When the vault files with unsaved changes get saved automatically
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum AutosaveMode {
+    #[default]
+    Off,
+    /// After some seconds without editing
+    Idle,
+    /// When the window loses focus
+    FocusLost,
+    /// When switching to another file
+    SwitchFile,
+}
+
+impl Display for AutosaveMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AutosaveMode::Off => write!(f, "{}", fl!("autosave-off")),
+            AutosaveMode::Idle => write!(f, "{}", fl!("autosave-idle")),
+            AutosaveMode::FocusLost => write!(f, "{}", fl!("autosave-focus-lost")),
+            AutosaveMode::SwitchFile => write!(f, "{}", fl!("autosave-switch-file")),
+        }
+    }
+}
+
+impl AutosaveMode {
+    pub fn all_labels() -> &'static [String] {
+        static LABELS: LazyLock<Vec<String>> = LazyLock::new(|| {
+            vec![
+                fl!("autosave-off"),
+                fl!("autosave-idle"),
+                fl!("autosave-focus-lost"),
+                fl!("autosave-switch-file"),
+            ]
+        });
+        &LABELS
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            0 => AutosaveMode::Off,
+            1 => AutosaveMode::Idle,
+            2 => AutosaveMode::FocusLost,
+            3 => AutosaveMode::SwitchFile,
+            _ => AutosaveMode::default(),
+        }
+    }
+
+    pub fn to_index(self) -> usize {
+        match self {
+            AutosaveMode::Off => 0,
+            AutosaveMode::Idle => 1,
+            AutosaveMode::FocusLost => 2,
+            AutosaveMode::SwitchFile => 3,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct CedillaHighlighterTheme(pub cosmic::iced::highlighter::Theme);
 
@@ -241,6 +541,16 @@ pub enum ConfigInput {
     OpenLastFile(BoolState),
     /// Update if the user wants the editor and preview scrollbars to be in sync
     ScrollbarSync(BoolState),
+    /// Update when the vault files get saved automatically
+    Autosave(AutosaveMode),
+    /// Update the seconds without editing before autosaving
+    UpdateAutosaveDelay(u16),
+    /// Update if the undo history is kept between runs
+    PersistentUndo(BoolState),
+    /// Update the days a persisted undo history is kept
+    UpdateUndoMaxDays(u16),
+    /// Update the max size (in KB) of a persisted undo history
+    UpdateUndoMaxKb(u16),
     /// Update the current gotenberg client url
     GotenbergUrlInput(String),
     /// Save the new gotenberg url
@@ -255,4 +565,18 @@ pub enum ConfigInput {
     UpdateFont(usize),
     /// Reset to the default font
     ResetFont,
+    /// Update the display name of the vault at the given index
+    RenameVault(usize, String),
+    /// Remove the vault at the given index from the list of vaults (its folder is kept)
+    RemoveVault(usize),
+    /// Update the (comma separated) extensions of the files treated as notes
+    NoteExtensionsInput(String),
+    /// Update the (comma separated) exclude patterns of the vault
+    ExcludePatternsInput(String),
+    /// Save the note extensions and exclude patterns and reload the vault tree
+    VaultFilterSave,
+    /// Update if the `.gitignore` files of the vault are honored
+    RespectGitignore(BoolState),
+    /// Update if the files that are not notes are shown in the navbar
+    ShowAttachments(BoolState),
 }
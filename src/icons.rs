@@ -42,6 +42,9 @@ impl IconCache {
         bundle!("markdown-symbolic", 18);
         bundle!("dialog-information-symbolic", 18);
         bundle!("regex-symbolic", 18);
+        bundle!("case-sensitive-symbolic", 18);
+        bundle!("whole-word-symbolic", 18);
+        bundle!("text-select-symbolic", 18);
 
         bundle!("helperbar/bold-symbolic", 18);
         bundle!("helperbar/bulleted-list-symbolic", 18);
@@ -3,7 +3,7 @@
 use std::collections::HashMap;
 
 use cosmic::iced::keyboard::Key;
-use cosmic::iced::keyboard::key::Named::Escape;
+use cosmic::iced::keyboard::key::Named::{Escape, PageDown, PageUp};
 use cosmic::widget::menu::key_bind::KeyBind;
 use cosmic::widget::menu::key_bind::Modifier;
 
@@ -30,10 +30,14 @@ pub fn key_binds() -> HashMap<KeyBind, MenuAction> {
     bind!([Ctrl, Shift], Key::Character("t".into()), NewFile);
     bind!([Ctrl], Key::Character("s".into()), SaveFile);
     bind!([Ctrl], Key::Character("o".into()), OpenFile);
+    bind!([Ctrl], Key::Character("w".into()), CloseTab);
+    bind!([Ctrl], Key::Named(PageDown), NextTab);
+    bind!([Ctrl], Key::Named(PageUp), PreviousTab);
 
     bind!([Ctrl], Key::Character("z".into()), Undo);
     bind!([Ctrl, Shift], Key::Character("z".into()), Redo);
     bind!([Ctrl], Key::Character("f".into()), Search);
+    bind!([Ctrl, Shift], Key::Character("f".into()), FindInVault);
 
     bind!([Ctrl], Key::Character(",".into()), Settings);
     bind!([Ctrl], Key::Character("i".into()), About);
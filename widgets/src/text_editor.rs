@@ -43,7 +43,7 @@ use cosmic::Theme;
 use cosmic::iced::core::clipboard::{self, Clipboard};
 use cosmic::iced::core::layout::{self, Layout};
 use cosmic::iced::core::renderer;
-use cosmic::iced::core::text::editor::{Cursor, Editor as _};
+use cosmic::iced::core::text::editor::{Cursor, Editor as _, Position};
 use cosmic::iced::core::text::highlighter::{self, Highlighter};
 use cosmic::iced::core::text::{self, Ellipsize, LineHeight, Text, Wrapping};
 use cosmic::iced::core::widget::operation::focusable;
@@ -140,6 +140,7 @@ where
     on_edit: Option<Box<dyn Fn(Action) -> Message + 'a>>,
     highlighter_settings: Highlighter::Settings,
     highlighter_format: fn(&Highlighter::Highlight, &Theme) -> highlighter::Format<Renderer::Font>,
+    line_changes: &'a [(usize, LineChange)],
     last_status: Option<Status>,
 }
 
@@ -171,6 +172,7 @@ where
             on_edit: None,
             highlighter_settings: (),
             highlighter_format: |_highlight, _theme| highlighter::Format::default(),
+            line_changes: &[],
             last_status: None,
         }
     }
@@ -325,10 +327,19 @@ where
             on_edit: self.on_edit,
             highlighter_settings: settings,
             highlighter_format: to_format,
+            line_changes: self.line_changes,
             last_status: self.last_status,
         }
     }
 
+    /// Sets the lines marked as changed in the gutter of the [`TextEditor`].
+    ///
+    /// The markers are drawn inside of the left [`Padding`], which should leave room for them.
+    pub fn line_changes(mut self, line_changes: &'a [(usize, LineChange)]) -> Self {
+        self.line_changes = line_changes;
+        self
+    }
+
     /// Sets the closure to produce key bindings on key presses.
     ///
     /// See [`Binding`] for the list of available bindings.
@@ -1015,6 +1026,25 @@ where
         );
 
         let text_bounds = bounds.shrink(self.padding);
+        let translation = text_bounds.position() - Point::ORIGIN;
+
+        // the markers are measured before the editor is handed to the renderer
+        let markers = if self.line_changes.is_empty() {
+            Vec::new()
+        } else {
+            let line_height = self
+                .line_height
+                .to_absolute(self.text_size.unwrap_or_else(|| renderer.default_size()))
+                .into();
+
+            line_change_markers(
+                &mut internal.editor,
+                self.line_changes,
+                line_height,
+                bounds.x,
+                translation.y,
+            )
+        };
 
         if internal.editor.is_empty() {
             if let Some(placeholder) = self.placeholder.clone() {
@@ -1045,7 +1075,21 @@ where
             );
         }
 
-        let translation = text_bounds.position() - Point::ORIGIN;
+        for (marker, change) in markers {
+            if let Some(marker) = bounds.intersection(&marker) {
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: marker,
+                        ..renderer::Quad::default()
+                    },
+                    match change {
+                        LineChange::Added => style.added,
+                        LineChange::Modified => style.modified,
+                        LineChange::Removed => style.removed,
+                    },
+                );
+            }
+        }
 
         match internal.editor.selection() {
             Selection::Caret(position) => {
@@ -1147,6 +1191,90 @@ where
     }
 }
 
+/// Width of the gutter markers of the changed lines
+const LINE_CHANGE_MARKER_WIDTH: f32 = 3.0;
+
+/// Kind of change of a line marked in the gutter of a [`TextEditor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChange {
+    /// The line has been added.
+    Added,
+    /// The line has been modified.
+    Modified,
+    /// One or more lines have been removed right before this line.
+    Removed,
+}
+
+/// Computes the bounds of the gutter markers of the given line changes.
+///
+/// Wrapped lines span several visual lines, so the position of each line is found by moving the
+/// caret to its start and end; the original cursor is restored afterwards.
+fn line_change_markers<E: text::Editor>(
+    editor: &mut E,
+    line_changes: &[(usize, LineChange)],
+    line_height: f32,
+    x: f32,
+    translation_y: f32,
+) -> Vec<(Rectangle, LineChange)> {
+    let cursor = editor.cursor();
+    let line_count = editor.line_count();
+    let mut markers = Vec::with_capacity(line_changes.len());
+
+    for &(line, change) in line_changes {
+        if line_count == 0 {
+            break;
+        }
+
+        // lines removed at the end of the text are marked below the last line
+        let is_past_end = line >= line_count;
+        let line = line.min(line_count - 1);
+
+        let columns = editor
+            .line(line)
+            .map(|l| l.text.chars().count())
+            .unwrap_or(0);
+        let (Some(top), Some(bottom)) = (caret_y(editor, line, 0), caret_y(editor, line, columns))
+        else {
+            continue;
+        };
+
+        let bounds = match change {
+            LineChange::Added | LineChange::Modified => Rectangle::new(
+                Point::new(x, top + translation_y),
+                Size::new(LINE_CHANGE_MARKER_WIDTH, bottom - top + line_height),
+            ),
+            LineChange::Removed => {
+                let y = if is_past_end {
+                    bottom + line_height
+                } else {
+                    top
+                };
+                Rectangle::new(
+                    Point::new(x, y + translation_y - LINE_CHANGE_MARKER_WIDTH / 2.0),
+                    Size::new(LINE_CHANGE_MARKER_WIDTH * 2.0, LINE_CHANGE_MARKER_WIDTH),
+                )
+            }
+        };
+        markers.push((bounds, change));
+    }
+
+    editor.move_to(cursor);
+    markers
+}
+
+/// Vertical position of the caret when placed at the given line and column.
+fn caret_y<E: text::Editor>(editor: &mut E, line: usize, column: usize) -> Option<f32> {
+    editor.move_to(Cursor {
+        position: Position { line, column },
+        selection: None,
+    });
+
+    match editor.selection() {
+        Selection::Caret(position) => Some(position.y),
+        Selection::Range(_) => None,
+    }
+}
+
 /// A binding to an action in the [`TextEditor`].
 #[derive(Debug, Clone, PartialEq)]
 pub enum Binding<Message> {
@@ -1461,6 +1589,12 @@ pub struct Style {
     pub value: Color,
     /// The [`Color`] of the selection of the text input.
     pub selection: Color,
+    /// The [`Color`] of the gutter markers of added lines.
+    pub added: Color,
+    /// The [`Color`] of the gutter markers of modified lines.
+    pub modified: Color,
+    /// The [`Color`] of the gutter markers of removed lines.
+    pub removed: Color,
 }
 
 /// The theme catalog of a [`TextEditor`].
@@ -1505,6 +1639,9 @@ pub fn default(theme: &Theme, _status: Status, is_code_block: bool) -> Style {
         placeholder: palette.on_bg_color().into(),
         value: palette.on_bg_color().into(),
         selection: palette.accent.base.into(),
+        added: palette.success_color().into(),
+        modified: palette.warning_color().into(),
+        removed: palette.destructive_color().into(),
     };
 
     if is_code_block {